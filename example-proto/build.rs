@@ -1,18 +1,29 @@
+use prost_reflect::DescriptorPool;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let files = &["message.proto", "service.proto"];
     let includes = &["proto", "../prost-validate-types/proto"];
 
-    let mut config = {
-        let mut c = prost_build::Config::new();
-        c.service_generator(tonic_prost_build::configure().service_generator());
-        c
-    };
-
-    prost_validate_build::Builder::new().configure(&mut config, files, includes)?;
-
-    prost_reflect_build::Builder::new()
+    prost_validate_build::tonic::Builder::new()
         .descriptor_pool("DESCRIPTOR_POOL")
-        .compile_protos_with_config(config, files, includes)?;
+        .validate_responses(true)
+        .compile_protos(files, includes)?;
+
+    // the plain prost code, with the implementations generated by the protoc plugin
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let plugin_dir = out_dir.join("plugin");
+    fs::create_dir_all(&plugin_dir)?;
+    prost_build::Config::new()
+        .out_dir(&plugin_dir)
+        .compile_protos(files, includes)?;
+    let pool =
+        DescriptorPool::decode(fs::read(out_dir.join("file_descriptor_set.bin"))?.as_slice())?;
+    let files = files.map(String::from);
+    for file in protoc_gen_prost_validate::generate(&pool, &files, "", &mut io::stderr())? {
+        fs::write(plugin_dir.join(file.name()), file.content())?;
+    }
 
     Ok(())
 }
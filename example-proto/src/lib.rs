@@ -8,3 +8,174 @@ static DESCRIPTOR_POOL: Lazy<DescriptorPool> = Lazy::new(|| {
     .unwrap()
 });
 include!(concat!(env!("OUT_DIR"), "/validate.example.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::example_service_server::{ExampleService, ExampleServiceServer};
+    use super::validated_example_service_server::ValidatedExampleServiceServer;
+    use super::*;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use prost::Message;
+    use tonic::codegen::Service;
+    use tonic_types::StatusExt;
+
+    const METHOD: &str = "/validate.example.ExampleService/ExampleMethod";
+    const STREAM: &str = "/validate.example.ExampleService/ExampleStream";
+
+    /// An `ExampleService` echoing the request, or the last message of the stream.
+    struct EchoService;
+
+    #[tonic::async_trait]
+    impl ExampleService for EchoService {
+        async fn example_method(
+            &self,
+            req: tonic::Request<ExampleMessage>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            Ok(tonic::Response::new(req.into_inner()))
+        }
+
+        async fn example_stream(
+            &self,
+            req: tonic::Request<tonic::Streaming<ExampleMessage>>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            let mut stream = req.into_inner();
+            let mut last = ExampleMessage::default();
+            while let Some(msg) = stream.message().await? {
+                last = msg;
+            }
+            Ok(tonic::Response::new(last))
+        }
+    }
+
+    /// An `ExampleService` replying the same message to every request.
+    struct Reply(&'static str);
+
+    #[tonic::async_trait]
+    impl ExampleService for Reply {
+        async fn example_method(
+            &self,
+            _: tonic::Request<ExampleMessage>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            Ok(tonic::Response::new(ExampleMessage {
+                content: self.0.to_owned(),
+            }))
+        }
+
+        async fn example_stream(
+            &self,
+            _: tonic::Request<tonic::Streaming<ExampleMessage>>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            Err(tonic::Status::unimplemented("not implemented"))
+        }
+    }
+
+    /// Returns the gRPC request of the framed messages.
+    #[allow(clippy::unwrap_used)]
+    fn request(path: &str, contents: &[&str]) -> http::Request<Full<Bytes>> {
+        let mut buf = vec![];
+        for content in contents {
+            let msg = ExampleMessage {
+                content: (*content).to_owned(),
+            }
+            .encode_to_vec();
+            buf.push(0);
+            buf.extend((msg.len() as u32).to_be_bytes());
+            buf.extend(msg);
+        }
+        http::Request::builder()
+            .method("POST")
+            .uri(path)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(Full::new(Bytes::from(buf)))
+            .unwrap()
+    }
+
+    /// Returns the status of a response, either trailers-only or in the trailers of its body.
+    #[allow(clippy::unwrap_used)]
+    async fn status(res: http::Response<tonic::body::Body>) -> tonic::Status {
+        if let Some(status) = tonic::Status::from_header_map(res.headers()) {
+            return status;
+        }
+        let body = res.into_body().collect().await.unwrap();
+        tonic::Status::from_header_map(body.trailers().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validated_server() {
+        let mut svc = ValidatedExampleServiceServer::new(EchoService);
+
+        let res = svc.call(request(METHOD, &["Hello, world!"])).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+
+        let res = svc.call(request(METHOD, &["Hi"])).await.unwrap();
+        let err = status(res).await;
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let details = err.get_error_details();
+        let f = &details.bad_request().unwrap().field_violations;
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].field, "content");
+
+        // each message of the streaming requests is validated
+        let res = svc
+            .call(request(STREAM, &["Hello, world!", "Hello, world!"]))
+            .await
+            .unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+
+        let res = svc
+            .call(request(STREAM, &["Hello, world!", "Hi"]))
+            .await
+            .unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::InvalidArgument);
+
+        // the original server does not validate the requests
+        let mut svc = ExampleServiceServer::new(EchoService);
+        let res = svc.call(request(METHOD, &["Hi"])).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validated_server_responses() {
+        let mut svc = ValidatedExampleServiceServer::new(Reply("Hello, world!"));
+        let res = svc.call(request(METHOD, &["Hello, world!"])).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+
+        let mut svc = ValidatedExampleServiceServer::new(Reply("Hi"));
+        let res = svc.call(request(METHOD, &["Hello, world!"])).await.unwrap();
+        let err = status(res).await;
+        assert_eq!(err.code(), tonic::Code::Internal);
+        assert!(err.message().contains("invalid response"), "{}", err);
+
+        // the original server does not validate the responses
+        let mut svc = ExampleServiceServer::new(Reply("Hi"));
+        let res = svc.call(request(METHOD, &["Hello, world!"])).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+    }
+}
+
+/// The plain prost code, with the implementations generated by `protoc-gen-prost-validate`.
+#[cfg(test)]
+mod plugin {
+    include!(concat!(env!("OUT_DIR"), "/plugin/validate.example.rs"));
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/plugin/validate.example.validate.rs"
+    ));
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_plugin_impls() {
+        use prost_validate::Validator;
+
+        let msg = ExampleMessage {
+            content: "Hello, world!".to_string(),
+        };
+        assert!(msg.validate().is_ok());
+        let err = ExampleMessage::default().validate().unwrap_err();
+        assert_eq!(err.field, "validate.example.ExampleMessage.content");
+    }
+}
@@ -1,32 +1,24 @@
-use crate::registry::FieldValidationFn;
-use prost_reflect::FieldDescriptor;
+use prost_reflect::Value;
 use prost_validate::errors::r#bool;
-use prost_validate::Error;
-use prost_validate_types::field_rules::Type;
-use prost_validate_types::FieldRules;
-use std::sync::Arc;
+use prost_validate::{Error, Result};
+use prost_validate_types::BoolRules;
 
-pub(crate) fn make_validate_bool(
-    field: &FieldDescriptor,
-    rules: &FieldRules,
-) -> Vec<FieldValidationFn<bool>> {
-    let mut fns: Vec<FieldValidationFn<bool>> = Vec::new();
-    if !matches!(rules.r#type, Some(Type::Bool(_))) {
-        return fns;
-    }
-    let rules = match rules.r#type {
-        Some(Type::Bool(rules)) => rules,
-        _ => return fns,
-    };
-    if let Some(v) = rules.r#const {
-        let name = field.full_name().to_string();
-        fns.push(Arc::new(move |val, _| {
-            let val = val.unwrap_or(false);
-            if val != v {
-                return Err(Error::new(name.clone(), r#bool::Error::Const(v)));
-            }
-            Ok(true)
-        }))
+/// A compiled rule of a bool value.
+#[derive(Debug, Clone)]
+pub(crate) enum BoolRule {
+    Const(bool),
+}
+
+pub(crate) fn compile_bool(rules: BoolRules) -> Vec<BoolRule> {
+    rules.r#const.map(BoolRule::Const).into_iter().collect()
+}
+
+impl BoolRule {
+    pub(crate) fn check(&self, name: &str, val: Option<&Value>) -> Result<bool> {
+        let val = val.and_then(Value::as_bool).unwrap_or(false);
+        match self {
+            Self::Const(v) if val != *v => Err(Error::new(name, r#bool::Error::Const(*v))),
+            _ => Ok(true),
+        }
     }
-    fns
 }
@@ -1,256 +1,106 @@
-use crate::registry::FieldValidationFn;
-use prost::bytes::Bytes;
-use prost_reflect::FieldDescriptor;
 use prost_validate::errors::bytes;
-use prost_validate::{format_err, Error};
+use prost_validate::{format_err, Error, Result};
 use prost_validate_types::bytes_rules::WellKnown;
-use prost_validate_types::field_rules::Type;
-use prost_validate_types::{BytesRules, FieldRules};
+use prost_validate_types::BytesRules;
 use regex::bytes::Regex;
-use std::ops::Deref;
-use std::sync::Arc;
 
-fn push<F>(fns: &mut Vec<FieldValidationFn<Arc<Bytes>>>, name: &Arc<String>, f: Arc<F>)
-where
-    F: Fn(&Bytes, &BytesRules, &String) -> prost_validate::Result<bool> + Send + Sync + 'static,
-{
-    let name = name.clone();
-    fns.push(Arc::new(move |val, rules| {
-        let default = Arc::new(Bytes::default());
-        let val = val.unwrap_or(default);
-        let rules = match &rules.r#type {
-            Some(Type::Bytes(rules)) => rules,
-            _ => return Err(format_err!(name, "unexpected string rules")),
-        };
-        f(&val, rules, &name)
-    }))
+/// A compiled rule of a bytes value.
+#[derive(Debug, Clone)]
+pub(crate) enum BytesRule {
+    IgnoreEmpty,
+    Const(Vec<u8>),
+    Len(usize),
+    MinLen(usize),
+    MaxLen(usize),
+    /// The pattern and its regex, compiled once, if it is valid.
+    Pattern(String, Option<Regex>),
+    Prefix(Vec<u8>),
+    Suffix(Vec<u8>),
+    Contains(Vec<u8>),
+    In(Vec<Vec<u8>>),
+    NotIn(Vec<Vec<u8>>),
+    Ip,
+    Ipv4,
+    Ipv6,
 }
 
-pub(crate) fn make_validate_bytes(
-    field: &FieldDescriptor,
-    rules: &FieldRules,
-) -> Vec<FieldValidationFn<Arc<Bytes>>> {
-    let mut fns = Vec::new();
-    if !matches!(rules.r#type, Some(Type::Bytes(_))) {
-        return fns;
-    }
-    let rules = match &rules.r#type {
-        Some(Type::Bytes(rule)) => rule,
-        _ => return fns,
-    };
-    let name = Arc::new(field.full_name().to_string());
+pub(crate) fn compile_bytes(rules: &BytesRules) -> Vec<BytesRule> {
+    let mut out = Vec::new();
     if rules.ignore_empty() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, _: &BytesRules, _: &String| Ok(!val.is_empty())),
-        );
+        out.push(BytesRule::IgnoreEmpty);
     }
-    if rules.r#const.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if val != rules.r#const() {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Const(rules.r#const().to_vec()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.r#const {
+        out.push(BytesRule::Const(v.to_vec()));
     }
-    if rules.len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if val.len() != rules.len() as usize {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Len(rules.len() as usize),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.len {
+        out.push(BytesRule::Len(v as usize));
     }
-    if rules.min_len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if val.len() < rules.min_len() as usize {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::MinLen(rules.min_len() as usize),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.min_len {
+        out.push(BytesRule::MinLen(v as usize));
     }
-    if rules.max_len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if val.len() > rules.max_len() as usize {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::MaxLen(rules.max_len() as usize),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.max_len {
+        out.push(BytesRule::MaxLen(v as usize));
     }
-    if rules.pattern.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                let regex = Regex::new(rules.pattern())
-                    .map_err(|_| format_err!(name, "must be a valid regex pattern"))?;
-                if !regex.is_match(val.iter().as_slice()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Pattern(rules.pattern().to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.pattern {
+        out.push(BytesRule::Pattern(v.clone(), Regex::new(v).ok()));
     }
-    if rules.prefix.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if !val.starts_with(rules.prefix()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Prefix(rules.prefix().to_vec()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.prefix {
+        out.push(BytesRule::Prefix(v.to_vec()));
     }
-    if rules.suffix.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if !val.ends_with(rules.suffix()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Suffix(rules.suffix().to_vec()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.suffix {
+        out.push(BytesRule::Suffix(v.to_vec()));
     }
-    if rules.contains.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                let v = Bytes::from(rules.contains().to_vec());
-                if !contains_slice(val, &v) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::Contains(rules.contains().to_vec()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.contains {
+        out.push(BytesRule::Contains(v.to_vec()));
     }
     if !rules.r#in.is_empty() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if !rules.r#in.contains(&val.deref().into()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::In(rules.r#in.clone()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+        out.push(BytesRule::In(
+            rules.r#in.iter().map(|v| v.to_vec()).collect(),
+        ));
     }
     if !rules.not_in.is_empty() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: &Bytes, rules: &BytesRules, name: &String| {
-                if rules.not_in.contains(&val.deref().into()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        bytes::Error::NotIn(rules.not_in.clone()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+        out.push(BytesRule::NotIn(
+            rules.not_in.iter().map(|v| v.to_vec()).collect(),
+        ));
     }
-    if rules.well_known.is_none() {
-        return fns;
+    match rules.well_known {
+        Some(WellKnown::Ip(true)) => out.push(BytesRule::Ip),
+        Some(WellKnown::Ipv4(true)) => out.push(BytesRule::Ipv4),
+        Some(WellKnown::Ipv6(true)) => out.push(BytesRule::Ipv6),
+        _ => {}
     }
-    #[allow(clippy::unwrap_used)]
-    match rules.well_known.unwrap() {
-        WellKnown::Ip(v) => {
-            if v {
-                push(
-                    &mut fns,
-                    &name,
-                    Arc::new(move |val: &Bytes, _: &BytesRules, name: &String| {
-                        if val.len() != 16 && val.len() != 4 {
-                            return Err(Error::new(name.to_string(), bytes::Error::Ip));
-                        }
-                        Ok(true)
-                    }),
-                );
-            }
-        }
-        WellKnown::Ipv4(v) => {
-            if v {
-                push(
-                    &mut fns,
-                    &name,
-                    Arc::new(move |val: &Bytes, _: &BytesRules, name: &String| {
-                        if val.len() != 4 {
-                            return Err(Error::new(name.to_string(), bytes::Error::Ipv4));
-                        }
-                        Ok(true)
-                    }),
-                );
+    out
+}
+
+impl BytesRule {
+    pub(crate) fn check(&self, name: &str, val: &[u8]) -> Result<bool> {
+        let err = match self {
+            Self::IgnoreEmpty => return Ok(!val.is_empty()),
+            Self::Const(v) if val != v.as_slice() => bytes::Error::Const(v.clone()),
+            Self::Len(v) if val.len() != *v => bytes::Error::Len(*v),
+            Self::MinLen(v) if val.len() < *v => bytes::Error::MinLen(*v),
+            Self::MaxLen(v) if val.len() > *v => bytes::Error::MaxLen(*v),
+            Self::Pattern(_, None) => {
+                return Err(format_err!(name, "must be a valid regex pattern"))
             }
-        }
-        WellKnown::Ipv6(v) => {
-            if v {
-                push(
-                    &mut fns,
-                    &name,
-                    Arc::new(move |val: &Bytes, _: &BytesRules, name: &String| {
-                        if val.len() != 16 {
-                            return Err(Error::new(name.to_string(), bytes::Error::Ipv6));
-                        }
-                        Ok(true)
-                    }),
-                );
+            Self::Pattern(v, Some(regex)) if !regex.is_match(val) => {
+                bytes::Error::Pattern(v.clone())
             }
-        }
+            Self::Prefix(v) if !val.starts_with(v) => bytes::Error::Prefix(v.clone()),
+            Self::Suffix(v) if !val.ends_with(v) => bytes::Error::Suffix(v.clone()),
+            Self::Contains(v) if !contains_slice(val, v) => bytes::Error::Contains(v.clone()),
+            Self::In(v) if !v.iter().any(|v| v == val) => bytes::Error::In(v.clone()),
+            Self::NotIn(v) if v.iter().any(|v| v == val) => bytes::Error::NotIn(v.clone()),
+            Self::Ip if val.len() != 16 && val.len() != 4 => bytes::Error::Ip,
+            Self::Ipv4 if val.len() != 4 => bytes::Error::Ipv4,
+            Self::Ipv6 if val.len() != 16 => bytes::Error::Ipv6,
+            _ => return Ok(true),
+        };
+        Err(Error::new(name, err))
     }
-    fns
 }
 
-fn contains_slice(slice: &'_ Bytes, sub: &'_ Bytes) -> bool {
+fn contains_slice(slice: &[u8], sub: &[u8]) -> bool {
     let len = sub.len();
     if len == 0 {
         return true;
@@ -0,0 +1,298 @@
+//! Evaluation of the protovalidate CEL expression rules,
+//! `(buf.validate.field).cel` and `(buf.validate.message).cel`.
+//!
+//! The expressions are evaluated with `this` bound to the field value, or to the message,
+//! and `now` bound to the current time of the validation context.
+//! An expression passes if it evaluates to `true` or to an empty string:
+//! `false` reports the message of the rule, a non-empty string is reported as the message.
+
+use crate::program::collected;
+use cel::objects::{Key, Map};
+use cel::{Env, Program, Value as CelValue};
+use once_cell::sync::Lazy;
+use prost_reflect::{
+    DynamicMessage, FieldDescriptor, MapKey, MessageDescriptor, ReflectMessage, Value,
+};
+use prost_validate::{
+    errors, format_err, Context, Error, FieldPath, Result, ValidateStringExt, Violations,
+};
+use prost_validate_types::buf::validate::{Ignore, Rule};
+use prost_validate_types::{FieldRulesExt, MessageRulesExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+static ENV: Lazy<Arc<Env>> = Lazy::new(|| Arc::new(Env::stdlib()));
+
+/// A compiled CEL expression rule.
+struct Expression {
+    id: String,
+    message: String,
+    source: String,
+    program: Program,
+}
+
+impl Expression {
+    fn compile(name: &str, rule: &Rule) -> Result<Self> {
+        let program = ENV
+            .compile(rule.expression())
+            .map_err(|e| format_err!(name, "{}: invalid CEL expression: {}", rule.id(), e))?;
+        Ok(Self {
+            id: rule.id().to_string(),
+            message: rule.message().to_string(),
+            source: rule.expression().to_string(),
+            program,
+        })
+    }
+
+    /// Evaluates the expression against `this`, returning the error to report if it fails.
+    fn eval(&self, name: &str, this: &CelValue, now: OffsetDateTime) -> Result<()> {
+        let mut ctx = cel::Context::with_env(ENV.clone());
+        ctx.add_variable_from_value("this", this.clone());
+        ctx.add_variable_from_value("now", timestamp(now.unix_timestamp(), now.nanosecond()));
+        add_functions(&mut ctx).map_err(|e| format_err!(name, "{}: {}", self.id, e))?;
+        let message = match self.program.execute(&ctx) {
+            Ok(CelValue::Bool(true)) => return Ok(()),
+            Ok(CelValue::Bool(false)) if self.message.is_empty() => {
+                format!("`{}` returned false", self.source)
+            }
+            Ok(CelValue::Bool(false)) => self.message.clone(),
+            Ok(CelValue::String(s)) if s.is_empty() => return Ok(()),
+            Ok(CelValue::String(s)) => s.to_string(),
+            Ok(v) => {
+                return Err(format_err!(
+                    name,
+                    "{}: expression must evaluate to a bool or a string, got {}",
+                    self.id,
+                    v.type_of()
+                ))
+            }
+            Err(e) => return Err(format_err!(name, "{}: {}", self.id, e)),
+        };
+        Err(Error::new(
+            name,
+            errors::cel::Error::Expression {
+                id: self.id.clone(),
+                message,
+            },
+        ))
+    }
+}
+
+fn compile(name: &str, rules: &[Rule]) -> Result<Vec<Expression>> {
+    rules.iter().map(|r| Expression::compile(name, r)).collect()
+}
+
+/// Evaluates all the expressions against `this`.
+fn eval_all(
+    exprs: &[Expression],
+    name: &str,
+    this: &CelValue,
+    ctx: &Context,
+    all: bool,
+) -> Result<(), Violations> {
+    let now = ctx.now();
+    let mut errs = Violations::default();
+    for expr in exprs {
+        if let Err(err) = expr.eval(name, this, now) {
+            if !all {
+                return Err(err.into());
+            }
+            errs.extend([err]);
+        }
+    }
+    collected(errs, ())
+}
+
+/// The compiled `(buf.validate.field).cel` rules of a field.
+pub(crate) struct FieldExpressions {
+    field: FieldDescriptor,
+    exprs: Vec<Expression>,
+    /// Whether an unset field is not validated.
+    ignore_empty: bool,
+}
+
+impl FieldExpressions {
+    /// Compiles the expressions of the field, if any.
+    pub(crate) fn compile(field: &FieldDescriptor) -> Result<Option<Self>> {
+        let rules = match field
+            .protovalidate_rules()
+            .map_err(|e| format_err!(field.full_name(), "{}", e))?
+        {
+            Some(r) if !r.cel.is_empty() && r.ignore() != Ignore::Always => r,
+            _ => return Ok(None),
+        };
+        Ok(Some(Self {
+            exprs: compile(field.full_name(), &rules.cel)?,
+            ignore_empty: field.supports_presence() || rules.ignore() == Ignore::IfZeroValue,
+            field: field.clone(),
+        }))
+    }
+
+    /// Returns the field of the expressions.
+    pub(crate) fn field(&self) -> &FieldDescriptor {
+        &self.field
+    }
+
+    pub(crate) fn validate(
+        &self,
+        msg: &DynamicMessage,
+        ctx: &Context,
+        all: bool,
+    ) -> Result<(), Violations> {
+        // without presence, has_field reports whether the field has a non-zero value
+        if self.ignore_empty && !msg.has_field(&self.field) {
+            return Ok(());
+        }
+        let this = value(&msg.get_field(&self.field));
+        eval_all(&self.exprs, self.field.full_name(), &this, ctx, all)
+    }
+}
+
+/// The compiled `(buf.validate.message).cel` rules of a message.
+pub(crate) struct MessageExpressions {
+    name: String,
+    exprs: Vec<Expression>,
+}
+
+impl MessageExpressions {
+    /// Compiles the expressions of the message, if any.
+    pub(crate) fn compile(desc: &MessageDescriptor) -> Result<Option<Self>> {
+        let rules = match desc
+            .protovalidate_rules()
+            .map_err(|e| format_err!(desc.full_name(), "{}", e))?
+        {
+            Some(r) if !r.cel.is_empty() => r,
+            _ => return Ok(None),
+        };
+        Ok(Some(Self {
+            exprs: compile(desc.full_name(), &rules.cel)?,
+            name: desc.full_name().to_string(),
+        }))
+    }
+
+    pub(crate) fn validate(
+        &self,
+        msg: &DynamicMessage,
+        ctx: &Context,
+        all: bool,
+    ) -> Result<(), Violations> {
+        let this = message(msg);
+        // the violations of the message rules are reported on the message itself
+        eval_all(&self.exprs, &self.name, &this, ctx, all).map_err(|errs| {
+            errs.into_iter()
+                .map(|err| err.with_path(FieldPath::default()))
+                .collect()
+        })
+    }
+}
+
+/// Converts a protobuf value to a CEL value.
+fn value(v: &Value) -> CelValue {
+    match v {
+        Value::Bool(v) => CelValue::Bool(*v),
+        Value::I32(v) => CelValue::Int(i64::from(*v)),
+        Value::I64(v) => CelValue::Int(*v),
+        Value::U32(v) => CelValue::UInt(u64::from(*v)),
+        Value::U64(v) => CelValue::UInt(*v),
+        Value::F32(v) => CelValue::Float(f64::from(*v)),
+        Value::F64(v) => CelValue::Float(*v),
+        Value::String(v) => CelValue::String(Arc::new(v.clone())),
+        Value::Bytes(v) => CelValue::Bytes(Arc::new(v.to_vec())),
+        Value::EnumNumber(v) => CelValue::Int(i64::from(*v)),
+        Value::Message(v) => message(v),
+        Value::List(v) => CelValue::List(Arc::new(v.iter().map(value).collect())),
+        Value::Map(v) => CelValue::Map(Map {
+            map: Arc::new(v.iter().map(|(k, v)| (key(k), value(v))).collect()),
+        }),
+    }
+}
+
+fn key(k: &MapKey) -> Key {
+    match k {
+        MapKey::Bool(v) => Key::Bool(*v),
+        MapKey::I32(v) => Key::Int(i64::from(*v)),
+        MapKey::I64(v) => Key::Int(*v),
+        MapKey::U32(v) => Key::Uint(u64::from(*v)),
+        MapKey::U64(v) => Key::Uint(*v),
+        MapKey::String(v) => Key::String(Arc::new(v.clone())),
+    }
+}
+
+/// Converts a message to a CEL value: the well-known types are converted to their CEL
+/// equivalent, the other messages to a map of their populated fields so that `has()` works.
+fn message(msg: &DynamicMessage) -> CelValue {
+    let desc = msg.descriptor();
+    let int = |name: &str| {
+        msg.get_field_by_name(name)
+            .and_then(|v| v.as_i64().or(v.as_i32().map(i64::from)))
+            .unwrap_or_default()
+    };
+    match desc.full_name() {
+        "google.protobuf.Timestamp" => {
+            let nanos = u32::try_from(int("nanos")).unwrap_or_default();
+            return timestamp(int("seconds"), nanos);
+        }
+        "google.protobuf.Duration" => {
+            return CelValue::Duration(
+                chrono::Duration::seconds(int("seconds"))
+                    + chrono::Duration::nanoseconds(int("nanos")),
+            );
+        }
+        "google.protobuf.BoolValue"
+        | "google.protobuf.BytesValue"
+        | "google.protobuf.DoubleValue"
+        | "google.protobuf.FloatValue"
+        | "google.protobuf.Int32Value"
+        | "google.protobuf.Int64Value"
+        | "google.protobuf.StringValue"
+        | "google.protobuf.UInt32Value"
+        | "google.protobuf.UInt64Value" => {
+            return msg
+                .get_field_by_name("value")
+                .map(|v| value(&v))
+                .unwrap_or(CelValue::Null);
+        }
+        _ => {}
+    }
+    let map: HashMap<Key, CelValue> = desc
+        .fields()
+        .filter(|f| !f.supports_presence() || msg.has_field(f))
+        .map(|f| {
+            (
+                Key::String(Arc::new(f.name().to_string())),
+                value(&msg.get_field(&f)),
+            )
+        })
+        .collect();
+    CelValue::Map(Map { map: Arc::new(map) })
+}
+
+fn timestamp(seconds: i64, nanos: u32) -> CelValue {
+    chrono::DateTime::from_timestamp(seconds, nanos)
+        .map(|t| CelValue::Timestamp(t.fixed_offset()))
+        .unwrap_or(CelValue::Null)
+}
+
+/// Registers the protovalidate extension functions.
+fn add_functions(ctx: &mut cel::Context) -> Result<(), cel::DeclarationError> {
+    use cel::extractors::This;
+
+    ctx.add_function("isEmail", |This(s): This<Arc<String>>| {
+        s.validate_email().is_ok()
+    })?;
+    ctx.add_function("isHostname", |This(s): This<Arc<String>>| {
+        s.validate_hostname().is_ok()
+    })?;
+    ctx.add_function("isIp", |This(s): This<Arc<String>>| s.validate_ip().is_ok())?;
+    ctx.add_function("isUri", |This(s): This<Arc<String>>| {
+        s.validate_uri().is_ok()
+    })?;
+    ctx.add_function("isUriRef", |This(s): This<Arc<String>>| {
+        s.validate_uri_ref().is_ok()
+    })?;
+    ctx.add_function("unique", |This(l): This<Arc<Vec<CelValue>>>| {
+        l.iter().enumerate().all(|(i, v)| !l[i + 1..].contains(v))
+    })?;
+    Ok(())
+}
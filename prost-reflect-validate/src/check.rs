@@ -0,0 +1,162 @@
+use crate::cel;
+use crate::overrides::Overrides;
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use prost_validate::{format_err, Error, Violations};
+use prost_validate_types::field_rules::Type;
+use prost_validate_types::{FieldRules, MessageRulesExt};
+
+/// Checks the rules of the fields of the message, without building their validation,
+/// returning all the invalid rules found.
+///
+/// The fields are checked with their overridden rules, if any.
+/// The rules of the embedded messages are not checked.
+pub(crate) fn check_message(
+    desc: &MessageDescriptor,
+    overrides: &Overrides,
+) -> Result<(), Violations> {
+    if desc.validation_disabled() || desc.validation_ignored() {
+        return Ok(());
+    }
+    let mut errs = Vec::new();
+    for field in desc.fields() {
+        match overrides.rules(&field) {
+            Ok(Some(rules)) => check_field(&field, &rules, false, &mut errs),
+            Ok(None) => {}
+            Err(err) => errs.push(err),
+        }
+        if let Err(err) = cel::FieldExpressions::compile(&field) {
+            errs.push(err);
+        }
+    }
+    if let Err(err) = cel::MessageExpressions::compile(desc) {
+        errs.push(err);
+    }
+    if errs.is_empty() {
+        Ok(())
+    } else {
+        Err(errs.into())
+    }
+}
+
+/// Checks that the rules match the type of the field, or of its items if `item` is true,
+/// and that their patterns compile.
+fn check_field(field: &FieldDescriptor, rules: &FieldRules, item: bool, errs: &mut Vec<Error>) {
+    let name = field.full_name();
+    let r#type = match &rules.r#type {
+        Some(r#type) => r#type,
+        None => return,
+    };
+    if !item && field.is_map() {
+        let Type::Map(rules) = r#type else {
+            return errs.push(unexpected(name, r#type, "map"));
+        };
+        let Some(entry) = field.kind().as_message().cloned() else {
+            return;
+        };
+        if let Some(keys) = &rules.keys {
+            check_field(&entry.map_entry_key_field(), keys, true, errs);
+        }
+        if let Some(values) = &rules.values {
+            check_field(&entry.map_entry_value_field(), values, true, errs);
+        }
+        return;
+    }
+    if !item && field.is_list() {
+        let Type::Repeated(rules) = r#type else {
+            return errs.push(unexpected(name, r#type, "repeated"));
+        };
+        if let Some(items) = &rules.items {
+            check_field(field, items, true, errs);
+        }
+        return;
+    }
+    let expected = match field.kind() {
+        Kind::Double => "double",
+        Kind::Float => "float",
+        Kind::Int32 => "int32",
+        Kind::Int64 => "int64",
+        Kind::Uint32 => "uint32",
+        Kind::Uint64 => "uint64",
+        Kind::Sint32 => "sint32",
+        Kind::Sint64 => "sint64",
+        Kind::Fixed32 => "fixed32",
+        Kind::Fixed64 => "fixed64",
+        Kind::Sfixed32 => "sfixed32",
+        Kind::Sfixed64 => "sfixed64",
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::Enum(_) => "enum",
+        Kind::Message(desc) => match desc.full_name() {
+            "google.protobuf.Any" => "any",
+            "google.protobuf.Duration" => "duration",
+            "google.protobuf.Timestamp" => "timestamp",
+            "google.protobuf.DoubleValue" => "double",
+            "google.protobuf.FloatValue" => "float",
+            "google.protobuf.Int64Value" => "int64",
+            "google.protobuf.UInt64Value" => "uint64",
+            "google.protobuf.Int32Value" => "int32",
+            "google.protobuf.UInt32Value" => "uint32",
+            "google.protobuf.BoolValue" => "bool",
+            "google.protobuf.StringValue" => "string",
+            "google.protobuf.BytesValue" => "bytes",
+            _ => "message",
+        },
+    };
+    if type_name(r#type) != expected {
+        return errs.push(unexpected(name, r#type, expected));
+    }
+    match r#type {
+        Type::String(rules) => {
+            if let Some(pattern) = &rules.pattern {
+                if let Err(err) = regex::Regex::new(pattern) {
+                    errs.push(format_err!(name, "invalid pattern: {}", err));
+                }
+            }
+        }
+        Type::Bytes(rules) => {
+            if let Some(pattern) = &rules.pattern {
+                if let Err(err) = regex::bytes::Regex::new(pattern) {
+                    errs.push(format_err!(name, "invalid pattern: {}", err));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn unexpected(name: &str, r#type: &Type, expected: &str) -> Error {
+    format_err!(
+        name,
+        "unexpected {} rules, expected {} rules",
+        type_name(r#type),
+        expected
+    )
+}
+
+/// Returns the name of the rules, as used in the rule definitions, e.g. `string`.
+fn type_name(r#type: &Type) -> &'static str {
+    match r#type {
+        Type::Float(_) => "float",
+        Type::Double(_) => "double",
+        Type::Int32(_) => "int32",
+        Type::Int64(_) => "int64",
+        Type::Uint32(_) => "uint32",
+        Type::Uint64(_) => "uint64",
+        Type::Sint32(_) => "sint32",
+        Type::Sint64(_) => "sint64",
+        Type::Fixed32(_) => "fixed32",
+        Type::Fixed64(_) => "fixed64",
+        Type::Sfixed32(_) => "sfixed32",
+        Type::Sfixed64(_) => "sfixed64",
+        Type::Bool(_) => "bool",
+        Type::String(_) => "string",
+        Type::Bytes(_) => "bytes",
+        Type::Enum(_) => "enum",
+        Type::Repeated(_) => "repeated",
+        Type::Map(_) => "map",
+        Type::Any(_) => "any",
+        Type::Duration(_) => "duration",
+        Type::Timestamp(_) => "timestamp",
+    }
+}
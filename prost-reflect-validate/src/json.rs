@@ -0,0 +1,55 @@
+use prost_reflect::{DynamicMessage, Kind, MessageDescriptor};
+use prost_validate::{errors, Error, FieldPath, PathSegment, Result, Violations};
+
+/// Deserializes the proto-JSON message of type `desc`, rejecting trailing characters.
+///
+/// The errors are reported as [`Decode`](errors::Error::Decode) errors,
+/// with the line and column of the invalid JSON.
+pub(crate) fn deserialize(desc: MessageDescriptor, json: &str) -> Result<DynamicMessage> {
+    let name = desc.full_name().to_string();
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    DynamicMessage::deserialize(desc, &mut deserializer)
+        .and_then(|msg| deserializer.end().map(|_| msg))
+        .map_err(|e| Error::new(name, errors::Error::Decode(e.to_string())))
+}
+
+/// Returns the errors with the JSON names of the fields in their paths,
+/// e.g. `val.mapVal[1]` instead of `val.map_val[1]`.
+pub(crate) fn json_errors(desc: &MessageDescriptor, errs: Violations) -> Violations {
+    errs.into_iter()
+        .map(|err| {
+            let path = json_path(desc, err.path.clone());
+            err.with_path(path)
+        })
+        .collect()
+}
+
+/// Returns the path with the JSON names of the fields of the message `desc`
+/// and of the messages they embed.
+fn json_path(desc: &MessageDescriptor, path: FieldPath) -> FieldPath {
+    // the kind of the value the current segment belongs to
+    let mut kind = Some(Kind::Message(desc.clone()));
+    path.into_iter()
+        .map(|segment| {
+            let PathSegment::Field(name) = segment else {
+                return segment;
+            };
+            let field = kind
+                .as_ref()
+                .and_then(Kind::as_message)
+                .and_then(|desc| desc.get_field_by_name(&name));
+            let Some(field) = field else {
+                // e.g. the name of a required oneof
+                kind = None;
+                return PathSegment::Field(name);
+            };
+            kind = match field.kind() {
+                Kind::Message(entry) if field.is_map() => {
+                    Some(entry.map_entry_value_field().kind())
+                }
+                kind => Some(kind),
+            };
+            PathSegment::Field(field.json_name().to_string())
+        })
+        .collect()
+}
@@ -1,23 +1,36 @@
 #![doc = include_str!("../README.md")]
 
-use crate::registry::REGISTRY;
-use prost_reflect::ReflectMessage;
+use once_cell::sync::Lazy;
+use prost_reflect::{DynamicMessage, FieldDescriptor, MessageDescriptor, ReflectMessage, Value};
+use prost_validate::{Context, Violations};
 
 mod any;
 mod bool;
 mod bytes;
+mod cel;
+mod check;
 mod duration;
 mod r#enum;
-mod field;
+mod json;
 mod list;
 mod map;
-mod message;
 mod number;
+mod overrides;
+mod program;
+mod range;
 mod registry;
 mod string;
 mod timestamp;
+#[cfg(feature = "tower")]
+pub mod tower;
 mod utils;
 
+pub use overrides::Overrides;
+pub use registry::Registry;
+
+/// The registry used by the free functions and [`ValidatorExt`].
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::default);
+
 /// Extension trait for validating messages using [`prost-reflect`](https://docs.rs/prost-reflect/latest/prost_reflect/).
 ///
 /// The implementation is provided for the [`prost_reflect::ReflectMessage`](https://docs.rs/prost-reflect/latest/prost_reflect/trait.ReflectMessage.html) trait:
@@ -37,15 +50,87 @@ mod utils;
 /// ```
 pub trait ValidatorExt: Send + Sync {
     fn validate(&self) -> prost_validate::Result<()>;
+
+    /// Validates the message in the given context,
+    /// e.g. using a custom clock for the time-relative rules.
+    fn validate_with(&self, ctx: &Context) -> prost_validate::Result<()>;
+
+    /// Validates the message without stopping at the first error,
+    /// returning all the violations found.
+    fn validate_all(&self) -> prost_validate::Result<(), Violations>;
+
+    /// Validates the message in the given context without stopping at the first error,
+    /// returning all the violations found.
+    fn validate_all_with(&self, ctx: &Context) -> prost_validate::Result<(), Violations>;
 }
 
 impl<T: ReflectMessage> ValidatorExt for T {
     fn validate(&self) -> prost_validate::Result<()> {
         validate(self)
     }
+
+    fn validate_with(&self, ctx: &Context) -> prost_validate::Result<()> {
+        validate_with(self, ctx)
+    }
+
+    fn validate_all(&self) -> prost_validate::Result<(), Violations> {
+        validate_all(self)
+    }
+
+    fn validate_all_with(&self, ctx: &Context) -> prost_validate::Result<(), Violations> {
+        validate_all_with(self, ctx)
+    }
 }
 
+/// Validates the message using the default [`Registry`].
 pub fn validate<T: ReflectMessage>(msg: &T) -> prost_validate::Result<()> {
-    let msg = msg.transcode_to_dynamic();
-    REGISTRY.validate(&msg)
+    validate_with(msg, &Context::default())
+}
+
+/// Validates the message in the given context.
+pub fn validate_with<T: ReflectMessage>(msg: &T, ctx: &Context) -> prost_validate::Result<()> {
+    REGISTRY.validate_with(msg, ctx)
+}
+
+/// Validates the message, collecting all the violations instead of returning the first one.
+pub fn validate_all<T: ReflectMessage>(msg: &T) -> prost_validate::Result<(), Violations> {
+    validate_all_with(msg, &Context::default())
+}
+
+/// Validates the message in the given context, collecting all the violations.
+pub fn validate_all_with<T: ReflectMessage>(
+    msg: &T,
+    ctx: &Context,
+) -> prost_validate::Result<(), Violations> {
+    REGISTRY.validate_all_with(msg, ctx)
+}
+
+/// Validates the dynamic message as is, without the encoding round trip of [`validate`].
+pub fn validate_dynamic(msg: &DynamicMessage) -> prost_validate::Result<()> {
+    REGISTRY.validate_dynamic(msg)
+}
+
+/// Validates the message of type `desc` encoded in `buf`, decoding it only once.
+pub fn validate_bytes(desc: &MessageDescriptor, buf: &[u8]) -> prost_validate::Result<()> {
+    REGISTRY.validate_bytes(desc, buf)
+}
+
+/// Validates the proto-JSON message of type `desc`, reporting the errors with the JSON names of the fields,
+/// see [`Registry::validate_json`].
+pub fn validate_json(desc: &MessageDescriptor, json: &str) -> prost_validate::Result<()> {
+    REGISTRY.validate_json(desc, json)
+}
+
+/// Validates the proto-JSON message of type `desc`, collecting all the violations.
+pub fn validate_all_json(
+    desc: &MessageDescriptor,
+    json: &str,
+) -> prost_validate::Result<(), Violations> {
+    REGISTRY.validate_all_json(desc, json)
+}
+
+/// Validates a single value of the field, running only the rules of this field,
+/// see [`Registry::validate_field`].
+pub fn validate_field(field: &FieldDescriptor, value: &Value) -> prost_validate::Result<()> {
+    REGISTRY.validate_field(field, value)
 }
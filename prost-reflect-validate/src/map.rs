@@ -1,223 +1,75 @@
-use crate::field::make_validate_field;
-use crate::registry::{NestedValidationFn, ValidationFn, REGISTRY};
-use prost_reflect::{FieldDescriptor, Kind, MapKey, Value};
+use prost::encoding::{decode_key, decode_varint, skip_field, DecodeContext, WireType};
+use prost_reflect::{MapKey, Value};
 use prost_validate::errors::map;
-use prost_validate::{format_err, Error};
-use prost_validate_types::field_rules::Type;
-use prost_validate_types::{FieldRules, MapRules};
-use std::borrow::Cow;
+use prost_validate::{Error, PathKey, Result};
+use prost_validate_types::MapRules;
 use std::collections::HashMap;
-use std::sync::Arc;
 
-macro_rules! list_rules {
-    ($rules:ident) => {
-        match &$rules.r#type {
-            Some(Type::Map(rules)) => rules,
-            _ => return Err(format_err!("unexpected map rules")),
-        }
-    };
+/// A compiled rule of a map value, the rules of the keys and values are compiled separately.
+#[derive(Debug, Clone)]
+pub(crate) enum MapRule {
+    IgnoreEmpty,
+    MinPairs(usize),
+    MaxPairs(usize),
 }
 
-fn push<F>(fns: &mut Vec<NestedValidationFn<HashMap<MapKey, Value>>>, name: &Arc<String>, f: Arc<F>)
-where
-    F: Fn(
-            &HashMap<MapKey, Value>,
-            &MapRules,
-            &String,
-            &HashMap<String, ValidationFn>,
-        ) -> prost_validate::Result<bool>
-        + Send
-        + Sync
-        + 'static,
-{
-    let name = name.clone();
-    fns.push(Arc::new(move |val, rules, m| {
-        let val = val.unwrap_or_default();
-        let rules = list_rules!(rules);
-        f(&val, rules, &name, m)
-    }))
+pub(crate) fn compile_map(rules: &MapRules) -> Vec<MapRule> {
+    let mut out = Vec::new();
+    if rules.ignore_empty() {
+        out.push(MapRule::IgnoreEmpty);
+    }
+    if rules.min_pairs.is_some() {
+        out.push(MapRule::MinPairs(rules.min_pairs() as usize));
+    }
+    if rules.max_pairs.is_some() {
+        out.push(MapRule::MaxPairs(rules.max_pairs() as usize));
+    }
+    out
 }
 
-#[allow(clippy::unwrap_used)]
-pub(crate) fn make_validate_map(
-    m: &mut HashMap<String, ValidationFn>,
-    field: &FieldDescriptor,
-    rules: &FieldRules,
-) -> Vec<NestedValidationFn<HashMap<MapKey, Value>>> {
-    let mut fns = Vec::new();
-    let name = Arc::new(field.full_name().to_string());
-    let (key_desc, val_desc) = {
-        let k = field.kind();
-        let k = k.as_message().unwrap();
-        (k.get_field(1).unwrap(), k.get_field(2).unwrap())
-    };
-    if let Some(Type::Map(rules)) = &rules.r#type {
-        if rules.ignore_empty() {
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          _: &MapRules,
-                          _: &String,
-                          _: &HashMap<String, ValidationFn>| {
-                        Ok(!vals.is_empty())
-                    },
-                ),
-            );
-        }
-        if rules.min_pairs.is_some() {
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          rules: &MapRules,
-                          name: &String,
-                          _: &HashMap<String, ValidationFn>| {
-                        let v = rules.min_pairs();
-                        if vals.len() < v as usize {
-                            return Err(Error::new(
-                                name.to_string(),
-                                map::Error::MinPairs(v as usize),
-                            ));
-                        }
-                        Ok(true)
-                    },
-                ),
-            );
-        }
-        if rules.max_pairs.is_some() {
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          rules: &MapRules,
-                          name: &String,
-                          _: &HashMap<String, ValidationFn>| {
-                        let v = rules.max_pairs();
-                        if vals.len() > v as usize {
-                            return Err(Error::new(
-                                name.to_string(),
-                                map::Error::MaxPairs(v as usize),
-                            ));
-                        }
-                        Ok(true)
-                    },
-                ),
-            );
-        }
-        if let Some(ref rules) = rules.keys {
-            let validate = make_validate_field(m, &key_desc, rules);
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          rules: &MapRules,
-                          name: &String,
-                          m: &HashMap<String, ValidationFn>| {
-                        let rules = rules.keys.as_ref().unwrap();
-                        for k in vals.keys() {
-                            let val = Value::from(k.clone());
-                            if !validate(Cow::Borrowed(&val), rules, m).map_err(|e| {
-                                Error::new(
-                                    format!("{}[{:?}]", name, map_key_string(k)),
-                                    map::Error::Keys(Box::new(e)),
-                                )
-                            })? {
-                                return Ok(false);
-                            }
-                        }
-                        Ok(true)
-                    },
-                ),
-            );
-        }
-        if let Some(ref rules) = rules.values {
-            if rules.message.map(|v| v.skip()).unwrap_or(false) {
-                return fns;
-            }
-            let validate = make_validate_field(m, &val_desc, rules);
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          rules: &MapRules,
-                          name: &String,
-                          m: &HashMap<String, ValidationFn>| {
-                        let rules = rules.values.as_ref().unwrap();
-                        for (k, val) in vals.iter() {
-                            let val = val.clone();
-                            if !validate(Cow::Borrowed(&val), rules, m).map_err(|e| {
-                                Error::new(
-                                    format!("{}[{:?}]", name, map_key_string(k)),
-                                    map::Error::Values(Box::new(e)),
-                                )
-                            })? {
-                                return Ok(false);
-                            }
-                        }
-                        Ok(true)
-                    },
-                ),
-            );
+impl MapRule {
+    pub(crate) fn check(&self, name: &str, vals: &HashMap<MapKey, Value>) -> Result<bool> {
+        let err = match self {
+            Self::IgnoreEmpty => return Ok(!vals.is_empty()),
+            Self::MinPairs(v) if vals.len() < *v => map::Error::MinPairs(*v),
+            Self::MaxPairs(v) if vals.len() > *v => map::Error::MaxPairs(*v),
+            _ => return Ok(true),
+        };
+        Err(Error::new(name, err))
+    }
+}
+
+/// Returns the length-delimited fields of an encoded message with their number,
+/// e.g. its embedded messages and map entries.
+///
+/// The message is decoded before, the iteration ends at the first malformed field.
+pub(crate) fn delimited_fields(mut buf: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    std::iter::from_fn(move || loop {
+        if buf.is_empty() {
+            return None;
         }
-        if rules.no_sparse.unwrap_or(false) {
-            let kind = Arc::new(field.kind());
-            push(
-                &mut fns,
-                &name,
-                Arc::new(
-                    move |vals: &HashMap<MapKey, Value>,
-                          _: &MapRules,
-                          name: &String,
-                          _: &HashMap<String, ValidationFn>| {
-                        let kind = kind.clone();
-                        for (k, val) in vals.iter() {
-                            if val.is_default(&kind) {
-                                return Err(Error::new(
-                                    format!("{}[{:?}]", name, map_key_string(k)),
-                                    map::Error::NoSparse,
-                                ));
-                            }
-                        }
-                        Ok(true)
-                    },
-                ),
-            );
+        let (number, wire_type) = decode_key(&mut buf).ok()?;
+        if wire_type != WireType::LengthDelimited {
+            skip_field(wire_type, number, &mut buf, DecodeContext::default()).ok()?;
+            continue;
         }
-    }
-    if let Kind::Message(desc) = val_desc.kind() {
-        if REGISTRY.register(m, &desc).is_err() {
-            return fns;
+        let len = usize::try_from(decode_varint(&mut buf).ok()?).ok()?;
+        if len > buf.len() {
+            return None;
         }
-        fns.push(Arc::new(move |vals, _, m| {
-            if let Some(vals) = vals {
-                for (k, val) in vals.iter() {
-                    if let Some(Err(err)) = val.as_message().map(|v| REGISTRY.do_validate(v, m)) {
-                        return Err(Error::new(
-                            format!("{}[{:?}]", name, map_key_string(k)),
-                            map::Error::Values(Box::new(err)),
-                        ));
-                    }
-                }
-            }
-            Ok(true)
-        }));
-    }
-    fns
+        let (field, rest) = buf.split_at(len);
+        buf = rest;
+        return Some((number, field));
+    })
 }
 
-fn map_key_string(k: &MapKey) -> String {
+pub(crate) fn path_key(k: &MapKey) -> PathKey {
     match k {
-        MapKey::Bool(k) => k.to_string(),
-        MapKey::I32(k) => k.to_string(),
-        MapKey::I64(k) => k.to_string(),
-        MapKey::U32(k) => k.to_string(),
-        MapKey::U64(k) => k.to_string(),
-        MapKey::String(k) => k.to_string(),
+        MapKey::Bool(k) => PathKey::from(*k),
+        MapKey::I32(k) => PathKey::from(*k),
+        MapKey::I64(k) => PathKey::from(*k),
+        MapKey::U32(k) => PathKey::from(*k),
+        MapKey::U64(k) => PathKey::from(*k),
+        MapKey::String(k) => PathKey::from(k.as_str()),
     }
 }
@@ -1,235 +1,91 @@
-use crate::registry::FieldValidationFn;
-use prost_reflect::FieldDescriptor;
-use prost_validate::format_err;
-use prost_validate::Error;
+use crate::range::{range_error, Range};
+use prost_reflect::Value;
+use prost_validate::{Error, Result};
 use prost_validate_types::field_rules::Type;
-use prost_validate_types::FieldRules;
-use std::sync::Arc;
 
-macro_rules! number_rules {
-    ($rules:ident,$enum_value:ident) => {
-        match &$rules.r#type {
-            Some(Type::$enum_value(rules)) => rules,
-            _ => return Err(format_err!("unexpected {} rules", stringify!($enum_value))),
-        }
-    };
+/// A compiled rule of a numeric value.
+#[derive(Debug, Clone)]
+pub(crate) enum NumberRule<T> {
+    IgnoreEmpty,
+    Const(T),
+    Range(Range<T>),
+    In(Vec<T>),
+    NotIn(Vec<T>),
 }
 
-macro_rules! make_validate_number {
-    ($name:ident,$enum_value:ident,$typ:ident,$default:expr,$module:ident) => {
-        pub(crate) fn $name(
-            field: &FieldDescriptor,
-            rules: &FieldRules,
-        ) -> Vec<FieldValidationFn<$typ>> {
-            use prost_validate::errors::$module;
-
-            let mut fns: Vec<FieldValidationFn<$typ>> = Vec::new();
-            if !matches!(rules.r#type, Some(Type::$enum_value(_))) {
-                return fns;
-            }
+macro_rules! make_number_rules {
+    ($($enum_value:ident($typ:ident, $conv:ident, $module:ident)),* $(,)?) => {
+        /// The compiled rules of the numeric fields, one variant per rules type.
+        #[derive(Debug, Clone)]
+        pub(crate) enum Number {
+            $($enum_value(NumberRule<$typ>),)*
+        }
 
-            let rules = match &rules.r#type {
-                Some(Type::$enum_value(rules)) => rules,
-                _ => return fns,
-            };
-            if rules.ignore_empty() {
-                fns.push(Arc::new(|val, _| {
-                    Ok(val.is_some() && val.unwrap() != $default)
-                }))
-            }
-            let name = Arc::new(field.full_name().to_string());
-            if let Some(_) = rules.r#const {
-                let name = name.clone();
-                fns.push(Arc::new(move |val, rules| {
-                    let val = val.unwrap_or($default);
-                    let v = number_rules!(rules, $enum_value).r#const.unwrap();
-                    if val != v {
-                        return Err(Error::new(name.to_string(), $module::Error::Const(v)));
+        /// Compiles the numeric rules, if any, in their evaluation order.
+        pub(crate) fn compile_number(rules: &Type) -> Vec<Number> {
+            match rules {
+                $(Type::$enum_value(rules) => {
+                    let mut out = Vec::new();
+                    if rules.ignore_empty() {
+                        out.push(NumberRule::IgnoreEmpty);
                     }
-                    Ok(true)
-                }));
-            }
-            // reference implementation: https://github.com/bufbuild/protoc-gen-validate/blob/v1.1.0/templates/goshared/ltgt.go
-            if let Some(lt) = rules.lt {
-                let name = name.clone();
-                if let Some(gt) = rules.gt {
-                    if lt > gt {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val <= gt || val >= lt {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::in_range(false, gt, lt, false),
-                                ));
-                            }
-                            Ok(true)
-                        }));
-                    } else {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val >= lt && val <= gt {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::not_in_range(true, lt, gt, true),
-                                ));
-                            }
-                            Ok(true)
-                        }));
+                    if let Some(v) = rules.r#const {
+                        out.push(NumberRule::Const(v));
                     }
-                } else if let Some(gte) = rules.gte {
-                    if lt > gte {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val < gte || val >= lt {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::in_range(true, gte, lt, false),
-                                ));
-                            }
-                            Ok(true)
-                        }));
-                    } else {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val >= lt && val < gte {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::not_in_range(true, lt, gte, false),
-                                ));
-                            }
-                            Ok(true)
-                        }));
+                    if let Some(range) = Range::compile(rules.lt, rules.lte, rules.gt, rules.gte) {
+                        out.push(NumberRule::Range(range));
                     }
-                } else {
-                    fns.push(Arc::new(move |val, _| {
-                        let val = val.unwrap_or($default);
-                        if val >= lt {
-                            return Err(Error::new(name.to_string(), $module::Error::Lt(lt)));
-                        }
-                        Ok(true)
-                    }));
-                }
-            } else if let Some(lte) = rules.lte {
-                let name = name.clone();
-                if let Some(gt) = rules.gt {
-                    if lte > gt {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val <= gt || val > lte {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::in_range(false, gt, lte, true),
-                                ));
-                            }
-                            Ok(true)
-                        }));
-                    } else {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val > lte && val <= gt {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::not_in_range(false, lte, gt, true),
-                                ));
-                            }
-                            Ok(true)
-                        }));
+                    if !rules.r#in.is_empty() {
+                        out.push(NumberRule::In(rules.r#in.clone()));
                     }
-                } else if let Some(gte) = rules.gte {
-                    if lte > gte {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val < gte || val > lte {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::in_range(true, gte, lte, true),
-                                ));
-                            }
-                            Ok(true)
-                        }));
-                    } else {
-                        fns.push(Arc::new(move |val, _| {
-                            let val = val.unwrap_or($default);
-                            if val > lte && val < gte {
-                                return Err(Error::new(
-                                    name.to_string(),
-                                    $module::Error::not_in_range(false, lte, gte, false),
-                                ));
-                            }
-                            Ok(true)
-                        }));
-                    }
-                } else {
-                    fns.push(Arc::new(move |val, _| {
-                        let val = val.unwrap_or($default);
-                        if val > lte {
-                            return Err(Error::new(name.to_string(), $module::Error::Lte(lte)));
-                        }
-                        Ok(true)
-                    }));
-                }
-            } else if let Some(gt) = rules.gt {
-                let name = name.clone();
-                fns.push(Arc::new(move |val, _| {
-                    let val = val.unwrap_or($default);
-                    if val <= gt {
-                        return Err(Error::new(name.to_string(), $module::Error::Gt(gt)));
+                    if !rules.not_in.is_empty() {
+                        out.push(NumberRule::NotIn(rules.not_in.clone()));
                     }
-                    Ok(true)
-                }));
-            } else if let Some(gte) = rules.gte {
-                let name = name.clone();
-                fns.push(Arc::new(move |val, _| {
-                    let val = val.unwrap_or($default);
-                    if val < gte {
-                        return Err(Error::new(name.to_string(), $module::Error::Gte(gte)));
-                    }
-                    Ok(true)
-                }));
+                    out.into_iter().map(Number::$enum_value).collect()
+                })*
+                _ => Vec::new(),
             }
+        }
 
-            if !rules.r#in.is_empty() {
-                let name = name.clone();
-                fns.push(Arc::new(move |val, rules| {
-                    let val = val.unwrap_or($default);
-                    let rules = number_rules!(rules, $enum_value);
-                    if !rules.r#in.contains(&val) {
-                        return Err(Error::new(
-                            name.to_string(),
-                            $module::Error::In(rules.r#in.clone()),
-                        ));
-                    }
-                    Ok(true)
-                }));
-            }
-            if !rules.not_in.is_empty() {
-                let name = name.clone();
-                fns.push(Arc::new(move |val, rules| {
-                    let val = val.unwrap_or($default);
-                    let rules = number_rules!(rules, $enum_value);
-                    if rules.not_in.contains(&val) {
-                        return Err(Error::new(
-                            name.to_string(),
-                            $module::Error::NotIn(rules.not_in.clone()),
-                        ));
-                    }
-                    Ok(true)
-                }));
+        impl Number {
+            /// Checks the value, returning whether the validation of the field goes on.
+            pub(crate) fn check(&self, name: &str, val: Option<&Value>) -> Result<bool> {
+                match self {
+                    $(Self::$enum_value(rule) => {
+                        use prost_validate::errors::$module;
+
+                        let val = val.and_then(Value::$conv).unwrap_or_default();
+                        let err = match rule {
+                            NumberRule::IgnoreEmpty => return Ok(val != $typ::default()),
+                            NumberRule::Const(v) if val != *v => $module::Error::Const(*v),
+                            NumberRule::Range(range) if range.rejects(&val) => {
+                                range_error!($module, range)
+                            }
+                            NumberRule::In(v) if !v.contains(&val) => $module::Error::In(v.clone()),
+                            NumberRule::NotIn(v) if v.contains(&val) => {
+                                $module::Error::NotIn(v.clone())
+                            }
+                            _ => return Ok(true),
+                        };
+                        Err(Error::new(name, err))
+                    })*
+                }
             }
-            fns
         }
     };
 }
 
-make_validate_number!(make_validate_u64, Uint64, u64, 0, uint64);
-make_validate_number!(make_validate_u32, Uint32, u32, 0, uint32);
-make_validate_number!(make_validate_i64, Int64, i64, 0, int64);
-make_validate_number!(make_validate_i32, Int32, i32, 0, int32);
-make_validate_number!(make_validate_double, Double, f64, 0.0, double);
-make_validate_number!(make_validate_float, Float, f32, 0.0, float);
-make_validate_number!(make_validate_sint32, Sint32, i32, 0, sint32);
-make_validate_number!(make_validate_sint64, Sint64, i64, 0, sint64);
-make_validate_number!(make_validate_fixed32, Fixed32, u32, 0, fixed32);
-make_validate_number!(make_validate_fixed64, Fixed64, u64, 0, fixed64);
-make_validate_number!(make_validate_sfixed32, Sfixed32, i32, 0, sfixed32);
-make_validate_number!(make_validate_sfixed64, Sfixed64, i64, 0, sfixed64);
+make_number_rules!(
+    Uint64(u64, as_u64, uint64),
+    Uint32(u32, as_u32, uint32),
+    Int64(i64, as_i64, int64),
+    Int32(i32, as_i32, int32),
+    Double(f64, as_f64, double),
+    Float(f32, as_f32, float),
+    Sint32(i32, as_i32, sint32),
+    Sint64(i64, as_i64, sint64),
+    Fixed32(u32, as_u32, fixed32),
+    Fixed64(u64, as_u64, fixed64),
+    Sfixed32(i32, as_i32, sfixed32),
+    Sfixed64(i64, as_i64, sfixed64),
+);
@@ -0,0 +1,200 @@
+use crate::utils::get_field_rules;
+use prost_reflect::{DynamicMessage, FieldDescriptor, ReflectMessage, Value};
+use prost_validate::{format_err, Error, Result, RuleOrigin};
+use prost_validate_types::FieldRules;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Validation rules overriding the `validate.rules` annotated on the fields,
+/// e.g. to tighten the limits of a deployment without recompiling the protos.
+///
+/// The overrides are keyed by the full name of the field, e.g. `pkg.Message.field`.
+/// Merged rules follow the protobuf merge semantics, except for the lists: the rules set in the override
+/// replace the annotated ones, the `in` and `not_in` lists set in the override replace the annotated lists,
+/// and rules of another type replace the annotated rules altogether.
+/// Replacing the rules of a field with empty rules disables its validation.
+///
+/// The overrides can be loaded with serde from any format, e.g. JSON or YAML,
+/// the rules being written in the protobuf JSON mapping:
+/// ```json
+/// {
+///   "merge": {"pkg.Message.name": {"string": {"maxLen": 32, "in": ["a", "b"]}}},
+///   "replace": {"pkg.Message.tags": {"repeated": {"maxItems": 8}}}
+/// }
+/// ```
+/// A field both merged and replaced is replaced.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Overrides {
+    #[serde(default)]
+    merge: HashMap<String, FieldRules>,
+    #[serde(default)]
+    replace: HashMap<String, FieldRules>,
+}
+
+impl Overrides {
+    /// Creates empty overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges the rules with the annotated rules of the field.
+    pub fn with_merged(mut self, field: impl Into<String>, rules: FieldRules) -> Self {
+        let field = field.into();
+        let _ = self.replace.remove(&field);
+        let _ = self.merge.insert(field, rules);
+        self
+    }
+
+    /// Replaces the annotated rules of the field with the rules.
+    pub fn with_replaced(mut self, field: impl Into<String>, rules: FieldRules) -> Self {
+        let field = field.into();
+        let _ = self.merge.remove(&field);
+        let _ = self.replace.insert(field, rules);
+        self
+    }
+
+    /// Returns the full names of the overridden fields.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.merge
+            .keys()
+            .filter(|field| !self.replace.contains_key(*field))
+            .chain(self.replace.keys())
+            .map(String::as_str)
+    }
+
+    /// Returns whether no field is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.merge.is_empty() && self.replace.is_empty()
+    }
+
+    pub(crate) fn contains(&self, field: &FieldDescriptor) -> bool {
+        self.merge.contains_key(field.full_name()) || self.replace.contains_key(field.full_name())
+    }
+
+    /// Returns the rules of the field, the annotated ones combined with its override.
+    pub(crate) fn rules(&self, field: &FieldDescriptor) -> Result<Option<Arc<FieldRules>>> {
+        if let Some(rules) = self.replace.get(field.full_name()) {
+            return Ok(Some(Arc::new(rules.clone())));
+        }
+        let annotated = get_field_rules(field)?;
+        let Some(rules) = self.merge.get(field.full_name()) else {
+            return Ok(annotated);
+        };
+        let mut merged = annotated
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+            .transcode_to_dynamic();
+        merge(&mut merged, &rules.transcode_to_dynamic());
+        let merged = merged
+            .transcode_to::<FieldRules>()
+            .map_err(|e| format_err!(field.full_name(), "invalid override: {}", e))?;
+        Ok(Some(Arc::new(merged)))
+    }
+
+    /// Returns the rules set by the override of the field, if any.
+    pub(crate) fn origin(&self, field: &FieldDescriptor) -> Option<Overridden> {
+        if self.replace.contains_key(field.full_name()) {
+            return Some(Overridden::All);
+        }
+        self.merge
+            .get(field.full_name())
+            .map(|rules| Overridden::Rules(rules.transcode_to_dynamic()))
+    }
+}
+
+/// Merges the rules set in `overrides` into `rules`.
+///
+/// Unlike the protobuf merge, the lists set in the override, e.g. `in` and `not_in`,
+/// replace the annotated ones instead of being appended to them,
+/// so that an allow-list override does not widen the annotated rule.
+fn merge(rules: &mut DynamicMessage, overrides: &DynamicMessage) {
+    for (field, value) in overrides.fields() {
+        match value {
+            Value::Message(value) if !field.is_list() && rules.has_field(&field) => {
+                if let Value::Message(rules) = rules.get_field_mut(&field) {
+                    merge(rules, value);
+                }
+            }
+            value => rules.set_field(&field, value.clone()),
+        }
+    }
+}
+
+/// The rules of a value set by an override, telling which errors come from it.
+#[derive(Clone)]
+pub(crate) enum Overridden {
+    /// The override replaces all the annotated rules.
+    All,
+    /// The override is merged with the annotated rules.
+    Rules(DynamicMessage),
+}
+
+impl Overridden {
+    /// Returns the override of the nested rules at `path`,
+    /// e.g. `["repeated", "items"]` for the rules of the items of a repeated field.
+    pub(crate) fn nested(&self, path: [&str; 2]) -> Option<Self> {
+        let Self::Rules(rules) = self else {
+            return Some(Self::All);
+        };
+        let mut rules = rules.clone();
+        for name in path {
+            if !rules.has_field_by_name(name) {
+                return None;
+            }
+            let nested = rules.get_field_by_name(name)?.as_message()?.clone();
+            rules = nested;
+        }
+        Some(Self::Rules(rules))
+    }
+
+    /// Marks the error as coming from the override if its rule is set by the override.
+    pub(crate) fn tag(&self, err: Error) -> Error {
+        if self.sets(err.rule_id()) {
+            err.with_origin(RuleOrigin::Override)
+        } else {
+            err
+        }
+    }
+
+    /// Returns whether the rule identified by `rule_id`, e.g. `string.max_len`, is set by the override.
+    ///
+    /// A range rule is set by the override if one of its bounds is.
+    fn sets(&self, rule_id: &str) -> bool {
+        let Self::Rules(rules) = self else {
+            return true;
+        };
+        let Some((r#type, rule)) = rule_id.split_once('.') else {
+            return false;
+        };
+        if !rules.has_field_by_name(r#type) {
+            return false;
+        }
+        let Some(rules) = rules.get_field_by_name(r#type) else {
+            return false;
+        };
+        let Some(rules) = rules.as_message() else {
+            return false;
+        };
+        let has = |name: &str| rules.has_field_by_name(name);
+        let rule = rule.strip_suffix("_exclusive").unwrap_or(rule);
+        has(rule)
+            // e.g. `string.well_known_regex.http_header_name`
+            || rule.split_once('.').is_some_and(|(name, _)| has(name))
+            // e.g. `int32.gt_lt`
+            || (rule
+                .split('_')
+                .all(|b| matches!(b, "gt" | "gte" | "lt" | "lte"))
+                && rule.split('_').any(has))
+    }
+}
+
+/// Marks the error as coming from the override if any.
+pub(crate) fn tag(overridden: Option<&Overridden>, err: Error) -> Error {
+    match overridden {
+        Some(overridden) => overridden.tag(err),
+        None => err,
+    }
+}
@@ -0,0 +1,947 @@
+//! The validation programs of the messages.
+//!
+//! The rules of a message are compiled once into a flat list of instructions,
+//! one per field, oneof or CEL expression, each holding the compiled rules of its values.
+//! The embedded messages are referenced by the index of their program,
+//! so that validating a message tree is a loop over the instructions without any lookup.
+//! The programs are shared between the snapshots of a registry: when its pool is reloaded
+//! or its overrides are replaced, only the programs of the messages defined or overridden
+//! differently, and of the ones embedding them, are compiled again.
+
+use crate::any::{compile_any, AnyRule};
+use crate::bool::{compile_bool, BoolRule};
+use crate::bytes::{compile_bytes, BytesRule};
+use crate::cel::{FieldExpressions, MessageExpressions};
+use crate::check::check_message;
+use crate::duration::{compile_duration, DurationRule};
+use crate::list::{compile_list, ListRule};
+use crate::map::{compile_map, delimited_fields, path_key, MapRule};
+use crate::number::{compile_number, Number};
+use crate::overrides::{tag, Overridden, Overrides};
+use crate::r#enum::{compile_enum, EnumRule};
+use crate::string::{compile_string, StringRule};
+use crate::timestamp::{compile_timestamp, TimestampRule};
+use crate::utils::is_set;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, OneofDescriptor,
+    ReflectMessage, Value,
+};
+use prost_validate::errors::{self, any, map, message};
+use prost_validate::{format_err, Context, Error, Result, Violations};
+use prost_validate_types::field_rules::Type;
+use prost_validate_types::{FieldRules, MessageRulesExt, OneofRulesExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+#[derive(Clone, Copy)]
+pub(crate) struct Args<'a> {
+    pub(crate) programs: &'a Programs,
+    /// Whether all the violations are collected instead of returning the first one.
+    pub(crate) all: bool,
+    /// The validation context, providing the clock used by the time-relative rules.
+    pub(crate) ctx: &'a Context,
+}
+
+impl Args<'_> {
+    /// Handles the result of a validation function.
+    /// When collecting all the violations, the errors are appended to `errs`
+    /// and the validation goes on, otherwise they are returned.
+    pub(crate) fn check<E: Into<Violations>>(
+        &self,
+        res: Result<bool, E>,
+        errs: &mut Violations,
+    ) -> Result<bool, Violations> {
+        match res {
+            Ok(ok) => Ok(ok),
+            Err(err) if self.all => {
+                errs.extend(err.into());
+                Ok(true)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Validates the message with the program at `index`.
+    pub(crate) fn validate(&self, index: usize, msg: &DynamicMessage) -> Result<(), Violations> {
+        match self.programs.programs.get(index) {
+            Some(program) => program.run(msg, self),
+            None => Err(format_err!(msg.descriptor().full_name(), "no validator").into()),
+        }
+    }
+
+    /// Validates the field numbered `number` of the message only, with the program at `index`.
+    pub(crate) fn validate_field(
+        &self,
+        index: usize,
+        msg: &DynamicMessage,
+        number: u32,
+    ) -> Result<(), Violations> {
+        match self.programs.programs.get(index) {
+            Some(program) => program.run_field(msg, number, self),
+            None => Err(format_err!(msg.descriptor().full_name(), "no validator").into()),
+        }
+    }
+
+    /// Reports the map entries of the encoded message missing their value,
+    /// with the program at `index`, in the maps with the `no_sparse` rule.
+    ///
+    /// prost decodes a missing message value as the default message,
+    /// so that they are only found in the encoded message.
+    pub(crate) fn check_sparse(&self, index: usize, buf: &[u8]) -> Result<(), Violations> {
+        match self.programs.programs.get(index) {
+            Some(program) if self.programs.sparse(index) => program.check_sparse(buf, self),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs the rules of a value in order, until one of them fails or stops the validation.
+    ///
+    /// The errors of the rules set by an override are marked as such.
+    fn eval(
+        &self,
+        name: &str,
+        rules: &[Rule],
+        overridden: Option<&Overridden>,
+        val: Option<&Value>,
+    ) -> Result<(), Violations> {
+        let msg = val.and_then(Value::as_message);
+        let mut errs = Violations::default();
+        for (i, rule) in rules.iter().enumerate() {
+            let res = match rule {
+                Rule::Number(rule) => rule.check(name, val),
+                Rule::Bool(rule) => rule.check(name, val),
+                Rule::Enum(rule) => rule.check(name, val),
+                Rule::String(rule) => {
+                    rule.check(name, val.and_then(Value::as_str).unwrap_or_default())
+                }
+                Rule::Bytes(rule) => {
+                    let val = val.and_then(Value::as_bytes).map(|v| &v[..]);
+                    rule.check(name, val.unwrap_or_default())
+                }
+                Rule::Duration(rule) => rule.check(name, msg),
+                Rule::Timestamp(rule) => rule.check(name, msg, self.ctx),
+                Rule::Any(rule) => rule.check(name, msg),
+                Rule::Required(err) => match msg {
+                    Some(_) => Ok(true),
+                    // a missing required value has nothing more to validate
+                    None => {
+                        let err = tag(overridden, Error::new(name, err.clone()));
+                        self.check(Err::<bool, _>(err), &mut errs)?;
+                        break;
+                    }
+                },
+                Rule::Present => Ok(msg.is_some()),
+                Rule::Unwrap => {
+                    if let Some(msg) = msg {
+                        let val = msg.get_field_by_number(1);
+                        let res = self.eval(name, &rules[i + 1..], overridden, val.as_deref());
+                        self.check(res.map(|_| true), &mut errs)?;
+                    }
+                    break;
+                }
+                Rule::Message(index) => {
+                    if let Some(msg) = msg {
+                        let res = self.validate(*index, msg).map_err(|errs| {
+                            errs.into_iter()
+                                .map(|err| Error::new(name, message::Error::Message(Box::new(err))))
+                                .collect::<Violations>()
+                        });
+                        self.check(res.map(|_| true), &mut errs)?;
+                    }
+                    continue;
+                }
+            };
+            let res = res.map_err(|err| tag(overridden, err));
+            if !self.check(res, &mut errs)? {
+                break;
+            }
+        }
+        collected(errs, ())
+    }
+}
+
+/// Returns `ok` if no violations were collected.
+pub(crate) fn collected<T>(errs: Violations, ok: T) -> Result<T, Violations> {
+    if errs.is_empty() {
+        Ok(ok)
+    } else {
+        Err(errs)
+    }
+}
+
+/// A compiled rule of a single value.
+enum Rule {
+    Number(Number),
+    Bool(BoolRule),
+    Enum(EnumRule),
+    String(StringRule),
+    Bytes(BytesRule),
+    Duration(DurationRule),
+    Timestamp(TimestampRule),
+    Any(AnyRule),
+    /// The message must be set, reporting the given error otherwise,
+    /// e.g. `message.required` or `any.required`.
+    Required(errors::Error),
+    /// Stops the validation of a message that is not set.
+    Present,
+    /// Validates the value of a wrapper message, e.g. `google.protobuf.StringValue`,
+    /// with the rules that follow.
+    Unwrap,
+    /// Validates the embedded message with the program at the given index.
+    Message(usize),
+}
+
+/// The rules of the values of a field, or of its items, keys or values.
+struct Rules {
+    name: String,
+    rules: Vec<Rule>,
+    /// The rules set by an override.
+    overridden: Option<Overridden>,
+}
+
+impl Rules {
+    /// Compiles the rules of the values of `field`.
+    fn compile(
+        programs: &mut Programs,
+        field: &FieldDescriptor,
+        field_rules: &FieldRules,
+        overridden: Option<Overridden>,
+    ) -> Self {
+        let mut rules = Vec::new();
+        let r#type = field_rules.r#type.as_ref();
+        let desc = match field.kind() {
+            Kind::Message(desc) => desc,
+            kind => {
+                rules.extend(r#type.map(|r| compile_scalar(&kind, r)).unwrap_or_default());
+                return Self::new(field, rules, overridden);
+            }
+        };
+        let message_rules = field_rules.message.unwrap_or_default();
+        // there is no way currently to check for "synthetic" oneof
+        let optional = field
+            .containing_oneof()
+            .map(|d| d.fields().len() == 1 && d.fields().any(|f| &f == field))
+            .unwrap_or(false);
+        if message_rules.required() && !optional {
+            rules.push(Rule::Required(message::Error::Required.into()));
+        } else if r#type.is_none() {
+            rules.push(Rule::Present);
+        }
+        if message_rules.skip() {
+            return Self::new(field, rules, overridden);
+        }
+        let presence = |required: bool, err: errors::Error| {
+            if required {
+                Rule::Required(err)
+            } else {
+                Rule::Present
+            }
+        };
+        match (desc.full_name(), r#type) {
+            (
+                "google.protobuf.StringValue"
+                | "google.protobuf.BytesValue"
+                | "google.protobuf.BoolValue"
+                | "google.protobuf.UInt64Value"
+                | "google.protobuf.UInt32Value"
+                | "google.protobuf.Int64Value"
+                | "google.protobuf.Int32Value"
+                | "google.protobuf.DoubleValue"
+                | "google.protobuf.FloatValue",
+                r#type,
+            ) => {
+                rules.push(Rule::Unwrap);
+                if let (Some(value), Some(r#type)) = (desc.get_field(1), r#type) {
+                    rules.extend(compile_scalar(&value.kind(), r#type));
+                }
+            }
+            ("google.protobuf.Timestamp", Some(Type::Timestamp(r))) => {
+                rules.push(presence(r.required(), message::Error::Required.into()));
+                rules.extend(compile_timestamp(r).into_iter().map(Rule::Timestamp));
+            }
+            ("google.protobuf.Duration", Some(Type::Duration(r))) => {
+                rules.push(presence(r.required(), message::Error::Required.into()));
+                rules.extend(compile_duration(r).into_iter().map(Rule::Duration));
+            }
+            ("google.protobuf.Any", Some(Type::Any(r))) => {
+                rules.push(presence(r.required(), any::Error::Required.into()));
+                rules.extend(compile_any(r).into_iter().map(Rule::Any));
+            }
+            (
+                "google.protobuf.Timestamp" | "google.protobuf.Duration" | "google.protobuf.Any",
+                _,
+            ) => {}
+            _ => {
+                rules.push(Rule::Message(programs.compile(&desc)));
+            }
+        }
+        Self::new(field, rules, overridden)
+    }
+
+    /// Returns the rules validating the embedded messages only.
+    fn nested(programs: &mut Programs, field: &FieldDescriptor, kind: &Kind) -> Option<Self> {
+        let index = programs.compile(kind.as_message()?);
+        Some(Self::new(field, vec![Rule::Message(index)], None))
+    }
+
+    fn new(field: &FieldDescriptor, rules: Vec<Rule>, overridden: Option<Overridden>) -> Self {
+        Self {
+            name: field.full_name().to_string(),
+            rules,
+            overridden,
+        }
+    }
+
+    fn eval(&self, val: Option<&Value>, args: &Args) -> Result<(), Violations> {
+        args.eval(&self.name, &self.rules, self.overridden.as_ref(), val)
+    }
+
+    /// Reports the missing map values of the encoded embedded message.
+    fn check_sparse(&self, buf: &[u8], args: &Args) -> Result<(), Violations> {
+        let index = self.rules.iter().find_map(|rule| match rule {
+            Rule::Message(index) => Some(*index),
+            _ => None,
+        });
+        match index {
+            Some(index) => args.check_sparse(index, buf).map_err(|errs| {
+                errs.into_iter()
+                    .map(|err| Error::new(&self.name, message::Error::Message(Box::new(err))))
+                    .collect()
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Compiles the rules of a scalar value of the given kind.
+fn compile_scalar(kind: &Kind, r#type: &Type) -> Vec<Rule> {
+    match r#type {
+        Type::Bool(r) => compile_bool(*r).into_iter().map(Rule::Bool).collect(),
+        Type::String(r) => compile_string(r).into_iter().map(Rule::String).collect(),
+        Type::Bytes(r) => compile_bytes(r).into_iter().map(Rule::Bytes).collect(),
+        Type::Enum(r) => match kind {
+            Kind::Enum(desc) => compile_enum(desc, r).into_iter().map(Rule::Enum).collect(),
+            _ => Vec::new(),
+        },
+        r => compile_number(r).into_iter().map(Rule::Number).collect(),
+    }
+}
+
+/// An instruction of a message program.
+enum Instr {
+    /// Validates the value of a field, only if it is set for a field of a oneof.
+    Field {
+        field: FieldDescriptor,
+        rules: Rules,
+        oneof: bool,
+    },
+    /// Validates a repeated field, then its items.
+    List {
+        field: FieldDescriptor,
+        rules: Vec<ListRule>,
+        overridden: Option<Overridden>,
+        items: Option<Rules>,
+    },
+    /// Validates a map field, then its keys and values.
+    Map {
+        field: FieldDescriptor,
+        rules: Vec<MapRule>,
+        overridden: Option<Overridden>,
+        /// Whether the entries of the encoded message missing their message value are rejected.
+        no_sparse: bool,
+        keys: Option<Rules>,
+        values: Option<Rules>,
+    },
+    /// Checks that exactly one field of a required oneof is set.
+    Oneof(OneofDescriptor),
+    FieldExpressions(FieldExpressions),
+    MessageExpressions(MessageExpressions),
+    /// Reports the invalid rules of the message.
+    Invalid(Violations),
+}
+
+impl Instr {
+    /// Returns whether the instruction validates the field numbered `number`,
+    /// the invalid rules of the message being reported for all its fields.
+    fn validates(&self, number: u32) -> bool {
+        match self {
+            Self::Field { field, .. } | Self::List { field, .. } | Self::Map { field, .. } => {
+                field.number() == number
+            }
+            Self::Oneof(oneof) => oneof.fields().any(|field| field.number() == number),
+            Self::FieldExpressions(exprs) => exprs.field().number() == number,
+            Self::MessageExpressions(_) => false,
+            Self::Invalid(_) => true,
+        }
+    }
+
+    fn run(&self, msg: &DynamicMessage, args: &Args) -> Result<(), Violations> {
+        match self {
+            Self::Field {
+                field,
+                rules,
+                oneof,
+            } => {
+                let val = msg.get_field(field);
+                if *oneof && !is_set(&val) {
+                    return Ok(());
+                }
+                // an unset message is validated as a missing value
+                let val = match field.kind() {
+                    Kind::Message(_) if !is_set(&val) => None,
+                    _ => Some(&*val),
+                };
+                rules.eval(val, args)
+            }
+            Self::List {
+                field,
+                rules,
+                overridden,
+                items,
+            } => {
+                let val = msg.get_field(field);
+                let vals = val.as_list().unwrap_or_default();
+                let name = field.full_name();
+                let mut errs = Violations::default();
+                for rule in rules {
+                    let res = rule
+                        .check(name, vals)
+                        .map_err(|err| tag(overridden.as_ref(), err));
+                    if !args.check(res, &mut errs)? {
+                        return collected(errs, ());
+                    }
+                }
+                if let Some(items) = items {
+                    for (i, val) in vals.iter().enumerate() {
+                        let res = items.eval(Some(val), args).map_err(|errs| {
+                            errs.into_iter()
+                                .map(|e| Error::list_item(name, i, e))
+                                .collect::<Violations>()
+                        });
+                        args.check(res.map(|_| true), &mut errs)?;
+                    }
+                }
+                collected(errs, ())
+            }
+            Self::Map {
+                field,
+                rules,
+                overridden,
+                keys,
+                values,
+                ..
+            } => {
+                let val = msg.get_field(field);
+                let empty = HashMap::new();
+                let vals = val.as_map().unwrap_or(&empty);
+                let name = field.full_name();
+                let mut errs = Violations::default();
+                for rule in rules {
+                    let res = rule
+                        .check(name, vals)
+                        .map_err(|err| tag(overridden.as_ref(), err));
+                    if !args.check(res, &mut errs)? {
+                        return collected(errs, ());
+                    }
+                }
+                if let Some(keys) = keys {
+                    for k in vals.keys() {
+                        let val = Value::from(k.clone());
+                        let res = keys.eval(Some(&val), args).map_err(|errs| {
+                            errs.into_iter()
+                                .map(|e| Error::map_key(name, path_key(k), e))
+                                .collect::<Violations>()
+                        });
+                        args.check(res.map(|_| true), &mut errs)?;
+                    }
+                }
+                if let Some(values) = values {
+                    for (k, val) in vals {
+                        let res = values.eval(Some(val), args).map_err(|errs| {
+                            errs.into_iter()
+                                .map(|e| Error::map_value(name, path_key(k), e))
+                                .collect::<Violations>()
+                        });
+                        args.check(res.map(|_| true), &mut errs)?;
+                    }
+                }
+                collected(errs, ())
+            }
+            Self::Oneof(oneof) => {
+                let mut has = false;
+                for field in oneof.fields() {
+                    if is_set(&msg.get_field(&field)) {
+                        if has {
+                            return Err(format_err!(
+                                oneof.name(),
+                                "oneof contains multiple values",
+                            )
+                            .into());
+                        }
+                        has = true;
+                    }
+                }
+                if !has {
+                    return Err(Error::new(oneof.name(), message::Error::Required).into());
+                }
+                Ok(())
+            }
+            Self::FieldExpressions(exprs) => exprs.validate(msg, args.ctx, args.all),
+            Self::MessageExpressions(exprs) => exprs.validate(msg, args.ctx, args.all),
+            Self::Invalid(errs) => Err(errs.clone()),
+        }
+    }
+}
+
+impl Instr {
+    /// Reports the missing map values of the encoded field,
+    /// the `i`th occurrence of its number in the message.
+    fn check_sparse(&self, buf: &[u8], i: usize, args: &Args) -> Result<(), Violations> {
+        match self {
+            Self::Field { rules, .. } => rules.check_sparse(buf, args),
+            Self::List {
+                field,
+                items: Some(items),
+                ..
+            } => items.check_sparse(buf, args).map_err(|errs| {
+                errs.into_iter()
+                    .map(|e| Error::list_item(field.full_name(), i, e))
+                    .collect()
+            }),
+            Self::Map {
+                field,
+                overridden,
+                no_sparse,
+                values,
+                ..
+            } => {
+                let Some(entry) = field
+                    .kind()
+                    .as_message()
+                    .and_then(|desc| DynamicMessage::decode(desc.clone(), buf).ok())
+                else {
+                    return Ok(());
+                };
+                let Some(k) = entry
+                    .get_field_by_number(1)
+                    .and_then(|k| k.into_owned().into_map_key())
+                else {
+                    return Ok(());
+                };
+                let name = field.full_name();
+                let mut errs = Violations::default();
+                if *no_sparse && !entry.has_field_by_number(2) {
+                    let err = tag(overridden.as_ref(), Error::new(name, map::Error::NoSparse));
+                    let res = Err::<bool, _>(Error::map_value(name, path_key(&k), err));
+                    args.check(res, &mut errs)?;
+                }
+                if let Some(values) = values {
+                    for (_, buf) in delimited_fields(buf).filter(|(number, _)| *number == 2) {
+                        let res = values.check_sparse(buf, args).map_err(|errs| {
+                            errs.into_iter()
+                                .map(|e| Error::map_value(name, path_key(&k), e))
+                                .collect::<Violations>()
+                        });
+                        args.check(res.map(|_| true), &mut errs)?;
+                    }
+                }
+                collected(errs, ())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The instructions validating a message.
+#[derive(Default)]
+struct Program {
+    instrs: Vec<Instr>,
+    /// Whether the message or the ones it embeds have maps with the `no_sparse` rule,
+    /// computed the first time an encoded message is validated.
+    sparse: OnceLock<bool>,
+}
+
+impl Program {
+    fn new(instrs: Vec<Instr>) -> Self {
+        Self {
+            instrs,
+            sparse: OnceLock::new(),
+        }
+    }
+
+    /// Returns the indexes of the programs of the embedded messages.
+    fn deps(&self) -> impl Iterator<Item = usize> + '_ {
+        self.instrs
+            .iter()
+            .flat_map(|instr| match instr {
+                Instr::Field { rules, .. } => [Some(rules), None, None],
+                Instr::List { items, .. } => [items.as_ref(), None, None],
+                Instr::Map { keys, values, .. } => [keys.as_ref(), values.as_ref(), None],
+                _ => [None, None, None],
+            })
+            .flatten()
+            .flat_map(|rules| &rules.rules)
+            .filter_map(|rule| match rule {
+                Rule::Message(index) => Some(*index),
+                _ => None,
+            })
+    }
+
+    fn run(&self, msg: &DynamicMessage, args: &Args) -> Result<(), Violations> {
+        let mut errs = Violations::default();
+        for instr in &self.instrs {
+            args.check(instr.run(msg, args).map(|_| true), &mut errs)?;
+        }
+        collected(errs, ())
+    }
+
+    /// Runs the instructions validating the field numbered `number` only.
+    fn run_field(&self, msg: &DynamicMessage, number: u32, args: &Args) -> Result<(), Violations> {
+        let mut errs = Violations::default();
+        for instr in self.instrs.iter().filter(|instr| instr.validates(number)) {
+            args.check(instr.run(msg, args).map(|_| true), &mut errs)?;
+        }
+        collected(errs, ())
+    }
+
+    /// Reports the missing map values of the encoded message and of the ones it embeds.
+    fn check_sparse(&self, buf: &[u8], args: &Args) -> Result<(), Violations> {
+        let mut errs = Violations::default();
+        let mut counts = HashMap::<u32, usize>::new();
+        for (number, buf) in delimited_fields(buf) {
+            let count = counts.entry(number).or_default();
+            for instr in self.instrs.iter().filter(|instr| instr.validates(number)) {
+                let res = instr.check_sparse(buf, *count, args);
+                args.check(res.map(|_| true), &mut errs)?;
+            }
+            *count += 1;
+        }
+        collected(errs, ())
+    }
+}
+
+/// The validation programs of the compiled messages, indexed by message name.
+///
+/// The clones share the compiled programs.
+#[derive(Default, Clone)]
+pub(crate) struct Programs {
+    /// The index of the program of each message and the descriptor it was compiled from.
+    index: HashMap<String, (usize, MessageDescriptor)>,
+    programs: Vec<Arc<Program>>,
+    /// The overrides of the annotated rules the programs are compiled with.
+    overrides: Arc<Overrides>,
+}
+
+impl Programs {
+    /// Returns the index of the program of the message,
+    /// if it is compiled from the same definition.
+    pub(crate) fn get(&self, desc: &MessageDescriptor) -> Option<usize> {
+        match self.index.get(desc.full_name()) {
+            Some((index, compiled)) if same_definition(compiled, desc) => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Compiles the program of the message and of the messages it embeds,
+    /// returning its index.
+    ///
+    /// A message with invalid rules is compiled to a program reporting them,
+    /// so that the messages embedding it report them too.
+    pub(crate) fn compile(&mut self, desc: &MessageDescriptor) -> usize {
+        if let Some(index) = self.get(desc) {
+            return index;
+        }
+        // reserve the index before compiling, so that the recursive messages refer to it
+        let index = self.programs.len();
+        self.programs.push(Arc::default());
+        let _ = self
+            .index
+            .insert(desc.full_name().to_string(), (index, desc.clone()));
+        let program = self
+            .compile_program(desc)
+            .unwrap_or_else(|errs| Program::new(vec![Instr::Invalid(errs)]));
+        self.programs[index] = Arc::new(program);
+        index
+    }
+
+    /// Returns the programs without the ones of the messages defined differently in `pool`,
+    /// nor the ones embedding them, which are compiled again when needed.
+    ///
+    /// If `replace` is true, the pool replaces the one the programs were compiled from:
+    /// the programs of the messages missing from the pool are dropped too,
+    /// and the ones kept are bound to the descriptors of the pool.
+    pub(crate) fn invalidate(&self, pool: &DescriptorPool, replace: bool) -> Self {
+        self.retain(|name, desc| match pool.get_message_by_name(name) {
+            Some(new) if same_definition(desc, &new) => {
+                if replace {
+                    Some(new)
+                } else {
+                    Some(desc.clone())
+                }
+            }
+            None if !replace => Some(desc.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the programs compiled with the given overrides,
+    /// without the ones of the messages with fields overridden before or now,
+    /// nor the ones embedding them, which are compiled again when needed.
+    pub(crate) fn with_overrides(&self, overrides: Overrides) -> Self {
+        let mut programs = self.retain(|_, desc| {
+            let overridden = desc
+                .fields()
+                .any(|field| self.overrides.contains(&field) || overrides.contains(&field));
+            (!overridden).then(|| desc.clone())
+        });
+        programs.overrides = Arc::new(overrides);
+        programs
+    }
+
+    /// Returns whether the message of the program at `index`, or one it embeds,
+    /// has a map with the `no_sparse` rule.
+    fn sparse(&self, index: usize) -> bool {
+        let Some(program) = self.programs.get(index) else {
+            return false;
+        };
+        *program.sparse.get_or_init(|| {
+            let mut seen = HashSet::from([index]);
+            let mut stack = vec![index];
+            while let Some(i) = stack.pop() {
+                let Some(program) = self.programs.get(i) else {
+                    continue;
+                };
+                let sparse = program.instrs.iter().any(|instr| {
+                    matches!(
+                        instr,
+                        Instr::Map {
+                            no_sparse: true,
+                            ..
+                        }
+                    )
+                });
+                if sparse {
+                    return true;
+                }
+                stack.extend(program.deps().filter(|i| seen.insert(*i)));
+            }
+            false
+        })
+    }
+
+    /// Returns the overrides the programs are compiled with.
+    pub(crate) fn overrides(&self) -> &Overrides {
+        &self.overrides
+    }
+
+    /// Returns the programs of the messages for which `keep` returns the descriptor to bind them to,
+    /// without the programs embedding the messages dropped.
+    fn retain(&self, keep: impl Fn(&str, &MessageDescriptor) -> Option<MessageDescriptor>) -> Self {
+        let mut index = HashMap::new();
+        let mut stale = HashSet::new();
+        for (name, (i, desc)) in &self.index {
+            match keep(name, desc) {
+                Some(desc) => {
+                    let _ = index.insert(name.clone(), (*i, desc));
+                }
+                None => {
+                    stale.insert(*i);
+                }
+            }
+        }
+        // the messages embedding a stale message are stale too
+        loop {
+            let count = stale.len();
+            for (i, _) in index.values() {
+                if !stale.contains(i) && self.programs[*i].deps().any(|i| stale.contains(&i)) {
+                    stale.insert(*i);
+                }
+            }
+            if stale.len() == count {
+                break;
+            }
+        }
+        index.retain(|_, (i, _)| !stale.contains(i));
+        let programs = self
+            .programs
+            .iter()
+            .enumerate()
+            .map(|(i, program)| {
+                if stale.contains(&i) {
+                    Arc::default()
+                } else {
+                    program.clone()
+                }
+            })
+            .collect();
+        Self {
+            index,
+            programs,
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    fn compile_program(&mut self, desc: &MessageDescriptor) -> Result<Program, Violations> {
+        if desc.validation_disabled() || desc.validation_ignored() {
+            return Ok(Program::default());
+        }
+        let overrides = self.overrides.clone();
+        check_message(desc, &overrides)?;
+        let mut instrs = Vec::new();
+        let mut oneofs = HashSet::new();
+        for field in desc.fields() {
+            let rules = match overrides.rules(&field)? {
+                Some(r) => r,
+                None => continue,
+            };
+            if let Some(oneof) = field.containing_oneof() {
+                if !oneofs.insert(oneof.full_name().to_string()) {
+                    continue;
+                }
+                for field in oneof.fields() {
+                    let rules = match overrides.rules(&field)? {
+                        Some(r) => r,
+                        None => continue,
+                    };
+                    instrs.push(Instr::Field {
+                        rules: Rules::compile(self, &field, &rules, overrides.origin(&field)),
+                        field,
+                        oneof: true,
+                    });
+                }
+                if oneof.required() {
+                    instrs.push(Instr::Oneof(oneof));
+                }
+                continue;
+            }
+            let overridden = overrides.origin(&field);
+            if field.is_list() {
+                instrs.push(self.compile_list_field(field, &rules, overridden));
+                continue;
+            }
+            if field.is_map() {
+                instrs.push(self.compile_map_field(field, &rules, overridden));
+                continue;
+            }
+            instrs.push(Instr::Field {
+                rules: Rules::compile(self, &field, &rules, overridden),
+                field,
+                oneof: false,
+            });
+        }
+        // the CEL expressions are evaluated after the standard rules
+        for field in desc.fields() {
+            if let Some(exprs) = FieldExpressions::compile(&field)? {
+                instrs.push(Instr::FieldExpressions(exprs));
+            }
+        }
+        if let Some(exprs) = MessageExpressions::compile(desc)? {
+            instrs.push(Instr::MessageExpressions(exprs));
+        }
+        Ok(Program::new(instrs))
+    }
+
+    fn compile_list_field(
+        &mut self,
+        field: FieldDescriptor,
+        rules: &FieldRules,
+        overridden: Option<Overridden>,
+    ) -> Instr {
+        let kind = field.kind();
+        let (rules, items) = match &rules.r#type {
+            Some(Type::Repeated(rules)) => (
+                compile_list(&kind, rules),
+                match &rules.items {
+                    Some(items) => {
+                        let nested = overridden
+                            .as_ref()
+                            .and_then(|o| o.nested(["repeated", "items"]));
+                        Some(Rules::compile(self, &field, items, nested))
+                    }
+                    None => Rules::nested(self, &field, &kind),
+                },
+            ),
+            _ => (Vec::new(), Rules::nested(self, &field, &kind)),
+        };
+        Instr::List {
+            field,
+            rules,
+            overridden,
+            items,
+        }
+    }
+
+    fn compile_map_field(
+        &mut self,
+        field: FieldDescriptor,
+        rules: &FieldRules,
+        overridden: Option<Overridden>,
+    ) -> Instr {
+        let (key, value) = match field.kind().as_message() {
+            Some(entry) => (entry.map_entry_key_field(), entry.map_entry_value_field()),
+            None => {
+                return Instr::Invalid(
+                    format_err!(field.full_name(), "unexpected map field").into(),
+                )
+            }
+        };
+        let kind = value.kind();
+        let (rules, no_sparse, keys, values) = match &rules.r#type {
+            Some(Type::Map(rules)) => (
+                compile_map(rules),
+                rules.no_sparse() && kind.as_message().is_some(),
+                rules.keys.as_ref().map(|keys| {
+                    let nested = overridden.as_ref().and_then(|o| o.nested(["map", "keys"]));
+                    Rules::compile(self, &key, keys, nested)
+                }),
+                match &rules.values {
+                    Some(values) => {
+                        let nested = overridden
+                            .as_ref()
+                            .and_then(|o| o.nested(["map", "values"]));
+                        Some(Rules::compile(self, &value, values, nested))
+                    }
+                    None => Rules::nested(self, &field, &kind),
+                },
+            ),
+            _ => (Vec::new(), false, None, Rules::nested(self, &field, &kind)),
+        };
+        Instr::Map {
+            field,
+            rules,
+            overridden,
+            no_sparse,
+            keys,
+            values,
+        }
+    }
+}
+
+/// Returns whether the two descriptors define the message and the enums of its fields the same way,
+/// e.g. the same message in two versions of a descriptor set.
+fn same_definition(a: &MessageDescriptor, b: &MessageDescriptor) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.descriptor_proto() != b.descriptor_proto()
+        || a.parent_file().syntax() != b.parent_file().syntax()
+    {
+        return false;
+    }
+    let enums = |desc: &MessageDescriptor| {
+        desc.fields()
+            .flat_map(|field| match field.kind() {
+                // the map entries are nested messages, only the enums of their fields differ
+                Kind::Message(entry) if entry.is_map_entry() => {
+                    entry.fields().map(|field| field.kind()).collect()
+                }
+                kind => vec![kind],
+            })
+            .filter_map(|kind| kind.as_enum().cloned())
+            .collect::<Vec<_>>()
+    };
+    enums(a)
+        .iter()
+        .zip(enums(b).iter())
+        .all(|(a, b)| a.enum_descriptor_proto() == b.enum_descriptor_proto())
+}
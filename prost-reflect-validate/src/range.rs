@@ -0,0 +1,139 @@
+/// A comparison rule of an ordered value: a number, a duration or a timestamp.
+#[derive(Debug, Clone)]
+pub(crate) enum Range<T> {
+    Lt(T),
+    Lte(T),
+    Gt(T),
+    Gte(T),
+    /// The value must be in the range, e.g. `(gt, lt)` when `lt > gt`.
+    In {
+        start: T,
+        start_inclusive: bool,
+        end: T,
+        end_inclusive: bool,
+    },
+    /// The value must not be in the range, e.g. `[lt, gt]` when `lt <= gt`.
+    NotIn {
+        start: T,
+        start_inclusive: bool,
+        end: T,
+        end_inclusive: bool,
+    },
+}
+
+impl<T: PartialOrd + Copy> Range<T> {
+    /// Combines the `lt`, `lte`, `gt` and `gte` rules into a single comparison.
+    // reference implementation: https://github.com/bufbuild/protoc-gen-validate/blob/v1.1.0/templates/goshared/ltgt.go
+    pub(crate) fn compile(
+        lt: Option<T>,
+        lte: Option<T>,
+        gt: Option<T>,
+        gte: Option<T>,
+    ) -> Option<Self> {
+        let range = |start, start_inclusive, end, end_inclusive, within: bool| {
+            if within {
+                Self::In {
+                    start,
+                    start_inclusive,
+                    end,
+                    end_inclusive,
+                }
+            } else {
+                Self::NotIn {
+                    start: end,
+                    start_inclusive: !end_inclusive,
+                    end: start,
+                    end_inclusive: !start_inclusive,
+                }
+            }
+        };
+        let range = match (
+            lt.map(|v| (v, false)).or(lte.map(|v| (v, true))),
+            gt.map(|v| (v, false)).or(gte.map(|v| (v, true))),
+        ) {
+            (Some((end, end_inclusive)), Some((start, start_inclusive))) => {
+                range(start, start_inclusive, end, end_inclusive, end > start)
+            }
+            (Some((v, false)), None) => Self::Lt(v),
+            (Some((v, true)), None) => Self::Lte(v),
+            (None, Some((v, false))) => Self::Gt(v),
+            (None, Some((v, true))) => Self::Gte(v),
+            (None, None) => return None,
+        };
+        Some(range)
+    }
+
+    /// Returns whether the value breaks the rule.
+    pub(crate) fn rejects(&self, val: &T) -> bool {
+        match self {
+            Self::Lt(v) => val >= v,
+            Self::Lte(v) => val > v,
+            Self::Gt(v) => val <= v,
+            Self::Gte(v) => val < v,
+            Self::In {
+                start,
+                start_inclusive,
+                end,
+                end_inclusive,
+            } => {
+                let below = if *start_inclusive {
+                    val < start
+                } else {
+                    val <= start
+                };
+                let above = if *end_inclusive {
+                    val > end
+                } else {
+                    val >= end
+                };
+                below || above
+            }
+            Self::NotIn {
+                start,
+                start_inclusive,
+                end,
+                end_inclusive,
+            } => {
+                let after = if *start_inclusive {
+                    val >= start
+                } else {
+                    val > start
+                };
+                let before = if *end_inclusive {
+                    val <= end
+                } else {
+                    val < end
+                };
+                after && before
+            }
+        }
+    }
+}
+
+/// Returns the error of the failed comparison rule,
+/// e.g. `range_error!(int32, range)` for a `prost_validate::errors::int32::Error`.
+macro_rules! range_error {
+    ($module:ident, $range:expr) => {{
+        use prost_validate::errors::$module;
+        match $range {
+            Range::Lt(v) => $module::Error::Lt(*v),
+            Range::Lte(v) => $module::Error::Lte(*v),
+            Range::Gt(v) => $module::Error::Gt(*v),
+            Range::Gte(v) => $module::Error::Gte(*v),
+            Range::In {
+                start,
+                start_inclusive,
+                end,
+                end_inclusive,
+            } => $module::Error::in_range(*start_inclusive, *start, *end, *end_inclusive),
+            Range::NotIn {
+                start,
+                start_inclusive,
+                end,
+                end_inclusive,
+            } => $module::Error::not_in_range(*start_inclusive, *start, *end, *end_inclusive),
+        }
+    }};
+}
+
+pub(crate) use range_error;
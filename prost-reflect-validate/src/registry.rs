@@ -1,189 +1,495 @@
-use crate::field::make_validate_field;
-use crate::list::make_validate_list;
-use crate::map::make_validate_map;
-use crate::utils::{get_field_rules, is_set};
-use no_deadlocks::RwLock;
-use once_cell::sync::Lazy;
-use prost_reflect::{DynamicMessage, MessageDescriptor, OneofDescriptor, ReflectMessage};
-use prost_validate::{format_err, Result};
-use prost_validate_types::FieldRules;
-use prost_validate_types::{MessageRulesExt, OneofRulesExt};
+use crate::check::check_message;
+use crate::json::{deserialize, json_errors};
+use crate::overrides::Overrides;
+use crate::program::{collected, Args, Programs};
+use arc_swap::ArcSwap;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, MessageDescriptor, ReflectMessage, Value,
+};
+use prost_validate::errors;
+use prost_validate::{format_err, Context, Error, Result, Violations};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
 
-pub(crate) struct Args<'a> {
-    pub(crate) m: &'a HashMap<String, ValidationFn>,
-    pub(crate) msg: &'a DynamicMessage,
+fn transcode<T: ReflectMessage>(desc: MessageDescriptor, msg: &T) -> Result<DynamicMessage> {
+    let mut dynamic = DynamicMessage::new(desc);
+    dynamic.transcode_from(msg).map_err(|e| {
+        Error::new(
+            dynamic.descriptor().full_name(),
+            errors::Error::Decode(e.to_string()),
+        )
+    })?;
+    Ok(dynamic)
 }
 
-pub(crate) type ValidationFn = Arc<dyn Fn(&Args) -> Result<()> + Send + Sync>;
-pub(crate) type FieldValidationFn<T> =
-    Arc<dyn Fn(Option<T>, &FieldRules) -> Result<bool> + Send + Sync>;
-pub(crate) type NestedValidationFn<T> = Arc<
-    dyn Fn(Option<T>, &FieldRules, &HashMap<String, ValidationFn>) -> Result<bool> + Send + Sync,
->;
+/// Validates messages using the rules of their descriptors.
+///
+/// The rules of a message type are compiled into a validation program the first time
+/// a message of this type is validated, and cached in the registry,
+/// which can be cloned cheaply: the clones share the same cache.
+/// The cache is an immutable snapshot, replaced when new message types are compiled,
+/// so that validating a message never takes a lock.
+///
+/// A registry created with [`Registry::new`] is bound to a [`DescriptorPool`]:
+/// the messages are validated with the rules of the message of the same name in this pool,
+/// so that several pools defining the same messages with different rules can be used side by side.
+/// The pool can be replaced at runtime with [`Registry::reload`].
+/// The annotated rules can be overridden at runtime too, see [`Registry::set_overrides`].
+/// The [`Default`] registry, used by [`validate`](crate::validate) and [`ValidatorExt`](crate::ValidatorExt),
+/// uses the descriptors of the messages themselves and identifies them by their full name,
+/// compiling a message type again when it is validated with a different definition.
+#[derive(Default, Clone)]
+pub struct Registry {
+    state: Arc<ArcSwap<State>>,
+    /// Serializes the compilations of new message types and the reloads.
+    lock: Arc<Mutex<()>>,
+}
 
-pub(crate) static REGISTRY: Lazy<Registry> = Lazy::new(Registry::default);
+/// A snapshot of the pool of a registry and of the programs compiled from it.
+#[derive(Default)]
+struct State {
+    pool: Option<DescriptorPool>,
+    programs: Programs,
+}
 
-#[derive(Default, Clone)]
-pub(crate) struct Registry {
-    m: Arc<RwLock<HashMap<String, ValidationFn>>>,
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("pool", &self.state.load().pool)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Registry {
-    #[allow(clippy::unwrap_used)]
-    pub(crate) fn register(
+    /// Creates a registry validating the messages with the rules of the given pool.
+    pub fn new(pool: DescriptorPool) -> Self {
+        Self::from_state(State {
+            pool: Some(pool),
+            programs: Programs::default(),
+        })
+    }
+
+    fn from_state(state: State) -> Self {
+        Self {
+            state: Arc::new(ArcSwap::from_pointee(state)),
+            lock: Arc::default(),
+        }
+    }
+
+    /// Returns the descriptor pool of the registry, if it is bound to one.
+    pub fn pool(&self) -> Option<DescriptorPool> {
+        self.state.load().pool.clone()
+    }
+
+    /// Replaces the descriptor pool of the registry, e.g. when a new version of the descriptors is loaded.
+    ///
+    /// The rules of the new pool are checked first, as with [`Registry::compile_pool`]:
+    /// if some of them are invalid, the errors are returned and the registry keeps the current pool.
+    /// The validations in progress finish with the current pool, the next ones use the new pool.
+    /// The compiled validation of the messages whose definition did not change is kept,
+    /// the other messages are compiled again the first time they are validated.
+    ///
+    /// The overrides of the registry are kept, those of the fields missing from the new pool are ignored.
+    pub fn reload(&self, pool: DescriptorPool) -> Result<(), Violations> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = self.state.load();
+        check_pool(&pool, state.programs.overrides())?;
+        let programs = state.programs.invalidate(&pool, true);
+        self.state.store(Arc::new(State {
+            pool: Some(pool),
+            programs,
+        }));
+        Ok(())
+    }
+
+    /// Replaces the overrides of the rules annotated on the fields, see [`Overrides`].
+    ///
+    /// For a registry bound to a pool, the overridden fields must be defined in the pool
+    /// and the rules of their messages are checked with the overrides:
+    /// if some of them are invalid, the errors are returned and the current overrides are kept.
+    /// The [`Default`] registry reports the invalid overridden rules when validating their messages.
+    /// The messages with overridden fields are compiled again the first time they are validated.
+    ///
+    /// The errors of the rules set by the overrides have the [`Override`](prost_validate::RuleOrigin::Override) origin.
+    pub fn set_overrides(&self, overrides: Overrides) -> Result<(), Violations> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = self.state.load();
+        if let Some(pool) = &state.pool {
+            check_overrides(pool, &overrides)?;
+        }
+        let programs = state.programs.with_overrides(overrides);
+        self.state.store(Arc::new(State {
+            pool: state.pool.clone(),
+            programs,
+        }));
+        Ok(())
+    }
+
+    /// Validates the message, returning the first error found.
+    pub fn validate<T: ReflectMessage>(&self, msg: &T) -> Result<()> {
+        self.validate_with(msg, &Context::default())
+    }
+
+    /// Validates the message in the given context.
+    pub fn validate_with<T: ReflectMessage>(&self, msg: &T, ctx: &Context) -> Result<()> {
+        let msg = self.dynamic(msg)?;
+        self.validate_dynamic_with(&msg, ctx)
+    }
+
+    /// Validates the message, collecting all the violations instead of returning the first one.
+    pub fn validate_all<T: ReflectMessage>(&self, msg: &T) -> Result<(), Violations> {
+        self.validate_all_with(msg, &Context::default())
+    }
+
+    /// Validates the message in the given context, collecting all the violations.
+    pub fn validate_all_with<T: ReflectMessage>(
+        &self,
+        msg: &T,
+        ctx: &Context,
+    ) -> Result<(), Violations> {
+        let msg = self.dynamic(msg)?;
+        self.validate_all_dynamic_with(&msg, ctx)
+    }
+
+    /// Validates the dynamic message as is, without transcoding it first
+    /// unless it belongs to another pool than the registry one.
+    pub fn validate_dynamic(&self, msg: &DynamicMessage) -> Result<()> {
+        self.validate_dynamic_with(msg, &Context::default())
+    }
+
+    /// Validates the dynamic message in the given context.
+    pub fn validate_dynamic_with(&self, msg: &DynamicMessage, ctx: &Context) -> Result<()> {
+        self.run(msg, None, ctx, false, None).map_err(|errs| {
+            errs.into_iter()
+                .next()
+                .unwrap_or_else(|| format_err!(msg.descriptor().full_name(), "validation failed"))
+        })
+    }
+
+    /// Validates the dynamic message, collecting all the violations.
+    pub fn validate_all_dynamic(&self, msg: &DynamicMessage) -> Result<(), Violations> {
+        self.validate_all_dynamic_with(msg, &Context::default())
+    }
+
+    /// Validates the dynamic message in the given context, collecting all the violations.
+    pub fn validate_all_dynamic_with(
+        &self,
+        msg: &DynamicMessage,
+        ctx: &Context,
+    ) -> Result<(), Violations> {
+        self.run(msg, None, ctx, true, None)
+    }
+
+    /// Validates the message of type `desc` encoded in `buf`, decoding it only once.
+    ///
+    /// A message that cannot be decoded is reported as a [`Decode`](prost_validate::errors::Error::Decode) error.
+    /// The `no_sparse` rule of the maps is only checked here, on the encoded message:
+    /// prost decodes a missing message value as the default message.
+    pub fn validate_bytes(&self, desc: &MessageDescriptor, buf: &[u8]) -> Result<()> {
+        self.validate_bytes_with(desc, buf, &Context::default())
+    }
+
+    /// Validates the encoded message in the given context.
+    pub fn validate_bytes_with(
+        &self,
+        desc: &MessageDescriptor,
+        buf: &[u8],
+        ctx: &Context,
+    ) -> Result<()> {
+        let msg = self.decode(desc, buf)?;
+        self.run(&msg, Some(buf), ctx, false, None).map_err(|errs| {
+            errs.into_iter()
+                .next()
+                .unwrap_or_else(|| format_err!(desc.full_name(), "validation failed"))
+        })
+    }
+
+    /// Validates the encoded message, collecting all the violations.
+    pub fn validate_all_bytes(
+        &self,
+        desc: &MessageDescriptor,
+        buf: &[u8],
+    ) -> Result<(), Violations> {
+        self.validate_all_bytes_with(desc, buf, &Context::default())
+    }
+
+    /// Validates the encoded message in the given context, collecting all the violations.
+    pub fn validate_all_bytes_with(
         &self,
-        m: &mut HashMap<String, ValidationFn>,
         desc: &MessageDescriptor,
+        buf: &[u8],
+        ctx: &Context,
+    ) -> Result<(), Violations> {
+        let msg = self.decode(desc, buf)?;
+        self.run(&msg, Some(buf), ctx, true, None)
+    }
+
+    /// Validates the proto-JSON message of type `desc`, e.g. the body of a REST request.
+    ///
+    /// The paths of the errors use the JSON names of the fields,
+    /// so that [`FieldPath::to_json_pointer`](prost_validate::FieldPath::to_json_pointer)
+    /// points to the invalid value in the JSON document.
+    /// A message that cannot be deserialized is reported as a [`Decode`](prost_validate::errors::Error::Decode) error.
+    pub fn validate_json(&self, desc: &MessageDescriptor, json: &str) -> Result<()> {
+        self.validate_json_with(desc, json, &Context::default())
+    }
+
+    /// Validates the proto-JSON message in the given context.
+    pub fn validate_json_with(
+        &self,
+        desc: &MessageDescriptor,
+        json: &str,
+        ctx: &Context,
     ) -> Result<()> {
-        if m.get(desc.full_name()).is_some() {
-            return Ok(());
+        self.run_json(desc, json, ctx, false).map_err(|errs| {
+            errs.into_iter()
+                .next()
+                .unwrap_or_else(|| format_err!(desc.full_name(), "validation failed"))
+        })
+    }
+
+    /// Validates the proto-JSON message, collecting all the violations.
+    pub fn validate_all_json(
+        &self,
+        desc: &MessageDescriptor,
+        json: &str,
+    ) -> Result<(), Violations> {
+        self.validate_all_json_with(desc, json, &Context::default())
+    }
+
+    /// Validates the proto-JSON message in the given context, collecting all the violations.
+    pub fn validate_all_json_with(
+        &self,
+        desc: &MessageDescriptor,
+        json: &str,
+        ctx: &Context,
+    ) -> Result<(), Violations> {
+        self.run_json(desc, json, ctx, true)
+    }
+
+    fn run_json(
+        &self,
+        desc: &MessageDescriptor,
+        json: &str,
+        ctx: &Context,
+        all: bool,
+    ) -> Result<(), Violations> {
+        let desc = self
+            .state
+            .load()
+            .resolve(desc)?
+            .unwrap_or_else(|| desc.clone());
+        let msg = deserialize(desc.clone(), json)?;
+        self.run(&msg, None, ctx, all, None)
+            .map_err(|errs| json_errors(&desc, errs))
+    }
+
+    /// Validates a single value of the field before the whole message exists,
+    /// e.g. the input of a form, running only the rules of this field.
+    ///
+    /// The value is validated as if it was set in an otherwise empty message,
+    /// so that the rules of a required oneof are checked too, but not the CEL expressions of the message.
+    /// A value not matching the type of the field is reported as a [`Decode`](prost_validate::errors::Error::Decode) error.
+    pub fn validate_field(&self, field: &FieldDescriptor, value: &Value) -> Result<()> {
+        self.validate_field_with(field, value, &Context::default())
+    }
+
+    /// Validates a single value of the field in the given context.
+    pub fn validate_field_with(
+        &self,
+        field: &FieldDescriptor,
+        value: &Value,
+        ctx: &Context,
+    ) -> Result<()> {
+        let mut msg = DynamicMessage::new(field.parent_message().clone());
+        msg.try_set_field(field, value.clone())
+            .map_err(|e| Error::new(field.name(), errors::Error::Decode(e.to_string())))?;
+        self.run(&msg, None, ctx, false, Some(field.number()))
+            .map_err(|errs| {
+                errs.into_iter()
+                    .next()
+                    .unwrap_or_else(|| format_err!(field.name(), "validation failed"))
+            })
+    }
+
+    /// Returns the message as a dynamic message of the registry pool.
+    fn dynamic<T: ReflectMessage>(&self, msg: &T) -> Result<DynamicMessage> {
+        match self.state.load().resolve(&msg.descriptor())? {
+            Some(desc) => transcode(desc, msg),
+            None => Ok(msg.transcode_to_dynamic()),
         }
-        // insert a dummy validation to prevent recursion
-        let _ = m.insert(desc.full_name().to_string(), Arc::new(|_| Ok(())));
+    }
+
+    fn decode(&self, desc: &MessageDescriptor, buf: &[u8]) -> Result<DynamicMessage> {
+        let desc = self
+            .state
+            .load()
+            .resolve(desc)?
+            .unwrap_or_else(|| desc.clone());
+        let name = desc.full_name().to_string();
+        DynamicMessage::decode(desc, buf)
+            .map_err(|e| Error::new(name, errors::Error::Decode(e.to_string())))
+    }
 
-        if desc.validation_disabled() || desc.validation_ignored() {
-            let _ = m.insert(desc.full_name().to_string(), Arc::new(|_| Ok(())));
-            return Ok(());
+    /// Creates a registry for the given pool, building the validation of all its messages up front.
+    ///
+    /// All the invalid rules found in the pool are returned,
+    /// e.g. rules not matching the type of their field or patterns that are not valid regexes,
+    /// instead of being reported when the first message of the type is validated.
+    pub fn compile_pool(pool: &DescriptorPool) -> Result<Self, Violations> {
+        let mut programs = Programs::default();
+        for desc in check_pool(pool, programs.overrides())? {
+            programs.compile(&desc);
         }
-        let mut fns: Vec<ValidationFn> = Vec::new();
-        let mut oneofs: HashMap<String, Rc<OneofDescriptor>> = HashMap::new();
-        for field in desc.fields() {
-            let rules = match get_field_rules(&field)
-                .map_err(|e| format_err!(field.full_name(), "{}", e))?
-            {
-                Some(r) => r,
-                None => continue,
-            };
-            if oneofs.contains_key(field.full_name()) {
-                continue;
-            }
-            if let Some(ref desc) = field.containing_oneof() {
-                let desc = Rc::new(desc.clone());
-                for field in desc.fields() {
-                    let field = field.clone();
-                    oneofs.insert(field.full_name().to_string(), desc.clone());
-                    let rules = match get_field_rules(&field)
-                        .map_err(|e| format_err!(field.full_name(), "{}", e))?
-                    {
-                        Some(r) => r,
-                        None => continue,
-                    };
-                    let validate_field = make_validate_field(m, &field, &rules);
-                    fns.push(Arc::new(move |Args { msg, m }| {
-                        let val = msg.get_field(&field);
-                        if !is_set(&val) {
-                            return Ok(());
-                        }
-                        validate_field(val, &rules, m)?;
-                        Ok(())
-                    }));
-                }
-                let field = field.clone();
-                if desc.required() {
-                    fns.push(Arc::new(move |Args { msg, .. }| {
-                        let mut has = false;
-                        for field in field.containing_oneof().unwrap().fields() {
-                            let ok = is_set(&msg.get_field(&field));
-                            if ok {
-                                if has {
-                                    return Err(format_err!(
-                                        field.containing_oneof().unwrap().name(),
-                                        "oneof contains multiple values",
-                                    ));
-                                }
-                                has = true;
-                            }
-                        }
-                        if !has {
-                            return Err(format_err!(
-                                field.containing_oneof().unwrap().name(),
-                                "oneof does not contains any value",
-                            ));
-                        }
-                        Ok(())
-                    }))
-                }
-                continue;
-            }
-            if field.is_list() {
-                let validate_list = make_validate_list(m, &field, &rules);
-                fns.push(Arc::new(move |Args { msg, m }| {
-                    let v = msg.get_field(&field).as_list().map(|v| v.to_vec());
-                    for f in &validate_list {
-                        let v = v.clone();
-                        if !f(v, &rules, m)? {
-                            break;
-                        }
-                    }
-                    Ok(())
-                }));
-                continue;
-            }
-            if field.is_map() {
-                let validate_map = make_validate_map(m, &field, &rules);
-                fns.push(Arc::new(move |Args { msg, m }| {
-                    let v = msg.get_field(&field).as_map().map(|v| v.to_owned());
-                    for f in &validate_map {
-                        let v = v.clone();
-                        if !f(v, &rules, m)? {
-                            break;
-                        }
-                    }
-                    Ok(())
-                }));
-                continue;
-            }
-            let validate_field = make_validate_field(m, &field, &rules);
-            let field = field.clone();
-            fns.push(Arc::new(move |Args { msg, m }| {
-                let v = msg.get_field(&field);
-                validate_field(v, &rules, m)?;
-                Ok(())
-            }));
+        Ok(Self::from_state(State {
+            pool: Some(pool.clone()),
+            programs,
+        }))
+    }
+
+    /// Validates the message, or only its field numbered `field` if any,
+    /// and the sparse maps of the message encoded in `buf` if any.
+    fn run(
+        &self,
+        msg: &DynamicMessage,
+        buf: Option<&[u8]>,
+        ctx: &Context,
+        all: bool,
+        field: Option<u32>,
+    ) -> Result<(), Violations> {
+        let state = self.state.load();
+        let msg = state.borrowed(msg)?;
+        let desc = msg.descriptor();
+        if let Some(index) = state.programs.get(&desc) {
+            return state.validate(index, &msg, buf, ctx, all, field);
+        }
+        let state = self.insert(&state, &desc);
+        match state.programs.get(&desc) {
+            Some(index) => state.validate(index, &msg, buf, ctx, all, field),
+            None => Err(format_err!(desc.full_name(), "no validator").into()),
         }
-        let _ = m.insert(
-            desc.full_name().to_string(),
-            Arc::new(move |v| {
-                for f in &fns {
-                    f(v)?;
-                }
-                Ok(())
-            }),
-        );
-        Ok(())
     }
 
-    pub(crate) fn validate(&self, msg: &DynamicMessage) -> Result<()> {
-        {
-            #[allow(clippy::unwrap_used)]
-            let m = self.m.read().unwrap();
-            if let Some(f) = m.get(msg.descriptor().full_name()) {
-                f(&Args { msg, m: &m })?;
-                return Ok(());
-            }
+    /// Compiles the message type in a new snapshot of the programs.
+    fn insert(&self, state: &State, desc: &MessageDescriptor) -> Arc<State> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let current = self.state.load_full();
+        if current.pool != state.pool {
+            // the pool was reloaded meanwhile, the message is validated with the previous one
+            return Arc::new(state.compile(desc));
+        }
+        if current.programs.get(desc).is_some() {
+            return current;
+        }
+        let state = Arc::new(current.compile(desc));
+        self.state.store(state.clone());
+        state
+    }
+}
+
+impl State {
+    /// Returns the descriptor of the message of the same name in the pool,
+    /// if the state is bound to another pool than the one of `desc`.
+    fn resolve(&self, desc: &MessageDescriptor) -> Result<Option<MessageDescriptor>> {
+        match &self.pool {
+            Some(pool) if pool != desc.parent_pool() => pool
+                .get_message_by_name(desc.full_name())
+                .map(Some)
+                .ok_or_else(|| {
+                    format_err!(desc.full_name(), "message not found in the descriptor pool")
+                }),
+            _ => Ok(None),
         }
-        {
-            #[allow(clippy::unwrap_used)]
-            let mut m = self.m.write().unwrap();
-            let desc = msg.descriptor();
-            self.register(&mut m, &desc)?;
+    }
+
+    /// Returns the dynamic message, transcoded only if it belongs to another pool.
+    fn borrowed<'a>(&self, msg: &'a DynamicMessage) -> Result<Cow<'a, DynamicMessage>> {
+        match self.resolve(&msg.descriptor())? {
+            Some(desc) => transcode(desc, msg).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(msg)),
         }
-        self.validate(msg)
     }
 
-    pub(crate) fn do_validate(
+    /// Returns a new state with the program of the message.
+    fn compile(&self, desc: &MessageDescriptor) -> Self {
+        let mut programs = match &self.pool {
+            Some(_) => self.programs.clone(),
+            // the programs of the messages defined differently in the pool of the message are replaced
+            None => self.programs.invalidate(desc.parent_pool(), false),
+        };
+        programs.compile(desc);
+        Self {
+            pool: self.pool.clone(),
+            programs,
+        }
+    }
+
+    fn validate(
         &self,
+        index: usize,
         msg: &DynamicMessage,
-        m: &HashMap<String, ValidationFn>,
-    ) -> Result<()> {
-        if let Some(f) = m.get(msg.descriptor().full_name()) {
-            f(&Args { msg, m })?;
-            Ok(())
-        } else {
-            Err(format_err!(msg.descriptor().full_name(), "no validator",))
+        buf: Option<&[u8]>,
+        ctx: &Context,
+        all: bool,
+        field: Option<u32>,
+    ) -> Result<(), Violations> {
+        let args = Args {
+            programs: &self.programs,
+            all,
+            ctx,
+        };
+        let res = match field {
+            Some(number) => args.validate_field(index, msg, number),
+            None => args.validate(index, msg),
+        };
+        let mut errs = Violations::default();
+        args.check(res.map(|_| true), &mut errs)?;
+        if let Some(buf) = buf {
+            args.check(args.check_sparse(index, buf).map(|_| true), &mut errs)?;
+        }
+        collected(errs, ())
+    }
+}
+
+/// Checks the rules of all the messages of the pool, returning the messages to compile.
+fn check_pool(
+    pool: &DescriptorPool,
+    overrides: &Overrides,
+) -> Result<Vec<MessageDescriptor>, Violations> {
+    let messages = pool
+        .all_messages()
+        .filter(|desc| !desc.is_map_entry())
+        .collect::<Vec<_>>();
+    let mut errs = Violations::default();
+    for desc in &messages {
+        if let Err(err) = check_message(desc, overrides) {
+            errs.extend(err);
+        }
+    }
+    collected(errs, messages)
+}
+
+/// Checks that the overridden fields are defined in the pool, and the rules of their messages.
+fn check_overrides(pool: &DescriptorPool, overrides: &Overrides) -> Result<(), Violations> {
+    let mut errs = Violations::default();
+    let mut messages = HashMap::new();
+    for name in overrides.fields() {
+        let field = name.rsplit_once('.').and_then(|(message, field)| {
+            pool.get_message_by_name(message)?.get_field_by_name(field)
+        });
+        match field {
+            Some(field) => {
+                let desc = field.parent_message().clone();
+                let _ = messages.insert(desc.full_name().to_string(), desc);
+            }
+            None => errs.extend([format_err!(name, "field not found in the descriptor pool")]),
+        }
+    }
+    for desc in messages.values() {
+        if let Err(err) = check_message(desc, overrides) {
+            errs.extend(err);
         }
     }
+    collected(errs, ())
 }
@@ -1,388 +1,154 @@
-use crate::registry::FieldValidationFn;
-use prost_reflect::FieldDescriptor;
 use prost_validate::errors::string;
 use prost_validate::ValidateStringExt;
-use prost_validate::{format_err, Error};
-use prost_validate_types::field_rules::Type;
+use prost_validate::{Error, Result};
 use prost_validate_types::string_rules::WellKnown;
-use prost_validate_types::{FieldRules, KnownRegex, StringRules};
+use prost_validate_types::{KnownRegex, StringRules};
 use regex::Regex;
-use std::ops::Deref;
-use std::sync::Arc;
 
-macro_rules! string_rules {
-    ($rules:ident) => {
-        match &$rules.r#type {
-            Some(Type::String(rules)) => rules,
-            _ => return Err(format_err!("unexpected string rules")),
-        }
-    };
-}
-
-fn push<F>(fns: &mut Vec<FieldValidationFn<String>>, name: &Arc<String>, f: Arc<F>)
-where
-    F: Fn(String, &StringRules, &String) -> prost_validate::Result<bool> + Send + Sync + 'static,
-{
-    let name = name.clone();
-    fns.push(Arc::new(move |val, rules| {
-        let val = val.unwrap_or("".to_string());
-        let rules = string_rules!(rules);
-        f(val, rules, &name)
-    }))
+/// A compiled rule of a string value.
+#[derive(Debug, Clone)]
+pub(crate) enum StringRule {
+    IgnoreEmpty,
+    Const(String),
+    Len(usize),
+    MinLen(usize),
+    MaxLen(usize),
+    LenBytes(usize),
+    MinBytes(usize),
+    MaxBytes(usize),
+    /// The pattern, compiled once, or the error reported if it is not a valid regex.
+    Pattern(String, Result<Regex, String>),
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+    NotContains(String),
+    In(Vec<String>),
+    NotIn(Vec<String>),
+    Email,
+    Hostname,
+    Ip,
+    Ipv4,
+    Ipv6,
+    Uri,
+    UriRef,
+    Address,
+    Uuid,
+    HttpHeaderName(bool),
+    HttpHeaderValue(bool),
 }
 
-pub(crate) fn make_validate_string(
-    field: &FieldDescriptor,
-    rules: &FieldRules,
-) -> Vec<FieldValidationFn<String>> {
-    let mut fns: Vec<FieldValidationFn<String>> = Vec::new();
-    if !matches!(rules.r#type, Some(Type::String(_))) {
-        return fns;
-    }
-    let rules = match &rules.r#type {
-        Some(Type::String(rule)) => rule,
-        _ => return fns,
-    };
+pub(crate) fn compile_string(rules: &StringRules) -> Vec<StringRule> {
+    let mut out = Vec::new();
     if rules.ignore_empty() {
-        fns.push(Arc::new(|val, _| {
-            Ok(val.map(|v| !v.is_empty()).unwrap_or(false))
-        }));
+        out.push(StringRule::IgnoreEmpty);
     }
-    let name = Arc::new(field.full_name().to_string());
-    if rules.r#const.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.r#const();
-                if val != v {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::Const(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.r#const {
+        out.push(StringRule::Const(v.clone()));
     }
-    if rules.len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.len() as usize;
-                if val.chars().count() != v {
-                    return Err(Error::new(name.to_string(), string::Error::Len(v)));
-                }
-                Ok(true)
-            }),
-        )
+    if let Some(v) = rules.len {
+        out.push(StringRule::Len(v as usize));
     }
-    if rules.min_len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.min_len() as usize;
-                if val.chars().count() < v {
-                    return Err(Error::new(name.to_string(), string::Error::MinLen(v)));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.min_len {
+        out.push(StringRule::MinLen(v as usize));
     }
-    if rules.max_len.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.max_len() as usize;
-                if val.chars().count() > v {
-                    return Err(Error::new(name.to_string(), string::Error::MaxLen(v)));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.max_len {
+        out.push(StringRule::MaxLen(v as usize));
     }
-    if rules.len_bytes.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.len_bytes() as usize;
-                if val.len() != v {
-                    return Err(Error::new(name.to_string(), string::Error::LenBytes(v)));
-                }
-                Ok(true)
-            }),
-        )
+    if let Some(v) = rules.len_bytes {
+        out.push(StringRule::LenBytes(v as usize));
     }
-    if rules.min_bytes.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.min_bytes() as usize;
-                if val.len() < v {
-                    return Err(Error::new(name.to_string(), string::Error::MinLenBytes(v)));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.min_bytes {
+        out.push(StringRule::MinBytes(v as usize));
     }
-    if rules.max_bytes.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.max_bytes() as usize;
-                if val.len() > v {
-                    return Err(Error::new(name.to_string(), string::Error::MaxLenBytes(v)));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = rules.max_bytes {
+        out.push(StringRule::MaxBytes(v as usize));
     }
     if let Some(v) = &rules.pattern {
-        let regex = Regex::new(v.as_str());
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.pattern();
-                let regex = match &regex {
-                    Ok(r) => r,
-                    Err(err) => {
-                        return Err(Error::new(
-                            name.to_string(),
-                            string::Error::Pattern(err.to_string()),
-                        ))
-                    }
-                };
-                if !regex.is_match(val.as_str()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::Pattern(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+        let regex = Regex::new(v).map_err(|err| err.to_string());
+        out.push(StringRule::Pattern(v.clone(), regex));
     }
-    if rules.prefix.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.prefix();
-                if !val.as_str().starts_with(v) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::Prefix(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.prefix {
+        out.push(StringRule::Prefix(v.clone()));
     }
-    if rules.suffix.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.suffix();
-                if !val.as_str().ends_with(v) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::Suffix(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.suffix {
+        out.push(StringRule::Suffix(v.clone()));
     }
-    if rules.contains.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.contains();
-                if !val.contains(v) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::Contains(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.contains {
+        out.push(StringRule::Contains(v.clone()));
     }
-    if rules.not_contains.is_some() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.not_contains();
-                if val.contains(v) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::NotContains(v.to_string()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
+    if let Some(v) = &rules.not_contains {
+        out.push(StringRule::NotContains(v.clone()));
     }
     if !rules.r#in.is_empty() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.r#in.deref();
-                if !v.contains(&val.to_string()) {
-                    return Err(Error::new(name.to_string(), string::Error::In(v.to_vec())));
-                }
-                Ok(true)
-            }),
-        );
+        out.push(StringRule::In(rules.r#in.clone()));
     }
     if !rules.not_in.is_empty() {
-        push(
-            &mut fns,
-            &name,
-            Arc::new(move |val: String, rules: &StringRules, name: &String| {
-                let v = rules.not_in.deref();
-                if v.contains(&val.to_string()) {
-                    return Err(Error::new(
-                        name.to_string(),
-                        string::Error::NotIn(v.to_vec()),
-                    ));
-                }
-                Ok(true)
-            }),
-        );
-    }
-    if rules.well_known.is_none() {
-        return fns;
+        out.push(StringRule::NotIn(rules.not_in.clone()));
     }
     let strict = rules.strict();
-    #[allow(clippy::unwrap_used)]
-    match rules.well_known.unwrap() {
-        WellKnown::Email(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    let val = val.unwrap_or("".to_string());
-                    if val.validate_email().is_err() {
-                        return Err(Error::new(name.to_string(), string::Error::Email));
-                    }
-                    Ok(true)
-                }));
-            }
-        }
-        WellKnown::Hostname(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    let val = val.unwrap_or("".to_string());
-                    if val.validate_hostname().is_err() {
-                        return Err(Error::new(name.to_string(), string::Error::Hostname));
-                    }
-                    Ok(true)
-                }));
-            }
-        }
-        WellKnown::Ip(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_ip() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Ip)),
-                    }
-                }));
-            }
-        }
-        WellKnown::Ipv4(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_ipv4() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Ipv4)),
-                    }
-                }));
-            }
-        }
-        WellKnown::Ipv6(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_ipv6() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Ipv6)),
-                    }
-                }));
-            }
-        }
-        WellKnown::Uri(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_uri() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Uri)),
-                    }
-                }));
-            }
-        }
-        WellKnown::UriRef(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_uri_ref() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::UriRef)),
-                    }
-                }));
+    let rule = match rules.well_known {
+        Some(WellKnown::Email(true)) => StringRule::Email,
+        Some(WellKnown::Hostname(true)) => StringRule::Hostname,
+        Some(WellKnown::Ip(true)) => StringRule::Ip,
+        Some(WellKnown::Ipv4(true)) => StringRule::Ipv4,
+        Some(WellKnown::Ipv6(true)) => StringRule::Ipv6,
+        Some(WellKnown::Uri(true)) => StringRule::Uri,
+        Some(WellKnown::UriRef(true)) => StringRule::UriRef,
+        Some(WellKnown::Address(true)) => StringRule::Address,
+        Some(WellKnown::Uuid(true)) => StringRule::Uuid,
+        Some(WellKnown::WellKnownRegex(v)) => match KnownRegex::try_from(v) {
+            Ok(KnownRegex::HttpHeaderName) => StringRule::HttpHeaderName(strict),
+            Ok(KnownRegex::HttpHeaderValue) => StringRule::HttpHeaderValue(strict),
+            _ => return out,
+        },
+        _ => return out,
+    };
+    out.push(rule);
+    out
+}
+
+impl StringRule {
+    pub(crate) fn check(&self, name: &str, val: &str) -> Result<bool> {
+        let err = match self {
+            Self::IgnoreEmpty => return Ok(!val.is_empty()),
+            Self::Const(v) if val != v => string::Error::Const(v.clone()),
+            Self::Len(v) if val.chars().count() != *v => string::Error::Len(*v),
+            Self::MinLen(v) if val.chars().count() < *v => string::Error::MinLen(*v),
+            Self::MaxLen(v) if val.chars().count() > *v => string::Error::MaxLen(*v),
+            Self::LenBytes(v) if val.len() != *v => string::Error::LenBytes(*v),
+            Self::MinBytes(v) if val.len() < *v => string::Error::MinLenBytes(*v),
+            Self::MaxBytes(v) if val.len() > *v => string::Error::MaxLenBytes(*v),
+            Self::Pattern(_, Err(err)) => string::Error::Pattern(err.clone()),
+            Self::Pattern(v, Ok(regex)) if !regex.is_match(val) => {
+                string::Error::Pattern(v.clone())
             }
-        }
-        WellKnown::Address(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    let val = val.unwrap_or("".to_string());
-                    if let Ok(()) = val.validate_ip() {
-                        return Ok(true);
-                    }
-                    match val.validate_hostname() {
-                        Ok(()) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Address)),
-                    }
-                }));
+            Self::Prefix(v) if !val.starts_with(v.as_str()) => string::Error::Prefix(v.clone()),
+            Self::Suffix(v) if !val.ends_with(v.as_str()) => string::Error::Suffix(v.clone()),
+            Self::Contains(v) if !val.contains(v.as_str()) => string::Error::Contains(v.clone()),
+            Self::NotContains(v) if val.contains(v.as_str()) => {
+                string::Error::NotContains(v.clone())
             }
-        }
-        WellKnown::Uuid(v) => {
-            if v {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_uuid() {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::Uuid)),
-                    }
-                }));
+            Self::In(v) if !v.iter().any(|s| s == val) => string::Error::In(v.clone()),
+            Self::NotIn(v) if v.iter().any(|s| s == val) => string::Error::NotIn(v.clone()),
+            Self::Email if val.validate_email().is_err() => string::Error::Email,
+            Self::Hostname if val.validate_hostname().is_err() => string::Error::Hostname,
+            Self::Ip if val.validate_ip().is_err() => string::Error::Ip,
+            Self::Ipv4 if val.validate_ipv4().is_err() => string::Error::Ipv4,
+            Self::Ipv6 if val.validate_ipv6().is_err() => string::Error::Ipv6,
+            Self::Uri if val.validate_uri().is_err() => string::Error::Uri,
+            Self::UriRef if val.validate_uri_ref().is_err() => string::Error::UriRef,
+            Self::Address if val.validate_ip().is_err() && val.validate_hostname().is_err() => {
+                string::Error::Address
             }
-        }
-        WellKnown::WellKnownRegex(v) => match KnownRegex::try_from(v) {
-            Ok(KnownRegex::HttpHeaderName) => {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_header_name(strict) {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::HttpHeaderName)),
-                    }
-                }));
+            Self::Uuid if val.validate_uuid().is_err() => string::Error::Uuid,
+            Self::HttpHeaderName(strict) if val.validate_header_name(*strict).is_err() => {
+                string::Error::HttpHeaderName
             }
-            Ok(KnownRegex::HttpHeaderValue) => {
-                fns.push(Arc::new(move |val, _| {
-                    match val.unwrap_or("".to_string()).validate_header_value(strict) {
-                        Ok(_) => Ok(true),
-                        Err(_) => Err(Error::new(name.to_string(), string::Error::HttpHeaderValue)),
-                    }
-                }));
+            Self::HttpHeaderValue(strict) if val.validate_header_value(*strict).is_err() => {
+                string::Error::HttpHeaderValue
             }
-            _ => {}
-        },
+            _ => return Ok(true),
+        };
+        Err(Error::new(name, err))
     }
-    fns
 }
@@ -0,0 +1,563 @@
+//! A [`tower`](https://docs.rs/tower) middleware validating the gRPC requests of any service
+//! from its descriptors only, e.g. in a generic proxy without generated Rust types.
+//!
+//! The [`ValidationLayer`] resolves the `/package.Service/Method` path of a request
+//! to the input message of the method in its [`DescriptorPool`], de-frames the gRPC
+//! length-prefixed messages of the body and validates them with a [`Registry`] bound to the pool.
+//! An invalid request is rejected with an `InvalidArgument` status carrying the `BadRequest` details.
+//!
+//! The requests of the methods missing from the pool are forwarded as is.
+//! The message of the unary and server streaming methods is validated before calling the inner service,
+//! which never sees an invalid request: the layer answers with a trailers-only error response.
+//! The client streams are validated as the body streams, so that they reach the inner service
+//! message by message, and the body ends with the error status of the first invalid message:
+//! the generated `tonic` servers return it from the request stream, while a proxy forwarding
+//! the body as is has already forwarded the messages before it.
+//! The messages longer than the [maximum size](ValidationLayer::with_max_message_size) are rejected
+//! with a `ResourceExhausted` status, the compressed ones with an `Unimplemented` status
+//! as they cannot be decoded, and the bodies ending with a truncated message with an `InvalidArgument` status.
+//!
+//! ```rust
+//! use example_proto::ExampleMessage;
+//! use prost_reflect::ReflectMessage;
+//! use prost_reflect_validate::tower::ValidationLayer;
+//!
+//! let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+//! let layer = ValidationLayer::new(pool);
+//! ```
+
+use crate::Registry;
+use bytes::{Buf, Bytes, BytesMut};
+use http_body::Frame;
+use http_body_util::BodyExt;
+use prost_reflect::{DescriptorPool, MessageDescriptor, MethodDescriptor};
+use prost_validate::Context;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Poll};
+use tonic::body::Body;
+use tonic::Status;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The length of the header of a gRPC message: the compression flag and the length of the message.
+const HEADER_LEN: usize = 5;
+
+/// The default maximum size of a message, the default decoding limit of `tonic`.
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// A [`Layer`] validating the gRPC requests of the wrapped services
+/// with the rules of a descriptor pool.
+#[derive(Debug, Clone)]
+pub struct ValidationLayer {
+    registry: Registry,
+    ctx: Context,
+    max_message_size: usize,
+}
+
+impl ValidationLayer {
+    /// Creates a new layer validating the requests of the services of the pool.
+    pub fn new(pool: DescriptorPool) -> Self {
+        Self {
+            registry: Registry::new(pool),
+            ctx: Context::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Returns the layer validating the messages in the given context.
+    pub fn with_context(self, ctx: Context) -> Self {
+        Self { ctx, ..self }
+    }
+
+    /// Returns the layer rejecting the messages longer than `max` bytes, 4 MiB by default.
+    ///
+    /// It bounds the memory used to buffer a message before validating it.
+    pub fn with_max_message_size(self, max: usize) -> Self {
+        Self {
+            max_message_size: max,
+            ..self
+        }
+    }
+
+    /// Returns the registry validating the requests, shared by the services of the layer,
+    /// e.g. to [`reload`](Registry::reload) its pool or [override](Registry::set_overrides) its rules.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+}
+
+impl<S> Layer<S> for ValidationLayer {
+    type Service = ValidationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidationService {
+            inner,
+            registry: self.registry.clone(),
+            ctx: self.ctx.clone(),
+            max_message_size: self.max_message_size,
+        }
+    }
+}
+
+/// The service created by the [`ValidationLayer`].
+#[derive(Debug, Clone)]
+pub struct ValidationService<S> {
+    inner: S,
+    registry: Registry,
+    ctx: Context,
+    max_message_size: usize,
+}
+
+impl<S, B> Service<http::Request<B>> for ValidationService<S>
+where
+    S: Service<http::Request<Body>, Response = http::Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = http::Response<Body>;
+    type Error = S::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let Some(method) = method(&self.registry, req.uri().path()) else {
+            return Box::pin(self.inner.call(req.map(Body::new)));
+        };
+        let (parts, body) = req.into_parts();
+        let mut body = ValidatingBody {
+            inner: Body::new(body),
+            registry: self.registry.clone(),
+            desc: method.input(),
+            ctx: self.ctx.clone(),
+            max_message_size: self.max_message_size,
+            buf: BytesMut::new(),
+            peeked: None,
+            done: false,
+        };
+        if method.is_client_streaming() {
+            let req = http::Request::from_parts(parts, Body::new(body));
+            return Box::pin(self.inner.call(req));
+        }
+        // the inner service is ready, a clone takes its place for the next requests
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            match body.frame().await {
+                Some(Err(status)) => return Ok(status.into_http()),
+                frame => body.peeked = frame.and_then(Result::ok),
+            }
+            inner
+                .call(http::Request::from_parts(parts, Body::new(body)))
+                .await
+        })
+    }
+}
+
+/// Returns the method at `path`, e.g. `/package.Service/Method`.
+fn method(registry: &Registry, path: &str) -> Option<MethodDescriptor> {
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    registry
+        .pool()?
+        .get_service_by_name(service)?
+        .methods()
+        .find(|m| m.name() == method)
+}
+
+/// A request body yielding the gRPC length-prefixed messages of the inner body once validated.
+///
+/// It ends with the error status of the first invalid message.
+struct ValidatingBody {
+    inner: Body,
+    registry: Registry,
+    desc: MessageDescriptor,
+    ctx: Context,
+    max_message_size: usize,
+    /// The bytes of the inner body not yet yielded.
+    buf: BytesMut,
+    /// The first frame, polled before calling the inner service.
+    peeked: Option<Frame<Bytes>>,
+    done: bool,
+}
+
+impl ValidatingBody {
+    /// Returns the next buffered message once validated, if it is complete.
+    fn next_message(&mut self) -> Result<Option<Bytes>, Status> {
+        if self.buf.len() < HEADER_LEN {
+            return Ok(None);
+        }
+        let mut header = &self.buf[..HEADER_LEN];
+        let compressed = header.get_u8() != 0;
+        let len = header.get_u32() as usize;
+        if compressed {
+            return Err(Status::unimplemented(
+                "compressed messages cannot be validated",
+            ));
+        }
+        if len > self.max_message_size {
+            return Err(Status::resource_exhausted(format!(
+                "message length too large: found {} bytes, the limit is: {} bytes",
+                len, self.max_message_size
+            )));
+        }
+        if self.buf.len() < HEADER_LEN + len {
+            self.buf.reserve(HEADER_LEN + len - self.buf.len());
+            return Ok(None);
+        }
+        let frame = self.buf.split_to(HEADER_LEN + len).freeze();
+        self.registry
+            .validate_all_bytes_with(&self.desc, &frame[HEADER_LEN..], &self.ctx)?;
+        Ok(Some(frame))
+    }
+}
+
+impl http_body::Body for ValidatingBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        if let Some(frame) = this.peeked.take() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            match this.next_message() {
+                Ok(Some(frame)) => return Poll::Ready(Some(Ok(Frame::data(frame)))),
+                Ok(None) => {}
+                Err(status) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+            }
+            match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => this.buf.extend_from_slice(&data),
+                    // the trailers end the body, after a truncated message
+                    Err(_) if !this.buf.is_empty() => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(Status::invalid_argument(
+                            "invalid gRPC message frame",
+                        ))));
+                    }
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(status)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+                None => {
+                    this.done = true;
+                    if this.buf.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Err(Status::invalid_argument(
+                        "invalid gRPC message frame",
+                    ))));
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done && self.peeked.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use example_proto::example_service_server::{ExampleService, ExampleServiceServer};
+    use example_proto::ExampleMessage;
+    use http_body_util::{BodyExt, Full};
+    use prost::Message;
+    use prost_reflect::ReflectMessage;
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::future::Ready;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tonic_types::StatusExt;
+
+    const METHOD: &str = "/validate.example.ExampleService/ExampleMethod";
+    const STREAM: &str = "/validate.example.ExampleService/ExampleStream";
+
+    struct Echo;
+
+    #[tonic::async_trait]
+    impl ExampleService for Echo {
+        async fn example_method(
+            &self,
+            req: tonic::Request<ExampleMessage>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            Ok(tonic::Response::new(req.into_inner()))
+        }
+
+        /// Returns the first message of the stream without waiting for the end of the stream.
+        async fn example_stream(
+            &self,
+            req: tonic::Request<tonic::Streaming<ExampleMessage>>,
+        ) -> Result<tonic::Response<ExampleMessage>, tonic::Status> {
+            let msg = req.into_inner().message().await?.unwrap_or_default();
+            Ok(tonic::Response::new(msg))
+        }
+    }
+
+    /// A request body streaming the frames sent to the channel.
+    struct ChannelBody(mpsc::UnboundedReceiver<Vec<u8>>);
+
+    impl http_body::Body for ChannelBody {
+        type Data = Bytes;
+        type Error = Status;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Status>>> {
+            self.0
+                .poll_recv(cx)
+                .map(|buf| buf.map(|buf| Ok(Frame::data(Bytes::from(buf)))))
+        }
+    }
+
+    /// A request body yielding the given frames.
+    struct FramesBody(VecDeque<Frame<Bytes>>);
+
+    impl http_body::Body for FramesBody {
+        type Data = Bytes;
+        type Error = Status;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Status>>> {
+            Poll::Ready(self.0.pop_front().map(Ok))
+        }
+    }
+
+    /// A generic proxy answering with the request body as is, recording whether it was called.
+    #[derive(Clone, Default)]
+    struct Proxy(Arc<AtomicBool>);
+
+    impl Service<http::Request<Body>> for Proxy {
+        type Response = http::Response<Body>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+            self.0.store(true, Ordering::SeqCst);
+            std::future::ready(Ok(http::Response::new(req.into_body())))
+        }
+    }
+
+    /// Returns the gRPC frame of a message.
+    fn frame(content: &str) -> Vec<u8> {
+        let msg = ExampleMessage {
+            content: content.to_owned(),
+        }
+        .encode_to_vec();
+        let mut buf = vec![0];
+        buf.extend((msg.len() as u32).to_be_bytes());
+        buf.extend(msg);
+        buf
+    }
+
+    #[allow(clippy::unwrap_used)]
+    fn request(path: &str, body: Vec<u8>) -> http::Request<Full<Bytes>> {
+        grpc_request(path, Full::new(Bytes::from(body)))
+    }
+
+    #[allow(clippy::unwrap_used)]
+    fn grpc_request<B>(path: &str, body: B) -> http::Request<B> {
+        http::Request::builder()
+            .method("POST")
+            .uri(path)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(body)
+            .unwrap()
+    }
+
+    /// Returns the status of a response, either trailers-only or in the trailers of its body.
+    #[allow(clippy::unwrap_used)]
+    async fn status(res: http::Response<Body>) -> tonic::Status {
+        if let Some(status) = tonic::Status::from_header_map(res.headers()) {
+            return status;
+        }
+        let body = res.into_body().collect().await.unwrap();
+        tonic::Status::from_header_map(body.trailers().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validate_grpc_requests() {
+        let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+        let mut svc = ValidationLayer::new(pool).layer(ExampleServiceServer::new(Echo));
+
+        let res = svc
+            .call(request(METHOD, frame("Hello, world!")))
+            .await
+            .unwrap();
+        assert!(tonic::Status::from_header_map(res.headers()).is_none());
+        let body = res.into_body().collect().await.unwrap();
+        let trailers = body.trailers().unwrap();
+        assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+
+        let res = svc.call(request(METHOD, frame(""))).await.unwrap();
+        // the error is returned before calling the server, without a body
+        assert!(tonic::Status::from_header_map(res.headers()).is_some());
+        let status = status(res).await;
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let details = status.get_error_details();
+        let f = &details.bad_request().unwrap().field_violations;
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].field, "content");
+
+        // the methods missing from the pool are forwarded to the server
+        let res = svc
+            .call(request(
+                "/validate.example.ExampleService/Missing",
+                frame(""),
+            ))
+            .await
+            .unwrap();
+        let status = tonic::Status::from_header_map(res.headers()).unwrap();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validate_proxied_requests() {
+        let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+        let proxy = Proxy::default();
+        let mut svc = ValidationLayer::new(pool).layer(proxy.clone());
+
+        // the invalid requests never reach the proxy
+        let res = svc.call(request(METHOD, frame(""))).await.unwrap();
+        let status = tonic::Status::from_header_map(res.headers()).unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(!proxy.0.load(Ordering::SeqCst));
+
+        let res = svc
+            .call(request(METHOD, frame("Hello, world!")))
+            .await
+            .unwrap();
+        assert!(proxy.0.load(Ordering::SeqCst));
+        let buf = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(buf, frame("Hello, world!"));
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_invalid_frames() {
+        let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+        let mut svc = ValidationLayer::new(pool).layer(ExampleServiceServer::new(Echo));
+
+        let mut buf = frame("Hello, world!");
+        let truncated = request(METHOD, buf[..buf.len() - 1].to_vec());
+        let res = svc.call(truncated).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::InvalidArgument);
+
+        // the trailers cannot end the body in the middle of a message
+        for path in [METHOD, STREAM] {
+            let body = FramesBody(VecDeque::from([
+                Frame::data(Bytes::copy_from_slice(&buf[..buf.len() - 1])),
+                Frame::trailers(http::HeaderMap::new()),
+            ]));
+            let res = svc.call(grpc_request(path, body)).await.unwrap();
+            assert_eq!(status(res).await.code(), tonic::Code::InvalidArgument);
+        }
+
+        buf[0] = 1;
+        let res = svc.call(request(METHOD, buf)).await.unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_max_message_size() {
+        let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+        let layer = ValidationLayer::new(pool);
+        let len = ExampleMessage {
+            content: "Hello, world!".to_owned(),
+        }
+        .encoded_len();
+
+        let mut svc = layer
+            .clone()
+            .with_max_message_size(len)
+            .layer(ExampleServiceServer::new(Echo));
+        let res = svc
+            .call(request(METHOD, frame("Hello, world!")))
+            .await
+            .unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::Ok);
+
+        let mut svc = layer
+            .with_max_message_size(len - 1)
+            .layer(ExampleServiceServer::new(Echo));
+        let res = svc
+            .call(request(METHOD, frame("Hello, world!")))
+            .await
+            .unwrap();
+        assert_eq!(status(res).await.code(), tonic::Code::ResourceExhausted);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validate_client_streams() {
+        let pool = ExampleMessage::default().descriptor().parent_pool().clone();
+        let mut svc = ValidationLayer::new(pool).layer(ExampleServiceServer::new(Echo));
+
+        // the first message reaches the method while the stream is still open
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(frame("Hello, world!")).unwrap();
+        let res = tokio::time::timeout(
+            Duration::from_secs(5),
+            svc.call(grpc_request(STREAM, ChannelBody(rx))),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let body = res.into_body().collect().await.unwrap();
+        assert_eq!(body.trailers().unwrap().get("grpc-status").unwrap(), "0");
+        let buf = body.to_bytes();
+        let msg = ExampleMessage::decode(&buf[HEADER_LEN..]).unwrap();
+        assert_eq!(msg.content, "Hello, world!");
+        drop(tx);
+
+        // an invalid message ends the stream with its error
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(frame("")).unwrap();
+        let res = tokio::time::timeout(
+            Duration::from_secs(5),
+            svc.call(grpc_request(STREAM, ChannelBody(rx))),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let status = status(res).await;
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let details = status.get_error_details();
+        let f = &details.bad_request().unwrap().field_violations;
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].field, "content");
+        drop(tx);
+    }
+}
@@ -1,4 +1,4 @@
-use prost_reflect::{FieldDescriptor, Value};
+use prost_reflect::{DynamicMessage, FieldDescriptor, Value};
 use prost_validate::format_err;
 use prost_validate_types::{FieldRules, FieldRulesExt};
 use std::borrow::Cow;
@@ -24,3 +24,17 @@ pub(crate) fn get_field_rules(
     };
     Ok(Some(Arc::new(rules)))
 }
+
+/// Returns the `seconds` and `nanos` fields of a `google.protobuf.Duration`
+/// or `google.protobuf.Timestamp` message, read in place without transcoding it.
+pub(crate) fn seconds_nanos(msg: &DynamicMessage) -> (i64, i32) {
+    let seconds = msg
+        .get_field_by_number(1)
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default();
+    let nanos = msg
+        .get_field_by_number(2)
+        .and_then(|v| v.as_i32())
+        .unwrap_or_default();
+    (seconds, nanos)
+}
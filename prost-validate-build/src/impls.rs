@@ -0,0 +1,177 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use syn::{parse_quote, Attribute, Ident, Item};
+
+/// The attribute deriving `Validator`, as added by the builder.
+const DERIVE: &str = "#[derive(::prost_validate::Validator)]";
+
+/// Moves the derived `Validator` implementations of the file generated by prost at `path`
+/// to a `.validate.rs` file next to it, e.g. `pkg.validate.rs` for `pkg.rs`.
+///
+/// The derive and `validate` attributes are removed from the prost file.
+/// A file already split, i.e. without any derive and with its implementations file,
+/// is left as is, e.g. when prost did not write it again.
+pub(crate) fn write_impls(path: &Path) -> io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    if !content.contains(DERIVE) && impls_path(path).exists() {
+        return Ok(());
+    }
+    let (content, impls) = split(&content).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), e),
+        )
+    })?;
+    fs::write(path, content)?;
+    fs::write(impls_path(path), impls)
+}
+
+/// Returns the path of the implementations of the prost file at `path`.
+fn impls_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".rs"))
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.validate.rs", stem))
+}
+
+/// Splits the code generated by prost into the code without the validation attributes
+/// and the `Validator` implementations of its types.
+pub(crate) fn split(content: &str) -> syn::Result<(String, String)> {
+    let mut file = syn::parse_file(content)?;
+    let mut impls = TokenStream::new();
+    extract(&mut file.items, &mut Vec::new(), &mut impls);
+    let mut impls = syn::parse2::<syn::File>(impls)?;
+    // the implementations are not linted, as when they are derived
+    for item in &mut impls.items {
+        if let Item::Impl(item) = item {
+            item.attrs.push(parse_quote!(#[allow(unused, clippy::all)]));
+        }
+    }
+    Ok((
+        prettyplease::unparse(&file),
+        format!(
+            "// This file is @generated by prost-validate-build.\n{}",
+            prettyplease::unparse(&impls)
+        ),
+    ))
+}
+
+/// Generates the implementations of the types deriving `Validator` in `items`,
+/// defined in `module` relatively to the root of the file.
+fn extract(items: &mut [Item], module: &mut Vec<Ident>, impls: &mut TokenStream) {
+    for item in items {
+        match item {
+            Item::Struct(item) => {
+                if derives(&item.attrs) {
+                    impls.extend(derive(item.to_token_stream(), module));
+                }
+                strip(&mut item.attrs);
+                for field in &mut item.fields {
+                    strip(&mut field.attrs);
+                }
+            }
+            Item::Enum(item) => {
+                if derives(&item.attrs) {
+                    impls.extend(derive(item.to_token_stream(), module));
+                }
+                strip(&mut item.attrs);
+                for variant in &mut item.variants {
+                    strip(&mut variant.attrs);
+                    for field in &mut variant.fields {
+                        strip(&mut field.attrs);
+                    }
+                }
+            }
+            Item::Mod(item) => {
+                if let Some((_, items)) = &mut item.content {
+                    module.push(item.ident.clone());
+                    extract(items, module, impls);
+                    module.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn derive(input: TokenStream, module: &[Ident]) -> TokenStream {
+    let module = (!module.is_empty()).then(|| quote! { #(#module)::* });
+    prost_validate_derive_core::derive_with_module(input, module)
+}
+
+/// Returns whether the attributes derive `Validator`.
+fn derives(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(is_derive)
+}
+
+fn is_derive(attr: &Attribute) -> bool {
+    attr.path().is_ident("derive") && attr.to_token_stream().to_string().replace(' ', "") == DERIVE
+}
+
+/// Removes the `Validator` derive and the `validate` attributes.
+fn strip(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| !is_derive(attr) && !attr.path().is_ident("validate"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let content = r#"
+            #[derive(::prost_validate::Validator)]
+            #[derive(Clone, PartialEq, ::prost::Message)]
+            pub struct Message {
+                #[prost(string, tag = "1")]
+                #[validate(name = "pkg.Message.name")]
+                #[validate(r#type(string(min_len = 1)))]
+                pub name: ::prost::alloc::string::String,
+                #[prost(oneof = "message::Kind", tags = "2")]
+                #[validate(name = "pkg.Message.kind")]
+                pub kind: ::core::option::Option<message::Kind>,
+            }
+            /// Nested message and enum types in `Message`.
+            pub mod message {
+                #[derive(::prost_validate::Validator)]
+                #[derive(Clone, PartialEq, ::prost::Oneof)]
+                #[validate(name = "pkg.Message.kind")]
+                pub enum Kind {
+                    #[prost(int32, tag = "2")]
+                    #[validate(name = "pkg.Message.id")]
+                    #[validate(r#type(int32(gt = 0)))]
+                    Id(i32),
+                }
+            }
+        "#;
+        let (content, impls) = split(content).unwrap();
+        assert!(!content.contains("validate"), "{}", content);
+        assert!(content.contains("#[derive(Clone, PartialEq, ::prost::Message)]"));
+        assert!(content.contains("#[prost(string, tag = \"1\")]"));
+        assert!(
+            impls.contains("impl ::prost_validate::Validator for Message {"),
+            "{}",
+            impls
+        );
+        assert!(
+            impls.contains("impl ::prost_validate::Validator for message::Kind {"),
+            "{}",
+            impls
+        );
+        assert!(impls.contains("#[allow(unused, clippy::all)]"), "{}", impls);
+        assert!(impls.contains("\"pkg.Message.name\""), "{}", impls);
+        assert!(impls.contains("\"pkg.Message.id\""), "{}", impls);
+    }
+
+    #[test]
+    fn test_impls_path() {
+        assert_eq!(
+            impls_path(Path::new("out/validate.example.rs")),
+            Path::new("out/validate.example.validate.rs")
+        );
+    }
+}
@@ -11,16 +11,37 @@
 //!     .compile_protos(&["path/to/protobuf.proto"], &["path/to/include"])
 //!     .expect("Failed to compile protos");
 //! ```
+//!
+//! The validation rules are [linted](lint()) before the code is generated:
+//! the problems found are reported as cargo warnings with their `.proto` location,
+//! and the invalid rules fail the build.
+//!
+//! With [`Builder::impl_files`], the `Validator` implementations are generated as plain Rust
+//! in separate files instead of being derived.
+//!
+//! With the `tonic` feature, the [`tonic::Builder`] compiles the protos with their `tonic` services
+//! and generates servers validating the requests.
+mod impls;
+mod lint;
+mod path;
 mod rules;
+#[cfg(feature = "tonic")]
+pub mod tonic;
 
+pub use crate::lint::{lint, Diagnostic, Location, Severity};
 use crate::rules::IntoFieldAttribute;
+use prost_build::Module;
 use prost_reflect::prost_types::FileDescriptorProto;
-use prost_reflect::{DescriptorPool, OneofDescriptor};
+use prost_reflect::{
+    DescriptorPool, FieldDescriptor, FileDescriptor, MessageDescriptor, OneofDescriptor,
+};
+use prost_validate_derive_core::cel;
+use prost_validate_types::buf::validate::Ignore;
 use prost_validate_types::{FieldRulesExt, MessageRulesExt, OneofRulesExt};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::{env, fs, io};
+use std::{env, fmt, fs, io};
 
 /// Configuration builder for prost-validate code generation.
 ///
@@ -33,17 +54,32 @@ use std::{env, fs, io};
 #[derive(Debug, Clone)]
 pub struct Builder {
     file_descriptor_set_path: PathBuf,
+    lint: bool,
+    impl_files: bool,
+    out_dir: PathBuf,
+    /// The names of the files generated by prost for the configured protos.
+    files: Vec<String>,
+    include_packages: Vec<String>,
+    exclude_messages: Vec<String>,
+    skip_fields: Vec<String>,
 }
 
 impl Default for Builder {
     fn default() -> Self {
-        let file_descriptor_set_path = env::var_os("OUT_DIR")
+        let out_dir = env::var_os("OUT_DIR")
             .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("file_descriptor_set.bin");
+            .unwrap_or_else(|| PathBuf::from("."));
+        let file_descriptor_set_path = out_dir.join("file_descriptor_set.bin");
 
         Self {
             file_descriptor_set_path,
+            lint: true,
+            impl_files: false,
+            out_dir,
+            files: Vec::new(),
+            include_packages: Vec::new(),
+            exclude_messages: Vec::new(),
+            skip_fields: Vec::new(),
         }
     }
 }
@@ -67,6 +103,89 @@ impl Builder {
         self
     }
 
+    /// Enable or disable the lint pass over the validation rules, enabled by default.
+    ///
+    /// See [`lint()`] for the problems reported.
+    pub fn lint(&mut self, enabled: bool) -> &mut Self {
+        self.lint = enabled;
+        self
+    }
+
+    /// Generate the `Validator` implementations as plain Rust in `<module>.validate.rs` files,
+    /// next to the files generated by prost, instead of deriving them, disabled by default.
+    ///
+    /// The implementations are generated once by the build script: the crate including the code
+    /// does not depend on the `derive` feature of `prost-validate`, and its builds do not expand
+    /// the derive macro of every message. The files must be included along with the prost ones:
+    ///
+    /// ```ignore
+    /// include!(concat!(env!("OUT_DIR"), "/validate.example.rs"));
+    /// include!(concat!(env!("OUT_DIR"), "/validate.example.validate.rs"));
+    /// ```
+    ///
+    /// The files are generated in the [`out_dir`](Builder::out_dir) of the builder
+    /// by [`compile_protos`](Builder::compile_protos) and [`compile_protos_with_config`](Builder::compile_protos_with_config).
+    /// When the protos are compiled with a configuration from [`configure`](Builder::configure),
+    /// call [`write_impl_files`](Builder::write_impl_files) once they are compiled.
+    ///
+    /// The implementations are generated at the root of the module of each package,
+    /// so the option must be enabled before [`configure`](Builder::configure) is called.
+    pub fn impl_files(&mut self, enabled: bool) -> &mut Self {
+        self.impl_files = enabled;
+        self
+    }
+
+    /// Set the directory of the files generated by prost, by default `$OUT_DIR`,
+    /// where the implementations are generated with [`impl_files`](Builder::impl_files).
+    pub fn out_dir<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.out_dir = path.into();
+        self
+    }
+
+    /// Validate only the messages of the packages matched by `paths`, by default all the packages.
+    ///
+    /// The paths use the syntax of the [`prost_build::Config`] paths: `.google` matches the
+    /// `google` package and its sub-packages, and `.` matches every package.
+    ///
+    /// The messages of the other packages, e.g. third-party protos compiled along with the owned ones,
+    /// get an implementation of `Validator` accepting every value, so that the messages referring to them compile.
+    pub fn include_packages<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.include_packages = paths.into_iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Do not validate the messages matched by `paths`, e.g. `.pkg.Message`,
+    /// nor the messages nested in them.
+    ///
+    /// As with [`include_packages`](Builder::include_packages), the excluded messages
+    /// get an implementation of `Validator` accepting every value.
+    pub fn exclude_messages<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.exclude_messages = paths.into_iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Do not validate the fields matched by `paths`, e.g. `.pkg.Message.field` or `Message.field`:
+    /// their rules are ignored, and their messages are not validated.
+    pub fn skip_fields<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.skip_fields = paths.into_iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
     /// Configure `config` to derive [`prost_validate::Validator`] for all messages included in `protos`.
     /// This method does not generate prost-validate compatible code,
     /// but `config` may be used later to compile protocol buffers independently of [`Builder`].
@@ -97,7 +216,22 @@ impl Builder {
 
         let buf = fs::read(&self.file_descriptor_set_path)?;
         let descriptor = DescriptorPool::decode(buf.as_ref()).expect("Invalid file descriptor");
-        self.annotate(config, &descriptor);
+        self.check(&descriptor)?;
+        self.annotate(config, &descriptor)?;
+        // the packages only imported by the protos are left to their own build
+        let names = protos
+            .iter()
+            .filter_map(|proto| {
+                includes
+                    .iter()
+                    .find_map(|include| proto.as_ref().strip_prefix(include).ok())
+            })
+            .collect::<Vec<_>>();
+        self.files = file_names(
+            descriptor
+                .files()
+                .filter(|file| names.iter().any(|name| *name == Path::new(file.name()))),
+        );
         Ok(())
     }
 
@@ -112,7 +246,9 @@ impl Builder {
                 .expect("Invalid file descriptor protos");
             d
         };
-        self.annotate(config, &descriptor);
+        self.check(&descriptor)?;
+        self.annotate(config, &descriptor)?;
+        self.files = file_names(descriptor.files());
         Ok(())
     }
 
@@ -125,7 +261,70 @@ impl Builder {
     ) -> io::Result<()> {
         self.configure(&mut config, protos, includes)?;
 
-        config.skip_protoc_run().compile_protos(protos, includes)
+        if !self.impl_files {
+            return config.skip_protoc_run().compile_protos(protos, includes);
+        }
+        config
+            .out_dir(&self.out_dir)
+            .skip_protoc_run()
+            .compile_protos(protos, includes)?;
+        self.write_impl_files()
+    }
+
+    /// Moves the derived `Validator` implementations of the files generated by prost
+    /// for the configured protos to `<module>.validate.rs` files, see [`impl_files`](Builder::impl_files).
+    pub fn write_impl_files(&self) -> io::Result<()> {
+        for file in &self.files {
+            let path = self.out_dir.join(file);
+            // no code is generated for the packages without messages
+            if path.exists() {
+                impls::write_impls(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the `Validator` implementations of the messages of the `files` of the pool,
+    /// returning the code of each module, e.g. for a `protoc` plugin writing them next to the code
+    /// generated by `protoc-gen-prost`.
+    ///
+    /// `config` must generate the same code as the one used to compile the messages,
+    /// as their validation depends on the Rust types of their fields.
+    /// The rules are not linted: use [`lint()`] first.
+    pub fn generate_impls(
+        &self,
+        mut config: prost_build::Config,
+        descriptor: &DescriptorPool,
+        files: &[impl AsRef<str>],
+    ) -> io::Result<HashMap<Module, String>> {
+        // the implementations are generated at the root of the module of each package
+        let mut builder = self.clone();
+        builder.impl_files = true;
+        builder.annotate(&mut config, descriptor)?;
+        let requests = files
+            .iter()
+            .map(|name| {
+                let file = descriptor.get_file_by_name(name.as_ref()).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{}: file not found in the descriptor pool", name.as_ref()),
+                    )
+                })?;
+                Ok((
+                    Module::from_protobuf_package_name(file.package_name()),
+                    file.file_descriptor_proto().clone(),
+                ))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        config
+            .generate(requests)?
+            .into_iter()
+            .map(|(module, content)| {
+                let (_, impls) = impls::split(&content)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Ok((module, impls))
+            })
+            .collect()
     }
 
     /// Compile protocol buffers into Rust.
@@ -137,10 +336,64 @@ impl Builder {
         self.compile_protos_with_config(prost_build::Config::new(), protos, includes)
     }
 
-    pub fn annotate(&self, config: &mut prost_build::Config, descriptor: &DescriptorPool) {
+    /// Lints the validation rules of the pool if enabled, reporting the problems found as cargo warnings.
+    /// The rules of the messages and fields which are not validated are not reported.
+    /// Returns an error listing the invalid rules, if any.
+    fn check(&self, descriptor: &DescriptorPool) -> io::Result<()> {
+        if !self.lint {
+            return Ok(());
+        }
+        let diagnostics = lint(descriptor)
+            .into_iter()
+            .filter(|d| {
+                if let Some(message) = descriptor.get_message_by_name(&d.field) {
+                    return self.validates(&message);
+                }
+                let Some((message, field)) = d.field.rsplit_once('.') else {
+                    return true;
+                };
+                let Some(message) = descriptor.get_message_by_name(message) else {
+                    return true;
+                };
+                self.validates(&message)
+                    && !message
+                        .get_field_by_name(field)
+                        .is_some_and(|field| self.skips(&field))
+            })
+            .collect::<Vec<_>>();
+        for diagnostic in &diagnostics {
+            println!("cargo:warning={}", diagnostic);
+        }
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid validation rules:\n{}", errors.join("\n")),
+        ))
+    }
+
+    /// Annotates the messages of the pool in `config` to derive their validation.
+    ///
+    /// The rules are not linted: use [`lint()`] first when calling this method directly.
+    /// Returns an error if a protovalidate CEL expression cannot be compiled.
+    pub fn annotate(
+        &self,
+        config: &mut prost_build::Config,
+        descriptor: &DescriptorPool,
+    ) -> io::Result<()> {
         for message in descriptor.all_messages() {
             let full_name = message.full_name();
             config.type_attribute(full_name, "#[derive(::prost_validate::Validator)]");
+            if !self.validates(&message) {
+                config.type_attribute(format!(".{}", full_name), "#[validate(skip)]");
+                continue;
+            }
             if message.validation_ignored() || message.validation_disabled() {
                 continue;
             }
@@ -150,6 +403,13 @@ impl Builder {
                     field.full_name(),
                     format!("#[validate(name = \"{}\")]", field.full_name()),
                 );
+                if self.skips(&field) {
+                    // the skipped fields of a oneof are handled with the other fields of the oneof
+                    if field.real_oneof().is_none() {
+                        skip_field(config, &field, field.full_name());
+                    }
+                    continue;
+                }
                 let field_rules = match field.validation_rules().unwrap() {
                     Some(r) => r,
                     None => continue,
@@ -175,6 +435,14 @@ impl Builder {
                             format!("#[validate(name = \"{}\")]", field.full_name()),
                         );
                         oneofs.insert(field.full_name().to_string(), desc.clone());
+                        if self.skips(&field) {
+                            skip_field(
+                                config,
+                                &field,
+                                &format!("{}.{}", desc.full_name(), field.name()),
+                            );
+                            continue;
+                        }
                         let field_rules = match field.validation_rules().unwrap() {
                             Some(r) => r,
                             None => continue,
@@ -199,6 +467,166 @@ impl Builder {
                         .field_attribute(field.full_name(), format!("#[validate({})]", attribute));
                 }
             }
+            self.annotate_cel(config, &message)?;
         }
+        Ok(())
+    }
+
+    /// Returns whether the message is validated, i.e. it is in the included packages and not excluded.
+    fn validates(&self, message: &MessageDescriptor) -> bool {
+        let package = format!(".{}", message.package_name());
+        (self.include_packages.is_empty() || path::matches(&self.include_packages, &package))
+            && !path::matches(&self.exclude_messages, &format!(".{}", message.full_name()))
+    }
+
+    /// Returns whether the validation of the field is skipped.
+    fn skips(&self, field: &FieldDescriptor) -> bool {
+        path::matches(&self.skip_fields, &format!(".{}", field.full_name()))
+    }
+
+    /// Compiles the protovalidate CEL expressions of the message and of its fields
+    /// to Rust code evaluated by the derived validation.
+    ///
+    /// Returns an error if an expression cannot be compiled.
+    fn annotate_cel(
+        &self,
+        config: &mut prost_build::Config,
+        message: &MessageDescriptor,
+    ) -> io::Result<()> {
+        for field in message.fields() {
+            if self.skips(&field) {
+                continue;
+            }
+            let rules = match field
+                .protovalidate_rules()
+                .map_err(|e| invalid_cel(field.full_name(), e))?
+            {
+                Some(r) if r.ignore() != Ignore::Always => r,
+                _ => continue,
+            };
+            let path = match field.real_oneof() {
+                Some(desc) => format!("{}.{}", desc.full_name(), field.name()),
+                None => field.full_name().to_string(),
+            };
+            for rule in &rules.cel {
+                let ignore_empty = rules.ignore() == Ignore::IfZeroValue;
+                let expr = cel::compile_field(&field, rule, ignore_empty, self.impl_files)
+                    .map_err(|e| invalid_cel(field.full_name(), e))?;
+                config.field_attribute(
+                    &path,
+                    format!(
+                        "#[validate(cel(id = {:?}, expr = {:?}))]",
+                        rule.id(),
+                        expr.to_string()
+                    ),
+                );
+            }
+        }
+        let rules = match message
+            .protovalidate_rules()
+            .map_err(|e| invalid_cel(message.full_name(), e))?
+        {
+            Some(r) if !r.cel.is_empty() => r,
+            _ => return Ok(()),
+        };
+        config.type_attribute(
+            message.full_name(),
+            format!("#[validate(name = \"{}\")]", message.full_name()),
+        );
+        for rule in &rules.cel {
+            let expr = cel::compile_message(message, rule, self.impl_files)
+                .map_err(|e| invalid_cel(message.full_name(), e))?;
+            config.type_attribute(
+                message.full_name(),
+                format!(
+                    "#[validate(cel(id = {:?}, expr = {:?}))]",
+                    rule.id(),
+                    expr.to_string()
+                ),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Returns the error of the protovalidate rules of the field or message `name` that cannot be compiled.
+fn invalid_cel(name: &str, err: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", name, err))
+}
+
+/// Skips the validation of the field annotated at `path`: the messages of the message,
+/// list and map fields are not validated, and the other fields have no default rules.
+fn skip_field(config: &mut prost_build::Config, field: &FieldDescriptor, path: &str) {
+    if field.kind().as_message().is_some() {
+        config.field_attribute(path, "#[validate(message(skip = true))]");
+    }
+}
+
+/// Returns the names of the files generated by prost for the packages of the `files`.
+fn file_names(files: impl Iterator<Item = FileDescriptor>) -> Vec<String> {
+    let mut files = files
+        .map(|file| Module::from_protobuf_package_name(file.package_name()).to_file_name_or("_"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_validate_types::DESCRIPTOR_POOL;
+
+    #[test]
+    fn test_filters() {
+        let message = |name| DESCRIPTOR_POOL.get_message_by_name(name).unwrap();
+        let mut builder = Builder::new();
+        assert!(builder.validates(&message("google.protobuf.Duration")));
+
+        builder
+            .include_packages([".validate"])
+            .exclude_messages(["FieldRules"])
+            .skip_fields([".validate.StringRules.pattern"]);
+        assert!(builder.validates(&message("validate.StringRules")));
+        assert!(!builder.validates(&message("validate.FieldRules")));
+        assert!(!builder.validates(&message("google.protobuf.Duration")));
+
+        let rules = message("validate.StringRules");
+        assert!(builder.skips(&rules.get_field_by_name("pattern").unwrap()));
+        assert!(!builder.skips(&rules.get_field_by_name("len").unwrap()));
+    }
+
+    #[test]
+    fn test_annotate_cel_errors() {
+        let file = serde_json::json!({
+            "name": "cel.proto",
+            "package": "test",
+            "dependency": ["buf/validate/validate.proto"],
+            "syntax": "proto3",
+            "messageType": [{
+                "name": "Message",
+                "options": {"[buf.validate.message]": {"cel": [
+                    {"id": "invalid", "expression": "this.unknown"},
+                ]}},
+            }],
+        });
+        let desc = DESCRIPTOR_POOL
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap();
+        let file = prost_reflect::DynamicMessage::deserialize(desc, file).unwrap();
+        let mut pool = DESCRIPTOR_POOL.clone();
+        pool.decode_file_descriptor_proto(prost::Message::encode_to_vec(&file).as_slice())
+            .unwrap();
+
+        let err = Builder::new()
+            .annotate(&mut prost_build::Config::new(), &pool)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(
+            err.to_string()
+                .starts_with("test.Message: CEL rule \"invalid\""),
+            "{}",
+            err
+        );
     }
 }
@@ -0,0 +1,718 @@
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, FileDescriptor, Kind, MessageDescriptor,
+    ReflectMessage, Value,
+};
+use prost_validate_derive_core::cel;
+use prost_validate_types::buf::validate::Ignore;
+use prost_validate_types::field_rules::Type;
+use prost_validate_types::{FieldRules, FieldRulesExt, MessageRulesExt};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The number of the `options` field of a `FieldDescriptorProto`.
+const FIELD_OPTIONS: i32 = 8;
+/// The number of the `(validate.rules)` field option.
+const VALIDATE_RULES: i32 = 1071;
+/// The number of the `options` field of a `DescriptorProto`.
+const MESSAGE_OPTIONS: i32 = 7;
+/// The number of the `(buf.validate.message)` message option.
+const BUF_VALIDATE_MESSAGE: i32 = 1159;
+
+/// The severity of a [`Diagnostic`]: errors fail the build, warnings are only reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// The position of a definition in a `.proto` file, the line and column starting at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: i32,
+    pub column: i32,
+}
+
+/// A problem found in the validation rules of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The full name of the field, e.g. `pkg.Message.field`,
+    /// or of the message for the rules of the message.
+    pub field: String,
+    pub message: String,
+    /// The position of the rules in their `.proto` file,
+    /// if the descriptors were compiled with the source code info.
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}: {}: {}", self.severity, self.field, self.message)
+    }
+}
+
+/// Checks the validation rules of all the messages of the pool, returning the problems found:
+///
+/// - rules not matching the type of their field, e.g. string rules on an `int32` field
+/// - patterns that are not valid regexes
+/// - minimum lengths or counts greater than the maximum ones, e.g. `min_len > max_len`
+/// - `gt` and `gte`, or `lt` and `lte`, set together, and ranges accepting every value
+/// - values both in `in` and `not_in`
+/// - protovalidate CEL expressions that cannot be compiled
+///
+/// Exclusive ranges whose bounds are equal, e.g. `gt == lt`, reject a single value:
+/// they are reported as warnings.
+pub fn lint(pool: &DescriptorPool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for message in pool.all_messages() {
+        lint_message(&message, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn lint_message(message: &MessageDescriptor, diagnostics: &mut Vec<Diagnostic>) {
+    if message.is_map_entry() || message.validation_ignored() || message.validation_disabled() {
+        return;
+    }
+    for field in message.fields() {
+        let mut lint = Lint {
+            field: &field,
+            diagnostics: &mut *diagnostics,
+        };
+        match field.validation_rules() {
+            Ok(Some(rules)) => lint.check(&field, &rules, false),
+            Ok(None) => {}
+            Err(err) => lint.error(format!("invalid rules: {}", err)),
+        }
+    }
+    lint_cel(message, diagnostics);
+}
+
+/// Reports the protovalidate CEL expressions of the message and of its fields that cannot be compiled.
+fn lint_cel(message: &MessageDescriptor, diagnostics: &mut Vec<Diagnostic>) {
+    for field in message.fields() {
+        let mut lint = Lint {
+            field: &field,
+            diagnostics: &mut *diagnostics,
+        };
+        let rules = match field.protovalidate_rules() {
+            Ok(Some(rules)) if rules.ignore() != Ignore::Always => rules,
+            Ok(_) => continue,
+            Err(err) => {
+                lint.error(format!("invalid rules: {}", err));
+                continue;
+            }
+        };
+        for rule in &rules.cel {
+            let ignore_empty = rules.ignore() == Ignore::IfZeroValue;
+            if let Err(err) = cel::compile_field(&field, rule, ignore_empty, false) {
+                lint.error(err.to_string());
+            }
+        }
+    }
+    let errors = match message.protovalidate_rules() {
+        Ok(Some(rules)) => rules
+            .cel
+            .iter()
+            .filter_map(|rule| cel::compile_message(message, rule, false).err())
+            .map(|err| err.to_string())
+            .collect(),
+        Ok(None) => vec![],
+        Err(err) => vec![format!("invalid rules: {}", err)],
+    };
+    diagnostics.extend(errors.into_iter().map(|error| Diagnostic {
+        severity: Severity::Error,
+        field: message.full_name().to_string(),
+        message: error,
+        location: message_location(message),
+    }));
+}
+
+/// The diagnostics of a field.
+struct Lint<'a> {
+    field: &'a FieldDescriptor,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Lint<'_> {
+    fn error(&mut self, message: String) {
+        self.report(Severity::Error, message);
+    }
+
+    fn warning(&mut self, message: String) {
+        self.report(Severity::Warning, message);
+    }
+
+    fn report(&mut self, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            field: self.field.full_name().to_string(),
+            message,
+            location: location(self.field),
+        });
+    }
+
+    /// Checks the rules of the field, or of its items if `item` is true.
+    fn check(&mut self, field: &FieldDescriptor, rules: &FieldRules, item: bool) {
+        let Some(r#type) = &rules.r#type else {
+            return;
+        };
+        if !item && field.is_map() {
+            let Type::Map(map) = r#type else {
+                return self.error(unexpected(r#type, "map"));
+            };
+            self.rules(r#type, rules);
+            let Some(entry) = field.kind().as_message().cloned() else {
+                return;
+            };
+            if let Some(keys) = &map.keys {
+                self.check(&entry.map_entry_key_field(), keys, true);
+            }
+            if let Some(values) = &map.values {
+                self.check(&entry.map_entry_value_field(), values, true);
+            }
+            return;
+        }
+        if !item && field.is_list() {
+            let Type::Repeated(repeated) = r#type else {
+                return self.error(unexpected(r#type, "repeated"));
+            };
+            self.rules(r#type, rules);
+            if let Some(items) = &repeated.items {
+                self.check(field, items, true);
+            }
+            return;
+        }
+        let expected = expected_type(field);
+        if type_name(r#type) != expected {
+            return self.error(unexpected(r#type, expected));
+        }
+        self.rules(r#type, rules);
+    }
+
+    /// Checks the rules of a type, e.g. the `string` rules, independently of the field.
+    fn rules(&mut self, r#type: &Type, rules: &FieldRules) {
+        let name = type_name(r#type);
+        let rules = rules.transcode_to_dynamic();
+        let Some(rules) = rules
+            .get_field_by_name(name)
+            .and_then(|rules| rules.as_message().cloned())
+        else {
+            return;
+        };
+        match r#type {
+            Type::String(rules) => {
+                if let Some(pattern) = &rules.pattern {
+                    if let Err(err) = regex::Regex::new(pattern) {
+                        self.error(format!("invalid {}.pattern: {}", name, err));
+                    }
+                }
+            }
+            Type::Bytes(rules) => {
+                if let Some(pattern) = &rules.pattern {
+                    if let Err(err) = regex::bytes::Regex::new(pattern) {
+                        self.error(format!("invalid {}.pattern: {}", name, err));
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.lengths(name, &rules);
+        self.range(name, &rules);
+        self.lists(name, &rules);
+    }
+
+    /// Checks that the minimum lengths or counts are not greater than the maximum ones.
+    fn lengths(&mut self, name: &str, rules: &DynamicMessage) {
+        let pairs = [
+            ("min_len", "max_len"),
+            ("min_bytes", "max_bytes"),
+            ("min_items", "max_items"),
+            ("min_pairs", "max_pairs"),
+        ];
+        for (min, max) in pairs {
+            let (Some(lo), Some(hi)) = (get(rules, min), get(rules, max)) else {
+                continue;
+            };
+            if compare(&lo, &hi) == Some(Ordering::Greater) {
+                self.error(format!(
+                    "{name}.{min} ({}) is greater than {name}.{max} ({})",
+                    display(&lo),
+                    display(&hi),
+                ));
+            }
+        }
+    }
+
+    /// Checks the `gt`, `gte`, `lt` and `lte` rules.
+    fn range(&mut self, name: &str, rules: &DynamicMessage) {
+        let mut bound = |exclusive: &'static str, inclusive: &'static str| match (
+            get(rules, exclusive),
+            get(rules, inclusive),
+        ) {
+            (Some(_), Some(_)) => {
+                self.error(format!(
+                    "{name}.{exclusive} and {name}.{inclusive} cannot be set together"
+                ));
+                None
+            }
+            (Some(v), None) => Some((exclusive, v, false)),
+            (None, Some(v)) => Some((inclusive, v, true)),
+            (None, None) => None,
+        };
+        let lower = bound("gt", "gte");
+        let upper = bound("lt", "lte");
+        let (Some((lo_name, lo, lo_inclusive)), Some((hi_name, hi, hi_inclusive))) = (lower, upper)
+        else {
+            return;
+        };
+        let lo_value = display(&lo);
+        match compare(&lo, &hi) {
+            Some(Ordering::Equal) if lo_inclusive && hi_inclusive => self.error(format!(
+                "{name}.{lo_name} equals {name}.{hi_name} ({lo_value}): every value is accepted, use {name}.const instead"
+            )),
+            Some(Ordering::Equal) if lo_inclusive || hi_inclusive => self.error(format!(
+                "{name}.{lo_name} equals {name}.{hi_name} ({lo_value}): every value is accepted"
+            )),
+            Some(Ordering::Equal) => self.warning(format!(
+                "{name}.{lo_name} equals {name}.{hi_name} ({lo_value}): only this value is rejected, use {name}.not_in instead"
+            )),
+            _ => {}
+        }
+    }
+
+    /// Checks that no value is both in the `in` and `not_in` lists.
+    fn lists(&mut self, name: &str, rules: &DynamicMessage) {
+        let (Some(r#in), Some(not_in)) = (get(rules, "in"), get(rules, "not_in")) else {
+            return;
+        };
+        let (Some(r#in), Some(not_in)) = (r#in.as_list(), not_in.as_list()) else {
+            return;
+        };
+        for value in r#in.iter().filter(|value| not_in.contains(value)) {
+            self.error(format!(
+                "{} is both in {name}.in and {name}.not_in",
+                display(value)
+            ));
+        }
+    }
+}
+
+/// Returns the value of the field of the rules, if it is set.
+fn get(rules: &DynamicMessage, name: &str) -> Option<Value> {
+    if !rules.has_field_by_name(name) {
+        return None;
+    }
+    rules.get_field_by_name(name).map(|v| v.into_owned())
+}
+
+/// Compares two numbers, durations or timestamps.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::I32(a), Value::I32(b)) => a.partial_cmp(b),
+        (Value::I64(a), Value::I64(b)) => a.partial_cmp(b),
+        (Value::U32(a), Value::U32(b)) => a.partial_cmp(b),
+        (Value::U64(a), Value::U64(b)) => a.partial_cmp(b),
+        (Value::F32(a), Value::F32(b)) => a.partial_cmp(b),
+        (Value::F64(a), Value::F64(b)) => a.partial_cmp(b),
+        (Value::Message(a), Value::Message(b)) => seconds(a)?.partial_cmp(&seconds(b)?),
+        _ => None,
+    }
+}
+
+/// Returns the seconds and nanoseconds of a duration or a timestamp.
+fn seconds(msg: &DynamicMessage) -> Option<(i64, i32)> {
+    let seconds = msg.get_field_by_name("seconds")?.as_i64()?;
+    let nanos = msg.get_field_by_name("nanos")?.as_i32()?;
+    Some((seconds, nanos))
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Bool(v) => v.to_string(),
+        Value::I32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::F32(v) => v.to_string(),
+        Value::F64(v) => v.to_string(),
+        Value::String(v) => format!("{:?}", v),
+        Value::Bytes(v) => format!("{:?}", v),
+        Value::EnumNumber(v) => v.to_string(),
+        Value::Message(msg) => match seconds(msg) {
+            Some((seconds, nanos)) => format!("{{seconds: {}, nanos: {}}}", seconds, nanos),
+            None => format!("{:?}", msg),
+        },
+        Value::List(_) | Value::Map(_) => format!("{:?}", value),
+    }
+}
+
+/// Returns the location of the `(validate.rules)` option of the field, or of the field itself.
+fn location(field: &FieldDescriptor) -> Option<Location> {
+    locate(
+        &field.parent_file(),
+        field.path(),
+        [FIELD_OPTIONS, VALIDATE_RULES],
+    )
+}
+
+/// Returns the location of the `(buf.validate.message)` option of the message, or of the message itself.
+fn message_location(message: &MessageDescriptor) -> Option<Location> {
+    locate(
+        &message.parent_file(),
+        message.path(),
+        [MESSAGE_OPTIONS, BUF_VALIDATE_MESSAGE],
+    )
+}
+
+/// Returns the location of the first option of the definition at `path` starting with `option`,
+/// e.g. `(validate.rules).string`, or of the definition itself.
+fn locate(file: &FileDescriptor, path: &[i32], option: [i32; 2]) -> Option<Location> {
+    let info = file.file_descriptor_proto().source_code_info.as_ref()?;
+    let mut option_path = path.to_vec();
+    option_path.extend(option);
+    let location = info
+        .location
+        .iter()
+        .find(|location| location.path.starts_with(&option_path))
+        .or_else(|| info.location.iter().find(|location| location.path == path))?;
+    Some(Location {
+        file: file.name().to_string(),
+        line: location.span.first()? + 1,
+        column: location.span.get(1)? + 1,
+    })
+}
+
+fn unexpected(r#type: &Type, expected: &str) -> String {
+    format!(
+        "unexpected {} rules, expected {} rules",
+        type_name(r#type),
+        expected
+    )
+}
+
+/// Returns the name of the rules matching the type of the field.
+fn expected_type(field: &FieldDescriptor) -> &'static str {
+    match field.kind() {
+        Kind::Double => "double",
+        Kind::Float => "float",
+        Kind::Int32 => "int32",
+        Kind::Int64 => "int64",
+        Kind::Uint32 => "uint32",
+        Kind::Uint64 => "uint64",
+        Kind::Sint32 => "sint32",
+        Kind::Sint64 => "sint64",
+        Kind::Fixed32 => "fixed32",
+        Kind::Fixed64 => "fixed64",
+        Kind::Sfixed32 => "sfixed32",
+        Kind::Sfixed64 => "sfixed64",
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::Enum(_) => "enum",
+        Kind::Message(desc) => match desc.full_name() {
+            "google.protobuf.Any" => "any",
+            "google.protobuf.Duration" => "duration",
+            "google.protobuf.Timestamp" => "timestamp",
+            "google.protobuf.DoubleValue" => "double",
+            "google.protobuf.FloatValue" => "float",
+            "google.protobuf.Int64Value" => "int64",
+            "google.protobuf.UInt64Value" => "uint64",
+            "google.protobuf.Int32Value" => "int32",
+            "google.protobuf.UInt32Value" => "uint32",
+            "google.protobuf.BoolValue" => "bool",
+            "google.protobuf.StringValue" => "string",
+            "google.protobuf.BytesValue" => "bytes",
+            _ => "message",
+        },
+    }
+}
+
+/// Returns the name of the rules, as used in the rule definitions, e.g. `string`.
+fn type_name(r#type: &Type) -> &'static str {
+    match r#type {
+        Type::Float(_) => "float",
+        Type::Double(_) => "double",
+        Type::Int32(_) => "int32",
+        Type::Int64(_) => "int64",
+        Type::Uint32(_) => "uint32",
+        Type::Uint64(_) => "uint64",
+        Type::Sint32(_) => "sint32",
+        Type::Sint64(_) => "sint64",
+        Type::Fixed32(_) => "fixed32",
+        Type::Fixed64(_) => "fixed64",
+        Type::Sfixed32(_) => "sfixed32",
+        Type::Sfixed64(_) => "sfixed64",
+        Type::Bool(_) => "bool",
+        Type::String(_) => "string",
+        Type::Bytes(_) => "bytes",
+        Type::Enum(_) => "enum",
+        Type::Repeated(_) => "repeated",
+        Type::Map(_) => "map",
+        Type::Any(_) => "any",
+        Type::Duration(_) => "duration",
+        Type::Timestamp(_) => "timestamp",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use prost_validate_types::DESCRIPTOR_POOL;
+
+    /// Returns a pool with the `test.Message` message whose fields are annotated with the given rules,
+    /// the rules of the first field being located at line 5, column 3.
+    fn pool(fields: serde_json::Value) -> DescriptorPool {
+        let file = serde_json::json!({
+            "name": "test.proto",
+            "package": "test",
+            "dependency": ["validate/validate.proto"],
+            "syntax": "proto3",
+            "messageType": [{"name": "Message", "field": fields}],
+            "sourceCodeInfo": {"location": [{"path": [4, 0, 2, 0, 8, 1071], "span": [4, 2, 40]}]},
+        });
+        let desc = DESCRIPTOR_POOL
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap();
+        let file = DynamicMessage::deserialize(desc, file).unwrap();
+        let mut pool = DESCRIPTOR_POOL.clone();
+        pool.decode_file_descriptor_proto(file.encode_to_vec().as_slice())
+            .unwrap();
+        pool
+    }
+
+    fn field(name: &str, number: i32, r#type: &str, rules: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "number": number,
+            "label": "LABEL_OPTIONAL",
+            "type": r#type,
+            "options": {"[validate.rules]": rules},
+        })
+    }
+
+    #[test]
+    fn test_lint() {
+        let pool = pool(serde_json::json!([
+            field(
+                "len",
+                1,
+                "TYPE_STRING",
+                serde_json::json!({"string": {"minLen": 10, "maxLen": 5}})
+            ),
+            field(
+                "range",
+                2,
+                "TYPE_INT32",
+                serde_json::json!({"int32": {"gte": 5, "lte": 5}})
+            ),
+            field(
+                "ex",
+                3,
+                "TYPE_INT32",
+                serde_json::json!({"int32": {"gt": 10, "lt": 0}})
+            ),
+            field(
+                "pattern",
+                4,
+                "TYPE_STRING",
+                serde_json::json!({"string": {"pattern": "("}})
+            ),
+            field(
+                "type",
+                5,
+                "TYPE_INT32",
+                serde_json::json!({"string": {"minLen": 1}})
+            ),
+            field(
+                "in",
+                6,
+                "TYPE_UINT32",
+                serde_json::json!({"uint32": {"in": [1, 2], "notIn": [2]}})
+            ),
+            field(
+                "valid",
+                7,
+                "TYPE_INT32",
+                serde_json::json!({"int32": {"gt": 0, "lt": 10}})
+            ),
+        ]));
+        let diagnostics = lint(&pool);
+        let messages = diagnostics
+            .iter()
+            .map(|d| (d.severity, d.field.as_str(), d.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages[..2],
+            [
+                (
+                    Severity::Error,
+                    "test.Message.len",
+                    "string.min_len (10) is greater than string.max_len (5)"
+                ),
+                (
+                    Severity::Error,
+                    "test.Message.range",
+                    "int32.gte equals int32.lte (5): every value is accepted, use int32.const instead"
+                ),
+            ]
+        );
+        assert_eq!(messages[2].1, "test.Message.pattern");
+        assert!(messages[2].2.starts_with("invalid string.pattern"));
+        assert_eq!(
+            messages[3..],
+            [
+                (
+                    Severity::Error,
+                    "test.Message.type",
+                    "unexpected string rules, expected int32 rules"
+                ),
+                (
+                    Severity::Error,
+                    "test.Message.in",
+                    "2 is both in uint32.in and uint32.not_in"
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "test.proto:5:3: error: test.Message.len: string.min_len (10) is greater than string.max_len (5)"
+        );
+        assert_eq!(diagnostics[1].location, None);
+        // the exclusive ranges are valid
+        assert!(messages
+            .iter()
+            .all(|(_, field, _)| *field != "test.Message.ex"));
+    }
+
+    #[test]
+    fn test_lint_cel() {
+        let file = serde_json::json!({
+            "name": "cel.proto",
+            "package": "test",
+            "dependency": ["buf/validate/validate.proto"],
+            "syntax": "proto3",
+            "messageType": [{
+                "name": "Message",
+                "field": [{
+                    "name": "val",
+                    "number": 1,
+                    "label": "LABEL_OPTIONAL",
+                    "type": "TYPE_INT32",
+                    "options": {"[buf.validate.field]": {"cel": [
+                        {"id": "valid", "expression": "this > 0"},
+                        {"id": "invalid", "expression": "this.unknown"},
+                    ]}},
+                }],
+                "options": {"[buf.validate.message]": {"cel": [
+                    {"id": "message", "expression": "this.val +"},
+                ]}},
+            }],
+            "sourceCodeInfo": {"location": [
+                {"path": [4, 0], "span": [3, 0, 12, 1]},
+                {"path": [4, 0, 7, 1159, 3, 0], "span": [4, 2, 60]},
+            ]},
+        });
+        let desc = DESCRIPTOR_POOL
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap();
+        let file = DynamicMessage::deserialize(desc, file).unwrap();
+        let mut pool = DESCRIPTOR_POOL.clone();
+        pool.decode_file_descriptor_proto(file.encode_to_vec().as_slice())
+            .unwrap();
+
+        let diagnostics = lint(&pool);
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].field, "test.Message.val");
+        assert!(
+            diagnostics[0].message.starts_with("CEL rule \"invalid\""),
+            "{}",
+            diagnostics[0].message
+        );
+        assert_eq!(diagnostics[1].field, "test.Message");
+        assert!(
+            diagnostics[1].message.starts_with("CEL rule \"message\""),
+            "{}",
+            diagnostics[1].message
+        );
+        assert_eq!(
+            diagnostics[1].location,
+            Some(Location {
+                file: "cel.proto".to_string(),
+                line: 5,
+                column: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lint_both_rules() {
+        let field = |name: &str, number: i32, buf: serde_json::Value| {
+            serde_json::json!({
+                "name": name,
+                "number": number,
+                "label": "LABEL_OPTIONAL",
+                "type": "TYPE_INT32",
+                "options": {
+                    "[validate.rules]": {"int32": {"gt": 0}},
+                    "[buf.validate.field]": buf,
+                },
+            })
+        };
+        let file = serde_json::json!({
+            "name": "both.proto",
+            "package": "test",
+            "dependency": ["validate/validate.proto", "buf/validate/validate.proto"],
+            "syntax": "proto3",
+            "messageType": [{
+                "name": "Message",
+                "field": [
+                    field("both", 1, serde_json::json!({"int32": {"lt": 10}})),
+                    field("cel", 2, serde_json::json!({"cel": [
+                        {"id": "cel", "expression": "this < 10"},
+                    ]})),
+                ],
+            }],
+        });
+        let desc = DESCRIPTOR_POOL
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap();
+        let file = DynamicMessage::deserialize(desc, file).unwrap();
+        let mut pool = DESCRIPTOR_POOL.clone();
+        pool.decode_file_descriptor_proto(file.encode_to_vec().as_slice())
+            .unwrap();
+
+        let diagnostics = lint(&pool);
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].field, "test.Message.both");
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("invalid rules: both (validate.rules) and (buf.validate.field)"),
+            "{}",
+            diagnostics[0].message
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Matching of the Protobuf paths, using the syntax of the `prost-build` paths.
+
+use std::iter;
+
+/// Returns whether the fully-qualified `fq_path`, e.g. `.pkg.Message.field`, is matched by one of the `paths`.
+///
+/// As with `prost-build`, a path matches a fully-qualified path if it is the path itself,
+/// one of its suffixes, e.g. `Message.field`, one of its prefixes, e.g. `.pkg`, or the global path `.`.
+pub(crate) fn matches(paths: &[String], fq_path: &str) -> bool {
+    sub_paths(fq_path).any(|path| paths.iter().any(|p| p == path))
+}
+
+/// Returns the path itself, its suffixes, its prefixes and the global path.
+///
+/// Example: `sub_paths(".a.b.c")` -> `[".a.b.c", "a.b.c", "b.c", "c", ".a.b", ".a", "."]`
+fn sub_paths(fq_path: &str) -> impl Iterator<Item = &str> {
+    let suffixes = iter::successors(Some(fq_path), |path| {
+        path.split_once('.')
+            .map(|(_, path)| path)
+            .filter(|path| !path.is_empty())
+    })
+    .skip(1);
+    let prefixes = iter::successors(Some(fq_path), |path| {
+        path.rsplit_once('.')
+            .map(|(path, _)| path)
+            .filter(|path| !path.is_empty())
+    })
+    .skip(1);
+    iter::once(fq_path)
+        .chain(suffixes)
+        .chain(prefixes)
+        .chain(iter::once("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_paths() {
+        assert_eq!(
+            sub_paths(".a.b.c").collect::<Vec<_>>(),
+            vec![".a.b.c", "a.b.c", "b.c", "c", ".a.b", ".a", "."]
+        );
+        assert_eq!(sub_paths(".").collect::<Vec<_>>(), vec![".", "."]);
+    }
+
+    #[test]
+    fn test_matches() {
+        let paths = vec![".google".to_string(), "Message.field".to_string()];
+        assert!(matches(&paths, ".google.api.Http"));
+        assert!(matches(&paths, ".pkg.Message.field"));
+        assert!(!matches(&paths, ".googleapis.Http"));
+        assert!(!matches(&paths, ".pkg.Message.other"));
+        assert!(!matches(&paths, ".pkg.OtherMessage.field"));
+        assert!(matches(&[".".to_string()], ".pkg.Message"));
+    }
+}
@@ -0,0 +1,320 @@
+//! Compiles the protos with their [`tonic`](https://docs.rs/tonic) services, enabled by the `tonic` feature.
+//!
+//! The [`Builder`] wires the `tonic-prost-build` service generator, the validation of the messages
+//! and optionally the `prost-reflect` descriptor pool in one call:
+//!
+//! ```no_run
+//! prost_validate_build::tonic::Builder::new()
+//!     .descriptor_pool("DESCRIPTOR_POOL")
+//!     .compile_protos(&["service.proto"], &["proto", "../prost-validate-types/proto"])
+//!     .expect("Failed to compile protos");
+//! ```
+//!
+//! Along with the `tonic` server of each service, e.g. `example_service_server::ExampleServiceServer`,
+//! a validated server is generated in a `validated_` module, e.g.
+//! `validated_example_service_server::ValidatedExampleServiceServer`.
+//! It serves the same implementation of the service trait, but validates each request message,
+//! including the messages of the streaming requests, before they reach it:
+//! the invalid ones are rejected with an `InvalidArgument` status carrying the `BadRequest` details.
+//!
+//! The validated servers decode the messages with `prost_validate::codec::ValidatingCodec`,
+//! which requires the `tonic` feature of `prost-validate`. With [`validate_responses`](Builder::validate_responses),
+//! they use `prost_validate::codec::ResponseValidatingCodec` to validate the responses too,
+//! failing the calls returning an invalid response with an `Internal` status.
+
+use proc_macro2::Ident;
+use prost_build::Service;
+use quote::format_ident;
+use std::path::Path;
+use std::{io, mem};
+use syn::visit_mut::VisitMut;
+use syn::{parse_quote, Item};
+
+/// The codec decoding the requests of the validated servers.
+const CODEC: &str = "::prost_validate::codec::ValidatingCodec";
+/// The codec of the validated servers validating the responses too.
+const RESPONSE_CODEC: &str = "::prost_validate::codec::ResponseValidatingCodec";
+
+/// Configuration builder compiling the protos with their `tonic` services and the validation of their messages.
+///
+/// ```no_run
+/// # use prost_validate_build::tonic::Builder;
+/// Builder::new()
+///     .compile_protos(&["path/to/protobuf.proto"], &["path/to/include"])
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+    validate: crate::Builder,
+    tonic: tonic_prost_build::Builder,
+    descriptor_pool: Option<String>,
+    validated_servers: bool,
+    validate_responses: bool,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            validate: crate::Builder::default(),
+            tonic: tonic_prost_build::configure(),
+            descriptor_pool: None,
+            validated_servers: true,
+            validate_responses: false,
+        }
+    }
+}
+
+impl Builder {
+    /// Create a new builder with default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the builder configuring the validation of the messages, e.g. to disable the lint pass.
+    pub fn validate(&mut self, builder: crate::Builder) -> &mut Self {
+        self.validate = builder;
+        self
+    }
+
+    /// Set the `tonic-prost-build` builder generating the services,
+    /// by default [`tonic_prost_build::configure`].
+    ///
+    /// Its codec is not used by the validated servers.
+    pub fn tonic(&mut self, builder: tonic_prost_build::Builder) -> &mut Self {
+        self.tonic = builder;
+        self
+    }
+
+    /// Derive `prost_reflect::ReflectMessage` for the messages with `prost-reflect-build`,
+    /// using the descriptor pool returned by the `expr` expression, e.g. a static `DESCRIPTOR_POOL`.
+    ///
+    /// The pool can be decoded from the file descriptor set of the
+    /// [`file_descriptor_set_path`](crate::Builder::file_descriptor_set_path) of the validation builder.
+    pub fn descriptor_pool<S>(&mut self, expr: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.descriptor_pool = Some(expr.into());
+        self
+    }
+
+    /// Generate a validated server for each service, enabled by default.
+    pub fn validated_servers(&mut self, enabled: bool) -> &mut Self {
+        self.validated_servers = enabled;
+        self
+    }
+
+    /// Validate the responses of the validated servers too, disabled by default.
+    pub fn validate_responses(&mut self, enabled: bool) -> &mut Self {
+        self.validate_responses = enabled;
+        self
+    }
+
+    /// Compile protocol buffers into Rust with given [`prost_build::Config`].
+    pub fn compile_protos_with_config(
+        &mut self,
+        mut config: prost_build::Config,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> io::Result<()> {
+        config.service_generator(Box::new(ServiceGenerator {
+            inner: self.tonic.clone().service_generator(),
+            validated: self.validated_servers.then(|| {
+                self.tonic
+                    .clone()
+                    .build_client(false)
+                    .build_server(true)
+                    .codec_path(if self.validate_responses {
+                        RESPONSE_CODEC
+                    } else {
+                        CODEC
+                    })
+                    .service_generator()
+            }),
+            code: String::new(),
+        }));
+        if let Some(expr) = &self.descriptor_pool {
+            prost_reflect_build::Builder::new()
+                .file_descriptor_set_path(self.validate.file_descriptor_set_path.clone())
+                .descriptor_pool(expr)
+                .configure(&mut config, protos, includes)?;
+        }
+        self.validate
+            .compile_protos_with_config(config, protos, includes)
+    }
+
+    /// Compile protocol buffers into Rust.
+    pub fn compile_protos(
+        &mut self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> io::Result<()> {
+        self.compile_protos_with_config(prost_build::Config::new(), protos, includes)
+    }
+}
+
+/// A service generator generating the validated servers along with the code of the `tonic` one.
+struct ServiceGenerator {
+    inner: Box<dyn prost_build::ServiceGenerator>,
+    /// The `tonic` generator of the servers decoding the requests with the validating codec.
+    validated: Option<Box<dyn prost_build::ServiceGenerator>>,
+    /// The code generated by the validated generator, not yet written.
+    code: String,
+}
+
+impl ServiceGenerator {
+    /// Appends the validated servers of the code generated so far to `buf`.
+    fn flush(&mut self, buf: &mut String) {
+        let code = mem::take(&mut self.code);
+        if code.is_empty() {
+            return;
+        }
+        let code = validated_servers(&code).expect("Invalid tonic generated code");
+        buf.push_str(&code);
+    }
+}
+
+impl prost_build::ServiceGenerator for ServiceGenerator {
+    fn generate(&mut self, service: Service, buf: &mut String) {
+        if let Some(validated) = &mut self.validated {
+            validated.generate(service.clone(), &mut self.code);
+        }
+        self.inner.generate(service, buf);
+    }
+
+    fn finalize(&mut self, buf: &mut String) {
+        self.inner.finalize(buf);
+        if let Some(validated) = &mut self.validated {
+            validated.finalize(&mut self.code);
+        }
+        self.flush(buf);
+    }
+
+    fn finalize_package(&mut self, package: &str, buf: &mut String) {
+        self.inner.finalize_package(package, buf);
+        if let Some(validated) = &mut self.validated {
+            validated.finalize_package(package, &mut self.code);
+        }
+        self.flush(buf);
+    }
+}
+
+/// Turns the servers generated by `tonic` into validated servers, serving the service trait of the original ones.
+///
+/// The `example_service_server` module becomes `validated_example_service_server`: its `ExampleService` trait
+/// is replaced by the one of the original module, and its `ExampleServiceServer` is renamed `ValidatedExampleServiceServer`.
+fn validated_servers(code: &str) -> syn::Result<String> {
+    let mut file = syn::parse_file(code)?;
+    for item in &mut file.items {
+        let Item::Mod(module) = item else {
+            continue;
+        };
+        let Some((_, items)) = &mut module.content else {
+            continue;
+        };
+        let Some(service) = items.iter().find_map(|item| match item {
+            Item::Trait(item) => Some(item.ident.clone()),
+            _ => None,
+        }) else {
+            continue;
+        };
+        let original = module.ident.clone();
+        items.retain(|item| !matches!(item, Item::Trait(_)));
+        items.insert(0, parse_quote! { pub use super::#original::#service; });
+        let mut rename = Rename {
+            from: format_ident!("{}Server", service),
+            to: format_ident!("Validated{}Server", service),
+        };
+        for item in items.iter_mut() {
+            rename.visit_item_mut(item);
+        }
+        let doc = format!(
+            " Generated server validating the requests of [`{}`] before calling it.",
+            service
+        );
+        module.attrs.retain(|attr| !attr.path().is_ident("doc"));
+        module.attrs.insert(0, parse_quote! { #[doc = #doc] });
+        module.ident = format_ident!("validated_{}", original);
+    }
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Renames the `from` identifiers to `to`.
+struct Rename {
+    from: Ident,
+    to: Ident,
+}
+
+impl VisitMut for Rename {
+    fn visit_ident_mut(&mut self, ident: &mut Ident) {
+        if *ident == self.from {
+            *ident = self.to.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validated_servers() {
+        let code = r#"
+            /// Generated server implementations.
+            pub mod example_service_server {
+                #![allow(unused_variables, dead_code, missing_docs)]
+                use tonic::codegen::*;
+                /// Generated trait containing gRPC methods that should be implemented for use with ExampleServiceServer.
+                #[async_trait]
+                pub trait ExampleService: std::marker::Send + std::marker::Sync + 'static {
+                    async fn example_method(
+                        &self,
+                        request: tonic::Request<super::ExampleMessage>,
+                    ) -> std::result::Result<tonic::Response<super::ExampleMessage>, tonic::Status>;
+                }
+                #[derive(Debug)]
+                pub struct ExampleServiceServer<T> {
+                    inner: Arc<T>,
+                }
+                impl<T> ExampleServiceServer<T> {
+                    pub fn new(inner: T) -> Self {
+                        Self { inner: Arc::new(inner) }
+                    }
+                }
+                impl<T: ExampleService> ExampleServiceServer<T> {
+                    fn codec() -> ::prost_validate::codec::ValidatingCodec<super::ExampleMessage, super::ExampleMessage> {
+                        ::prost_validate::codec::ValidatingCodec::default()
+                    }
+                }
+            }
+        "#;
+        let code = validated_servers(code).unwrap();
+        assert!(
+            code.contains("pub mod validated_example_service_server {"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("pub use super::example_service_server::ExampleService;"),
+            "{}",
+            code
+        );
+        assert!(!code.contains("pub trait"), "{}", code);
+        assert!(
+            code.contains("pub struct ValidatedExampleServiceServer<T>"),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains("impl<T: ExampleService> ValidatedExampleServiceServer<T>"),
+            "{}",
+            code
+        );
+        assert!(
+            !code.contains("Generated server implementations"),
+            "{}",
+            code
+        );
+        assert!(code.contains("#![allow(unused_variables"), "{}", code);
+    }
+}
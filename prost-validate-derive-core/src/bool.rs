@@ -11,10 +11,11 @@ pub struct BoolRules {
 impl ToValidationTokens for BoolRules {
     fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
         let field = &ctx.name;
+        let fail = ctx.fail();
         let r#const = self.r#const.map(|v| {
             quote! {
                 if *#name != #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::r#bool::Error::Const(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::r#bool::Error::Const(#v)));
                 }
             }
         });
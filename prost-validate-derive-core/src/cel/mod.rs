@@ -0,0 +1,1426 @@
+//! Compilation of the protovalidate CEL expression rules,
+//! `(buf.validate.field).cel` and `(buf.validate.message).cel`, to Rust code.
+//!
+//! The expressions are type checked against the message descriptors during the build and
+//! translated into a closure evaluated by the derived validation, so that no CEL interpreter
+//! is needed at runtime. The closure returns the violation message, if any, or an error if the
+//! evaluation fails, e.g. on an integer overflow.
+//!
+//! The generated code expects `this` to be bound to a reference to the field value, or to the
+//! message, and `ctx` to the validation context, which provides `now`.
+
+mod parser;
+
+use crate::{to_snake, to_upper_camel};
+use anyhow::{anyhow, bail, format_err, Result};
+use darling::FromMeta;
+use parser::{BinaryOp, Expr, UnaryOp};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use prost_validate_types::buf::validate::Rule;
+use prost_validate_types::FieldRulesExt;
+use quote::{format_ident, quote, ToTokens};
+use std::fmt;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+const WRAPPERS: &[&str] = &[
+    "google.protobuf.BoolValue",
+    "google.protobuf.BytesValue",
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int32Value",
+    "google.protobuf.Int64Value",
+    "google.protobuf.StringValue",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.UInt64Value",
+];
+
+/// Compiles the CEL expression `rule` of the field.
+///
+/// If `ignore_empty` is true, the expression is not evaluated when the field has its zero value.
+/// If `flat` is true, the code is generated for an implementation at the root of the module
+/// of the package, e.g. in the `.validate.rs` files of `prost-validate-build`,
+/// instead of the module of the message.
+pub fn compile_field(
+    field: &FieldDescriptor,
+    rule: &Rule,
+    ignore_empty: bool,
+    flat: bool,
+) -> Result<TokenStream> {
+    let message = field.parent_message();
+    let mut scope = if flat {
+        package(message)
+    } else {
+        module(message)
+    };
+    // the rules of the oneof fields are evaluated by the oneof enum
+    if field.real_oneof().is_some() && !flat {
+        scope.push(to_snake(message.name()));
+    }
+    let compiled = this(field).and_then(|this| {
+        let zero = (ignore_empty && !field.supports_presence()).then(|| is_zero(field));
+        compile(scope, this, zero, rule)
+    });
+    compiled.map_err(|e| format_err!("CEL rule {:?}: {}", rule.id(), e))
+}
+
+/// Compiles the CEL expression `rule` of the message.
+///
+/// If `flat` is true, the code is generated for an implementation at the root of the module
+/// of the package, see [`compile_field`].
+pub fn compile_message(desc: &MessageDescriptor, rule: &Rule, flat: bool) -> Result<TokenStream> {
+    let this = Value::new(quote! { Some(this) }, Type::Message(desc.clone()));
+    let scope = if flat { package(desc) } else { module(desc) };
+    compile(scope, this, None, rule).map_err(|e| format_err!("CEL rule {:?}: {}", rule.id(), e))
+}
+
+fn compile(
+    scope: Vec<String>,
+    this: Value,
+    zero: Option<TokenStream>,
+    rule: &Rule,
+) -> Result<TokenStream> {
+    let expr = parser::parse(rule.expression())
+        .map_err(|e| format_err!("invalid expression `{}`: {}", rule.expression(), e))?;
+    let mut compiler = Compiler {
+        scope,
+        vars: vec![("this".to_string(), this)],
+        now: false,
+        next: 0,
+    };
+    let value = compiler
+        .expr(&expr)
+        .map_err(|e| format_err!("cannot compile `{}`: {}", rule.expression(), e))?;
+    let result = match value.ty {
+        Type::Bool => {
+            let message = if rule.message().is_empty() {
+                format!("`{}` returned false", rule.expression())
+            } else {
+                rule.message().to_string()
+            };
+            let code = value.code;
+            quote! {
+                Ok(if #code { None } else { Some(::std::string::String::from(#message)) })
+            }
+        }
+        Type::String => {
+            let code = as_str(&value.code);
+            quote! {
+                let message = ::std::string::String::from(#code);
+                Ok(if message.is_empty() { None } else { Some(message) })
+            }
+        }
+        ty => bail!(
+            "`{}` must evaluate to a bool or a string, got {}",
+            rule.expression(),
+            ty
+        ),
+    };
+    let zero = zero.map(|zero| quote! { if #zero { return Ok(None); } });
+    let now = compiler.now.then(|| quote! { let now = ctx.now(); });
+    Ok(quote! {
+        (|| -> ::core::result::Result<::core::option::Option<::std::string::String>, ::std::string::String> {
+            #zero
+            #now
+            #result
+        })()
+    })
+}
+
+/// A compiled CEL rule, as given to the derive macro by `prost-validate-build`.
+#[derive(Debug, Clone, FromMeta)]
+pub struct CelRule {
+    pub id: String,
+    pub expr: String,
+}
+
+impl CelRule {
+    /// Returns the tokens evaluating the rule and reporting its violation on `name` using `fail`.
+    /// The violations of the message rules are reported on the message itself.
+    pub(crate) fn to_validation_tokens(
+        &self,
+        name: &str,
+        fail: &TokenStream,
+        message: bool,
+    ) -> TokenStream {
+        let expr = syn::parse_str::<TokenStream>(&self.expr)
+            .unwrap_or_else(|e| panic!("{name}: invalid CEL rule {:?}: {e}", self.id));
+        let id = &self.id;
+        let path = message.then(|| quote! { .with_path(::prost_validate::FieldPath::default()) });
+        quote! {
+            match #expr {
+                Ok(None) => {}
+                Ok(Some(message)) => #fail(::prost_validate::Error::new(
+                    #name,
+                    ::prost_validate::errors::cel::Error::Expression {
+                        id: #id.to_string(),
+                        message,
+                    },
+                )#path),
+                Err(err) => #fail(::prost_validate::format_err!(#name, "{}: {}", #id, err)#path),
+            }
+        }
+    }
+}
+
+/// The CEL type of a compiled expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    Bool,
+    Int,
+    Uint,
+    Double,
+    String,
+    Bytes,
+    Duration,
+    Timestamp,
+    Null,
+    List(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Message(MessageDescriptor),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool => write!(f, "bool"),
+            Self::Int => write!(f, "int"),
+            Self::Uint => write!(f, "uint"),
+            Self::Double => write!(f, "double"),
+            Self::String => write!(f, "string"),
+            Self::Bytes => write!(f, "bytes"),
+            Self::Duration => write!(f, "google.protobuf.Duration"),
+            Self::Timestamp => write!(f, "google.protobuf.Timestamp"),
+            Self::Null => write!(f, "null"),
+            Self::List(t) => write!(f, "list({t})"),
+            Self::Map(k, v) => write!(f, "map({k}, {v})"),
+            Self::Message(m) => write!(f, "{}", m.full_name()),
+        }
+    }
+}
+
+/// A compiled expression.
+///
+/// The scalars are compiled to their Rust equivalent, the strings and bytes to values
+/// implementing `AsRef<str>` and `AsRef<[u8]>`, the durations and timestamps to
+/// `time::Duration` and `time::OffsetDateTime`, and the messages to `Option<&Message>`.
+/// The lists and maps are compiled to iterators over their items and `(key, value)` pairs,
+/// which are created anew each time the expression is used.
+#[derive(Debug, Clone)]
+struct Value {
+    code: TokenStream,
+    ty: Type,
+}
+
+impl Value {
+    fn new(code: TokenStream, ty: Type) -> Self {
+        Self { code, ty }
+    }
+}
+
+impl ToTokens for Value {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.code.to_tokens(tokens)
+    }
+}
+
+struct Compiler {
+    /// The module of the generated code, used to refer to the oneof enums.
+    scope: Vec<String>,
+    /// The variables in scope, the innermost last.
+    vars: Vec<(String, Value)>,
+    /// Whether `now` is used.
+    now: bool,
+    next: usize,
+}
+
+impl Compiler {
+    /// Returns a new identifier for a comprehension variable.
+    fn var(&mut self) -> Ident {
+        self.next += 1;
+        format_ident!("var{}", self.next)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Value> {
+        match expr {
+            Expr::Int(v) if *v == i64::MIN => Ok(Value::new(quote! { i64::MIN }, Type::Int)),
+            Expr::Int(v) => Ok(Value::new(int(*v), Type::Int)),
+            Expr::Uint(v) => Ok(Value::new(
+                Literal::u64_suffixed(*v).into_token_stream(),
+                Type::Uint,
+            )),
+            Expr::Double(v) => Ok(Value::new(double(*v)?, Type::Double)),
+            Expr::String(v) => Ok(Value::new(
+                Literal::string(v).into_token_stream(),
+                Type::String,
+            )),
+            Expr::Bytes(v) => Ok(Value::new(
+                Literal::byte_string(v).into_token_stream(),
+                Type::Bytes,
+            )),
+            Expr::Bool(v) => Ok(Value::new(quote! { #v }, Type::Bool)),
+            Expr::Null => Ok(Value::new(quote! { None }, Type::Null)),
+            Expr::Ident(name) => self.ident(name),
+            Expr::Select(operand, name) => {
+                let operand = self.expr(operand)?;
+                self.select(&operand, name)
+            }
+            Expr::Call(target, name, args) => self.call(target.as_deref(), name, args),
+            Expr::Index(operand, index) => self.index(operand, index),
+            Expr::List(items) => self.list(items),
+            Expr::Map(_) => bail!("map literals are not supported"),
+            Expr::Unary(op, operand) => self.unary(*op, operand),
+            Expr::Binary(op, lhs, rhs) => self.binary(*op, lhs, rhs),
+            Expr::Ternary(condition, then, otherwise) => {
+                let condition = self.expr(condition)?;
+                expect(&condition, &Type::Bool, "the condition")?;
+                let then = self.expr(then)?;
+                let otherwise = self.expr(otherwise)?;
+                let (then, otherwise, ty) = unify(then, otherwise)?;
+                Ok(Value::new(
+                    quote! { if #condition { #then } else { #otherwise } },
+                    ty,
+                ))
+            }
+        }
+    }
+
+    fn ident(&mut self, name: &str) -> Result<Value> {
+        if let Some((_, value)) = self.vars.iter().rev().find(|(n, _)| n == name) {
+            return Ok(value.clone());
+        }
+        if name == "now" {
+            self.now = true;
+            return Ok(Value::new(quote! { now }, Type::Timestamp));
+        }
+        bail!("undeclared reference to `{name}`")
+    }
+
+    fn field<'a>(
+        &self,
+        operand: &'a Value,
+        name: &str,
+    ) -> Result<(&'a MessageDescriptor, FieldDescriptor)> {
+        let Type::Message(desc) = &operand.ty else {
+            bail!("no field `{name}` on a value of type {}", operand.ty);
+        };
+        let field = desc
+            .get_field_by_name(name)
+            .ok_or_else(|| anyhow!("no field `{name}` in {}", desc.full_name()))?;
+        Ok((desc, field))
+    }
+
+    /// Returns the path to the variant of the oneof enum of the field.
+    fn variant(&self, desc: &MessageDescriptor, field: &FieldDescriptor) -> Option<TokenStream> {
+        let oneof = field.real_oneof()?;
+        let mut module = module(desc);
+        module.push(to_snake(desc.name()));
+        let common = self
+            .scope
+            .iter()
+            .zip(&module)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let supers = self.scope[common..].iter().map(|_| quote! { super:: });
+        let segments = module[common..].iter().map(|s| ident(s));
+        let name = ident(&to_upper_camel(oneof.name()));
+        let variant = ident(&to_upper_camel(field.name()));
+        Some(quote! { #(#supers)* #(#segments::)* #name::#variant })
+    }
+
+    fn select(&self, operand: &Value, name: &str) -> Result<Value> {
+        let (desc, field) = self.field(operand, name)?;
+        let base = &operand.code;
+        let f = ident(&to_snake(field.name()));
+        if field.is_list() || field.is_map() {
+            let items = items(&field, quote! { m.#f.iter() })?;
+            return Ok(Value::new(
+                quote! { #base.into_iter().flat_map(|m| #items) },
+                items.ty,
+            ));
+        }
+        let value = match self.variant(desc, &field) {
+            Some(variant) => {
+                let o = ident(&to_snake(
+                    field
+                        .containing_oneof()
+                        .map(|o| o.name().to_string())
+                        .unwrap_or_default(),
+                ));
+                quote! {
+                    #base.and_then(|m| match m.#o {
+                        Some(#variant(ref v)) => Some(v),
+                        _ => None,
+                    })
+                }
+            }
+            None if field.supports_presence() => quote! { #base.and_then(|m| m.#f.as_ref()) },
+            None => {
+                let value = convert(&field.kind(), quote! { &m.#f })?;
+                let default = default(&value.ty)?;
+                return Ok(Value::new(
+                    quote! { #base.map(|m| #value).unwrap_or(#default) },
+                    value.ty,
+                ));
+            }
+        };
+        let kind = field.kind();
+        if let Some(Type::Message(desc)) = kind.as_message().map(message_type) {
+            if !is_google(&desc) {
+                return Ok(Value::new(value, Type::Message(desc)));
+            }
+        }
+        let converted = convert(&kind, quote! { v })?;
+        let default = default(&converted.ty)?;
+        Ok(Value::new(
+            quote! { #value.map(|v| #converted).unwrap_or(#default) },
+            converted.ty,
+        ))
+    }
+
+    fn has(&mut self, arg: &Expr) -> Result<Value> {
+        let Expr::Select(operand, name) = arg else {
+            bail!("invalid argument to has(), expected a field selection");
+        };
+        let operand = self.expr(operand)?;
+        let (desc, field) = self.field(&operand, name)?;
+        let base = &operand.code;
+        let f = ident(&to_snake(field.name()));
+        let test = if field.is_list() || field.is_map() {
+            quote! { !m.#f.is_empty() }
+        } else if let Some(variant) = self.variant(desc, &field) {
+            let o = ident(&to_snake(
+                field
+                    .containing_oneof()
+                    .map(|o| o.name().to_string())
+                    .unwrap_or_default(),
+            ));
+            quote! { matches!(m.#o, Some(#variant(_))) }
+        } else if field.supports_presence() {
+            quote! { m.#f.is_some() }
+        } else {
+            match field.kind() {
+                Kind::String | Kind::Bytes => quote! { !m.#f.is_empty() },
+                Kind::Bool => quote! { m.#f },
+                Kind::Double | Kind::Float => quote! { m.#f != 0.0 },
+                _ => quote! { m.#f != 0 },
+            }
+        };
+        Ok(Value::new(
+            quote! { #base.is_some_and(|m| #test) },
+            Type::Bool,
+        ))
+    }
+
+    fn call(&mut self, target: Option<&Expr>, name: &str, args: &[Expr]) -> Result<Value> {
+        match (target, name, args) {
+            (None, "has", [arg]) => self.has(arg),
+            (Some(target), "all" | "exists" | "exists_one" | "map" | "filter", _) => {
+                self.comprehension(target, name, args)
+            }
+            (Some(target), _, _) => {
+                let target = self.expr(target)?;
+                self.method(target, name, args)
+            }
+            (None, "duration" | "timestamp", [Expr::String(s)]) => literal(name, s),
+            (None, "duration" | "timestamp", _) => {
+                bail!("only string literals can be converted with {name}()")
+            }
+            (None, "matches", [target, args @ ..]) => {
+                let target = self.expr(target)?;
+                self.method(target, name, args)
+            }
+            (None, _, [arg]) => {
+                let arg = self.expr(arg)?;
+                match name {
+                    "size" => self.method(arg, name, &[]),
+                    _ => conversion(name, &arg),
+                }
+            }
+            _ => bail!("unsupported function `{name}`"),
+        }
+    }
+
+    fn method(&mut self, target: Value, name: &str, args: &[Expr]) -> Result<Value> {
+        // the literal arguments are checked during the build
+        match (name, args) {
+            ("matches", [Expr::String(pattern)]) => {
+                regex::Regex::new(pattern)
+                    .map_err(|e| format_err!("invalid regular expression: {e}"))?;
+            }
+            ("isIp", [Expr::Int(4 | 6)]) | ("isIp", []) => {}
+            ("isIp", _) => bail!("the version of isIp() must be 4 or 6"),
+            _ => {}
+        }
+        let version = match args {
+            [Expr::Int(v)] => *v,
+            _ => 0,
+        };
+        let literal = match args {
+            [Expr::String(pattern)] => Some(pattern.clone()),
+            _ => None,
+        };
+        let args = args
+            .iter()
+            .map(|arg| self.expr(arg))
+            .collect::<Result<Vec<_>>>()?;
+        let code = match (name, &target.ty, args.as_slice()) {
+            ("size", Type::String, []) => {
+                let s = as_str(&target);
+                size(quote! { #s.chars().count() })
+            }
+            ("size", Type::Bytes, []) => {
+                let b = as_bytes(&target);
+                size(quote! { #b.len() })
+            }
+            ("size", Type::List(_) | Type::Map(..), []) => size(quote! { #target.count() }),
+            ("contains" | "startsWith" | "endsWith", Type::String, [arg])
+                if arg.ty == Type::String =>
+            {
+                let s = as_str(&target);
+                let arg = as_str(arg);
+                let method = match name {
+                    "contains" => quote! { contains },
+                    "startsWith" => quote! { starts_with },
+                    _ => quote! { ends_with },
+                };
+                quote! { #s.#method(#arg) }
+            }
+            ("matches", Type::String, [arg]) if arg.ty == Type::String => {
+                let s = as_str(&target);
+                match literal {
+                    // the literal patterns are compiled once
+                    Some(pattern) => quote! {{
+                        static RE: ::std::sync::OnceLock<::core::result::Result<::regex::Regex, ::std::string::String>> =
+                            ::std::sync::OnceLock::new();
+                        match RE.get_or_init(|| ::regex::Regex::new(#pattern).map_err(|err| err.to_string())) {
+                            Ok(re) => re.is_match(#s),
+                            Err(err) => return Err(err.clone()),
+                        }
+                    }},
+                    None => {
+                        let pattern = as_str(arg);
+                        quote! {
+                            match ::regex::Regex::new(#pattern) {
+                                Ok(re) => re.is_match(#s),
+                                Err(err) => return Err(err.to_string()),
+                            }
+                        }
+                    }
+                }
+            }
+            ("isEmail" | "isHostname" | "isUri" | "isUriRef" | "isIp", Type::String, [])
+            | ("isIp", Type::String, [_]) => {
+                let method = match (name, version) {
+                    ("isEmail", _) => quote! { validate_email },
+                    ("isHostname", _) => quote! { validate_hostname },
+                    ("isUri", _) => quote! { validate_uri },
+                    ("isUriRef", _) => quote! { validate_uri_ref },
+                    (_, 4) => quote! { validate_ipv4 },
+                    (_, 6) => quote! { validate_ipv6 },
+                    _ => quote! { validate_ip },
+                };
+                let s = as_str(&target);
+                quote! { ::prost_validate::ValidateStringExt::#method(&#s).is_ok() }
+            }
+            ("unique", Type::List(item), []) => {
+                let a = Value::new(quote! { (*a) }, (**item).clone());
+                let b = Value::new(quote! { (*b) }, (**item).clone());
+                let eq = equal(&a, &b)?;
+                quote! {
+                    {
+                        let items = #target.collect::<::std::vec::Vec<_>>();
+                        items
+                            .iter()
+                            .enumerate()
+                            .all(|(i, a)| items[i + 1..].iter().all(|b| !(#eq)))
+                    }
+                }
+            }
+            _ => bail!(
+                "unsupported function `{name}` on a value of type {}{}",
+                target.ty,
+                args.iter()
+                    .map(|arg| format!(", {}", arg.ty))
+                    .collect::<String>()
+            ),
+        };
+        let ty = match name {
+            "size" => Type::Int,
+            _ => Type::Bool,
+        };
+        Ok(Value::new(code, ty))
+    }
+
+    /// Compiles the macros iterating over a list or the keys of a map.
+    fn comprehension(&mut self, target: &Expr, name: &str, args: &[Expr]) -> Result<Value> {
+        let range = self.expr(target)?;
+        let (item, pattern_map) = match &range.ty {
+            Type::List(item) => ((**item).clone(), false),
+            Type::Map(key, _) => ((**key).clone(), true),
+            ty => bail!("`{name}` cannot be applied to a value of type {ty}"),
+        };
+        let (var, args) = match args {
+            [Expr::Ident(var), args @ ..] if !args.is_empty() && args.len() <= 2 => (var, args),
+            _ => bail!("invalid arguments to `{name}`"),
+        };
+        let ident = self.var();
+        let pattern = if pattern_map {
+            quote! { (#ident, _) }
+        } else {
+            quote! { #ident }
+        };
+        self.vars
+            .push((var.clone(), Value::new(quote! { #ident }, item.clone())));
+        let body = args
+            .iter()
+            .map(|arg| self.expr(arg))
+            .collect::<Result<Vec<_>>>();
+        self.vars.pop();
+        let body = body?;
+        let predicate = |v: &Value| expect(v, &Type::Bool, format!("the predicate of `{name}`"));
+        let code = match (name, body.as_slice()) {
+            ("all", [p]) => {
+                predicate(p)?;
+                quote! {
+                    {
+                        let mut all = true;
+                        for #pattern in #range {
+                            if !(#p) {
+                                all = false;
+                                break;
+                            }
+                        }
+                        all
+                    }
+                }
+            }
+            ("exists", [p]) => {
+                predicate(p)?;
+                quote! {
+                    {
+                        let mut exists = false;
+                        for #pattern in #range {
+                            if #p {
+                                exists = true;
+                                break;
+                            }
+                        }
+                        exists
+                    }
+                }
+            }
+            ("exists_one", [p]) => {
+                predicate(p)?;
+                quote! {
+                    {
+                        let mut count = 0usize;
+                        for #pattern in #range {
+                            if #p {
+                                count += 1;
+                            }
+                        }
+                        count == 1
+                    }
+                }
+            }
+            ("filter", [p]) => {
+                predicate(p)?;
+                return Ok(Value::new(
+                    quote! {
+                        {
+                            let mut items = ::std::vec::Vec::new();
+                            for #pattern in #range {
+                                if #p {
+                                    items.push(#ident);
+                                }
+                            }
+                            items
+                        }
+                        .into_iter()
+                    },
+                    Type::List(Box::new(item)),
+                ));
+            }
+            ("map", [e]) | ("map", [_, e]) => {
+                let (e, ty) = owned(e)?;
+                let push = match body.as_slice() {
+                    [p, _] => {
+                        predicate(p)?;
+                        quote! {
+                            if #p {
+                                items.push(#e);
+                            }
+                        }
+                    }
+                    _ => quote! { items.push(#e); },
+                };
+                return Ok(Value::new(
+                    quote! {
+                        {
+                            let mut items = ::std::vec::Vec::new();
+                            for #pattern in #range {
+                                #push
+                            }
+                            items
+                        }
+                        .into_iter()
+                    },
+                    Type::List(Box::new(ty)),
+                ));
+            }
+            _ => bail!("invalid arguments to `{name}`"),
+        };
+        Ok(Value::new(code, Type::Bool))
+    }
+
+    fn index(&mut self, operand: &Expr, index: &Expr) -> Result<Value> {
+        let operand = self.expr(operand)?;
+        let index = self.expr(index)?;
+        match &operand.ty {
+            Type::List(item) => {
+                if !matches!(index.ty, Type::Int | Type::Uint) {
+                    bail!("a list cannot be indexed by a value of type {}", index.ty);
+                }
+                Ok(Value::new(
+                    quote! {
+                        {
+                            let index = usize::try_from(#index)
+                                .map_err(|_| ::std::string::String::from("index out of range"))?;
+                            #operand
+                                .nth(index)
+                                .ok_or_else(|| ::std::string::String::from("index out of range"))?
+                        }
+                    },
+                    (**item).clone(),
+                ))
+            }
+            Type::Map(key, value) => {
+                let needle = Value::new(quote! { needle }, index.ty.clone());
+                let eq = equal(&Value::new(quote! { item.0 }, (**key).clone()), &needle)?;
+                Ok(Value::new(
+                    quote! {
+                        {
+                            let needle = #index;
+                            #operand
+                                .find(|item| #eq)
+                                .map(|item| item.1)
+                                .ok_or_else(|| ::std::string::String::from("no such key"))?
+                        }
+                    },
+                    (**value).clone(),
+                ))
+            }
+            ty => bail!("a value of type {ty} cannot be indexed"),
+        }
+    }
+
+    fn list(&mut self, items: &[Expr]) -> Result<Value> {
+        let items = items
+            .iter()
+            .map(|item| self.expr(item).and_then(|item| owned(&item)))
+            .collect::<Result<Vec<_>>>()?;
+        let ty = match items.first() {
+            Some((_, ty)) => ty.clone(),
+            None => bail!("empty list literals are not supported"),
+        };
+        if let Some((_, other)) = items.iter().find(|(_, t)| *t != ty) {
+            bail!("list literals must be homogeneous, got {ty} and {other}");
+        }
+        let items = items.iter().map(|(code, _)| code);
+        Ok(Value::new(
+            quote! { [#(#items),*].into_iter() },
+            Type::List(Box::new(ty)),
+        ))
+    }
+
+    fn unary(&mut self, op: UnaryOp, operand: &Expr) -> Result<Value> {
+        // the negative literals are folded
+        match (op, operand) {
+            (UnaryOp::Neg, Expr::Int(v)) if *v != i64::MIN => {
+                return Ok(Value::new(int(-*v), Type::Int));
+            }
+            (UnaryOp::Neg, Expr::Double(v)) => return Ok(Value::new(double(-*v)?, Type::Double)),
+            _ => {}
+        }
+        let operand = self.expr(operand)?;
+        let code = match (op, &operand.ty) {
+            (UnaryOp::Not, Type::Bool) => quote! { !(#operand) },
+            (UnaryOp::Neg, Type::Int) => {
+                let overflow = error("integer overflow");
+                quote! { (#operand).checked_neg().ok_or_else(|| #overflow)? }
+            }
+            (UnaryOp::Neg, Type::Double) => quote! { -(#operand) },
+            (UnaryOp::Neg, Type::Duration) => {
+                let overflow = error("duration overflow");
+                quote! { (#operand).checked_neg().ok_or_else(|| #overflow)? }
+            }
+            (op, ty) => bail!(
+                "no matching overload for `{}` on {}",
+                match op {
+                    UnaryOp::Not => "!",
+                    UnaryOp::Neg => "-",
+                },
+                ty
+            ),
+        };
+        Ok(Value::new(code, operand.ty))
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: &Expr, rhs: &Expr) -> Result<Value> {
+        let lhs = self.expr(lhs)?;
+        let rhs = self.expr(rhs)?;
+        let (code, ty) = match (op, &lhs.ty, &rhs.ty) {
+            (BinaryOp::Or | BinaryOp::And, Type::Bool, Type::Bool) => {
+                let code = match op {
+                    BinaryOp::Or => quote! { (#lhs || #rhs) },
+                    _ => quote! { (#lhs && #rhs) },
+                };
+                (code, Type::Bool)
+            }
+            (BinaryOp::Eq, _, _) => (equal(&lhs, &rhs)?, Type::Bool),
+            (BinaryOp::Ne, _, _) => {
+                let eq = equal(&lhs, &rhs)?;
+                (quote! { !(#eq) }, Type::Bool)
+            }
+            (BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, _, _) => {
+                (compare(op, &lhs, &rhs)?, Type::Bool)
+            }
+            (BinaryOp::In, _, Type::List(item)) => {
+                let eq = equal(
+                    &Value::new(quote! { v }, (**item).clone()),
+                    &Value::new(quote! { needle }, lhs.ty.clone()),
+                )?;
+                let code = quote! {
+                    {
+                        let needle = #lhs;
+                        #rhs.any(|v| #eq)
+                    }
+                };
+                (code, Type::Bool)
+            }
+            (BinaryOp::In, _, Type::Map(key, _)) => {
+                let eq = equal(
+                    &Value::new(quote! { k }, (**key).clone()),
+                    &Value::new(quote! { needle }, lhs.ty.clone()),
+                )?;
+                let code = quote! {
+                    {
+                        let needle = #lhs;
+                        #rhs.any(|(k, _)| #eq)
+                    }
+                };
+                (code, Type::Bool)
+            }
+            (_, _, _) => arithmetic(op, &lhs, &rhs)?,
+        };
+        Ok(Value::new(code, ty))
+    }
+}
+
+fn arithmetic(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<(TokenStream, Type)> {
+    let checked = |method: TokenStream, message: &str, ty: Type| {
+        let err = error(message);
+        Ok((quote! { (#lhs).#method(#rhs).ok_or_else(|| #err)? }, ty))
+    };
+    match (op, &lhs.ty, &rhs.ty) {
+        (BinaryOp::Add, Type::Int, Type::Int) | (BinaryOp::Add, Type::Uint, Type::Uint) => {
+            checked(quote! { checked_add }, "integer overflow", lhs.ty.clone())
+        }
+        (BinaryOp::Sub, Type::Int, Type::Int) | (BinaryOp::Sub, Type::Uint, Type::Uint) => {
+            checked(quote! { checked_sub }, "integer overflow", lhs.ty.clone())
+        }
+        (BinaryOp::Mul, Type::Int, Type::Int) | (BinaryOp::Mul, Type::Uint, Type::Uint) => {
+            checked(quote! { checked_mul }, "integer overflow", lhs.ty.clone())
+        }
+        (BinaryOp::Div, Type::Int, Type::Int) | (BinaryOp::Div, Type::Uint, Type::Uint) => checked(
+            quote! { checked_div },
+            "division by zero or overflow",
+            lhs.ty.clone(),
+        ),
+        (BinaryOp::Rem, Type::Int, Type::Int) | (BinaryOp::Rem, Type::Uint, Type::Uint) => checked(
+            quote! { checked_rem },
+            "modulus by zero or overflow",
+            lhs.ty.clone(),
+        ),
+        (
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div,
+            Type::Double,
+            Type::Double,
+        ) => {
+            let op = match op {
+                BinaryOp::Add => quote! { + },
+                BinaryOp::Sub => quote! { - },
+                BinaryOp::Mul => quote! { * },
+                _ => quote! { / },
+            };
+            Ok((quote! { (#lhs #op #rhs) }, Type::Double))
+        }
+        (BinaryOp::Add, Type::String, Type::String) => {
+            let (lhs, rhs) = (as_str(lhs), as_str(rhs));
+            Ok((quote! { [#lhs, #rhs].concat() }, Type::String))
+        }
+        (BinaryOp::Add, Type::Bytes, Type::Bytes) => {
+            let (lhs, rhs) = (as_bytes(lhs), as_bytes(rhs));
+            Ok((quote! { [#lhs, #rhs].concat() }, Type::Bytes))
+        }
+        (BinaryOp::Add, Type::Timestamp, Type::Duration) => checked(
+            quote! { checked_add },
+            "timestamp overflow",
+            Type::Timestamp,
+        ),
+        (BinaryOp::Add, Type::Duration, Type::Timestamp) => {
+            let err = error("timestamp overflow");
+            Ok((
+                quote! { (#rhs).checked_add(#lhs).ok_or_else(|| #err)? },
+                Type::Timestamp,
+            ))
+        }
+        (BinaryOp::Sub, Type::Timestamp, Type::Duration) => checked(
+            quote! { checked_sub },
+            "timestamp overflow",
+            Type::Timestamp,
+        ),
+        (BinaryOp::Sub, Type::Timestamp, Type::Timestamp) => {
+            Ok((quote! { (#lhs - #rhs) }, Type::Duration))
+        }
+        (BinaryOp::Add, Type::Duration, Type::Duration) => {
+            checked(quote! { checked_add }, "duration overflow", Type::Duration)
+        }
+        (BinaryOp::Sub, Type::Duration, Type::Duration) => {
+            checked(quote! { checked_sub }, "duration overflow", Type::Duration)
+        }
+        (op, lhs, rhs) => bail!(
+            "no matching overload for `{}` on {} and {}",
+            op.as_str(),
+            lhs,
+            rhs
+        ),
+    }
+}
+
+/// Returns the code comparing the values for equality.
+fn equal(lhs: &Value, rhs: &Value) -> Result<TokenStream> {
+    Ok(match (&lhs.ty, &rhs.ty) {
+        (Type::Int, Type::Int)
+        | (Type::Uint, Type::Uint)
+        | (Type::Double, Type::Double)
+        | (Type::Bool, Type::Bool)
+        | (Type::Duration, Type::Duration)
+        | (Type::Timestamp, Type::Timestamp) => quote! { (#lhs == #rhs) },
+        (Type::Int, Type::Uint) | (Type::Uint, Type::Int) => {
+            quote! { (i128::from(#lhs) == i128::from(#rhs)) }
+        }
+        (Type::Int | Type::Uint, Type::Double) => quote! { ((#lhs as f64) == #rhs) },
+        (Type::Double, Type::Int | Type::Uint) => quote! { (#lhs == (#rhs as f64)) },
+        (Type::String, Type::String) => {
+            let (lhs, rhs) = (as_str(lhs), as_str(rhs));
+            quote! { (#lhs == #rhs) }
+        }
+        (Type::Bytes, Type::Bytes) => {
+            let (lhs, rhs) = (as_bytes(lhs), as_bytes(rhs));
+            quote! { (#lhs == #rhs) }
+        }
+        (Type::Message(_), Type::Null) => quote! { #lhs.is_none() },
+        (Type::Null, Type::Message(_)) => quote! { #rhs.is_none() },
+        (Type::Null, Type::Null) => quote! { true },
+        (lhs, rhs) => bail!("no matching overload for `==` on {lhs} and {rhs}"),
+    })
+}
+
+/// Returns the code comparing the values for ordering.
+fn compare(op: BinaryOp, lhs: &Value, rhs: &Value) -> Result<TokenStream> {
+    let op = match op {
+        BinaryOp::Lt => quote! { < },
+        BinaryOp::Le => quote! { <= },
+        BinaryOp::Gt => quote! { > },
+        _ => quote! { >= },
+    };
+    Ok(match (&lhs.ty, &rhs.ty) {
+        (Type::Int, Type::Int)
+        | (Type::Uint, Type::Uint)
+        | (Type::Double, Type::Double)
+        | (Type::Bool, Type::Bool)
+        | (Type::Duration, Type::Duration)
+        | (Type::Timestamp, Type::Timestamp) => quote! { (#lhs #op #rhs) },
+        (Type::Int, Type::Uint) | (Type::Uint, Type::Int) => {
+            quote! { (i128::from(#lhs) #op i128::from(#rhs)) }
+        }
+        (Type::Int | Type::Uint, Type::Double) => quote! { ((#lhs as f64) #op #rhs) },
+        (Type::Double, Type::Int | Type::Uint) => quote! { (#lhs #op (#rhs as f64)) },
+        (Type::String, Type::String) => {
+            let (lhs, rhs) = (as_str(lhs), as_str(rhs));
+            quote! { (#lhs #op #rhs) }
+        }
+        (Type::Bytes, Type::Bytes) => {
+            let (lhs, rhs) = (as_bytes(lhs), as_bytes(rhs));
+            quote! { (#lhs #op #rhs) }
+        }
+        (l, r) => bail!("no matching overload for `{}` on {l} and {r}", op),
+    })
+}
+
+/// Returns the branches of a conditional, converted to the same Rust type.
+fn unify(then: Value, otherwise: Value) -> Result<(TokenStream, TokenStream, Type)> {
+    match (&then.ty, &otherwise.ty) {
+        (Type::Message(_), Type::Null) => Ok((then.code, quote! { None }, then.ty)),
+        (Type::Null, Type::Message(_)) => Ok((quote! { None }, otherwise.code, otherwise.ty)),
+        (Type::List(_) | Type::Map(..), _) | (_, Type::List(_) | Type::Map(..)) => {
+            bail!(
+                "conditionals on {} and {} are not supported",
+                then.ty,
+                otherwise.ty
+            )
+        }
+        (l, r) if l == r => {
+            let (then, ty) = owned(&then)?;
+            let (otherwise, _) = owned(&otherwise)?;
+            Ok((then, otherwise, ty))
+        }
+        (l, r) => bail!("the branches of the conditional have different types: {l} and {r}"),
+    }
+}
+
+/// Returns the code of the value as an owned value, so that it can be stored or returned.
+fn owned(value: &Value) -> Result<(TokenStream, Type)> {
+    let code = match &value.ty {
+        Type::String => {
+            let s = as_str(value);
+            quote! { ::std::string::String::from(#s) }
+        }
+        Type::Bytes => {
+            let b = as_bytes(value);
+            quote! { #b.to_vec() }
+        }
+        Type::List(_) | Type::Map(..) | Type::Message(_) | Type::Null => {
+            bail!("values of type {} cannot be stored", value.ty)
+        }
+        _ => value.code.clone(),
+    };
+    Ok((code, value.ty.clone()))
+}
+
+/// Compiles the type conversion functions.
+fn conversion(name: &str, arg: &Value) -> Result<Value> {
+    let (code, ty) = match (name, &arg.ty) {
+        ("int", Type::Int) | ("uint", Type::Uint) | ("double", Type::Double) => {
+            return Ok(arg.clone());
+        }
+        ("int", Type::Uint) => {
+            let err = error("int overflow");
+            (quote! { i64::try_from(#arg).map_err(|_| #err)? }, Type::Int)
+        }
+        ("int", Type::Double) => {
+            let err = error("int overflow");
+            let code = quote! {
+                {
+                    let v = (#arg).trunc();
+                    if v >= -9223372036854775808.0 && v < 9223372036854775808.0 {
+                        v as i64
+                    } else {
+                        return Err(#err);
+                    }
+                }
+            };
+            (code, Type::Int)
+        }
+        ("int", Type::Timestamp) => (quote! { (#arg).unix_timestamp() }, Type::Int),
+        ("uint", Type::Int) => {
+            let err = error("uint overflow");
+            (
+                quote! { u64::try_from(#arg).map_err(|_| #err)? },
+                Type::Uint,
+            )
+        }
+        ("uint", Type::Double) => {
+            let err = error("uint overflow");
+            let code = quote! {
+                {
+                    let v = (#arg).trunc();
+                    if v >= 0.0 && v < 18446744073709551616.0 {
+                        v as u64
+                    } else {
+                        return Err(#err);
+                    }
+                }
+            };
+            (code, Type::Uint)
+        }
+        ("double", Type::Int | Type::Uint) => (quote! { (#arg as f64) }, Type::Double),
+        ("int" | "uint" | "double", Type::String) => {
+            let s = as_str(arg);
+            let (target, ty) = match name {
+                "int" => (quote! { i64 }, Type::Int),
+                "uint" => (quote! { u64 }, Type::Uint),
+                _ => (quote! { f64 }, Type::Double),
+            };
+            (
+                quote! { #s.parse::<#target>().map_err(|e| e.to_string())? },
+                ty,
+            )
+        }
+        ("string", Type::String) => (owned(arg)?.0, Type::String),
+        ("string", Type::Int | Type::Uint | Type::Double | Type::Bool) => {
+            (quote! { (#arg).to_string() }, Type::String)
+        }
+        ("string", Type::Bytes) => {
+            let b = as_bytes(arg);
+            (
+                quote! { ::std::string::String::from_utf8(#b.to_vec()).map_err(|e| e.to_string())? },
+                Type::String,
+            )
+        }
+        ("bytes", Type::Bytes) => return Ok(arg.clone()),
+        ("bytes", Type::String) => {
+            let s = as_str(arg);
+            (quote! { #s.as_bytes().to_vec() }, Type::Bytes)
+        }
+        ("int" | "uint" | "double" | "string" | "bytes", ty) => {
+            bail!("no matching overload for `{name}` on {ty}")
+        }
+        _ => bail!("unsupported function `{name}`"),
+    };
+    Ok(Value::new(code, ty))
+}
+
+/// Compiles the `duration` and `timestamp` literals.
+fn literal(name: &str, s: &str) -> Result<Value> {
+    match name {
+        "duration" => {
+            let (seconds, nanos) = parse_duration(s)?;
+            Ok(Value::new(
+                quote! { ::prost_validate::utils::duration(#seconds, #nanos) },
+                Type::Duration,
+            ))
+        }
+        _ => {
+            let t = OffsetDateTime::parse(s, &Rfc3339)
+                .map_err(|e| format_err!("invalid timestamp {s:?}: {e}"))?;
+            let seconds = t.unix_timestamp();
+            let nanos = i32::try_from(t.nanosecond())?;
+            Ok(Value::new(
+                quote! { ::prost_validate::utils::datetime(#seconds, #nanos) },
+                Type::Timestamp,
+            ))
+        }
+    }
+}
+
+/// Parses a duration, e.g. `1h30m` or `-1.5s`, into its seconds and nanoseconds.
+fn parse_duration(s: &str) -> Result<(i64, i32)> {
+    let invalid = || format_err!("invalid duration {s:?}");
+    let (negative, mut rest) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut total: i128 = 0;
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(invalid)?;
+        let (number, tail) = rest.split_at(end);
+        let unit_end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let scale: i128 = match unit {
+            "ns" => 1,
+            "us" | "µs" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60_000_000_000,
+            "h" => 3_600_000_000_000,
+            _ => return Err(invalid()),
+        };
+        let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid());
+        }
+        let int = if int.is_empty() {
+            0
+        } else {
+            int.parse::<i128>().map_err(|_| invalid())?
+        };
+        total = int
+            .checked_mul(scale)
+            .and_then(|v| total.checked_add(v))
+            .ok_or_else(invalid)?;
+        let mut digits = scale;
+        for c in frac.chars() {
+            digits /= 10;
+            total = total
+                .checked_add(i128::from(c.to_digit(10).ok_or_else(invalid)?) * digits)
+                .ok_or_else(invalid)?;
+        }
+        rest = tail;
+    }
+    if negative {
+        total = -total;
+    }
+    let seconds = i64::try_from(total / 1_000_000_000).map_err(|_| invalid())?;
+    let nanos = i32::try_from(total % 1_000_000_000).map_err(|_| invalid())?;
+    Ok((seconds, nanos))
+}
+
+/// Returns `this` for the rules of the field.
+fn this(field: &FieldDescriptor) -> Result<Value> {
+    if field.is_list() || field.is_map() {
+        return items(field, quote! { this.iter() });
+    }
+    let kind = field.kind();
+    match kind.as_message() {
+        // the wrappers are unwrapped by the derived validation
+        Some(m) if WRAPPERS.contains(&m.full_name()) => {
+            let value = m
+                .get_field_by_name("value")
+                .ok_or_else(|| format_err!("{}: value field not found", m.full_name()))?;
+            convert(&value.kind(), quote! { this })
+        }
+        _ => convert(&kind, quote! { this }),
+    }
+}
+
+/// Returns the items of the list or map field, `iter` being the iterator over the field items.
+fn items(field: &FieldDescriptor, iter: TokenStream) -> Result<Value> {
+    match field.kind().as_message() {
+        Some(entry) if field.is_map() => {
+            let key = convert(&entry.map_entry_key_field().kind(), quote! { k })?;
+            let value = convert(&entry.map_entry_value_field().kind(), quote! { v })?;
+            Ok(Value::new(
+                quote! { #iter.map(|(k, v)| (#key, #value)) },
+                Type::Map(Box::new(key.ty), Box::new(value.ty)),
+            ))
+        }
+        _ => {
+            let item = convert(&field.kind(), quote! { v })?;
+            Ok(Value::new(
+                quote! { #iter.map(|v| #item) },
+                Type::List(Box::new(item.ty)),
+            ))
+        }
+    }
+}
+
+/// Converts `v`, a reference to a value of the given kind, to its compiled representation.
+fn convert(kind: &Kind, v: TokenStream) -> Result<Value> {
+    let (code, ty) = match kind {
+        Kind::Double => (quote! { *(#v) }, Type::Double),
+        Kind::Float => (quote! { f64::from(*(#v)) }, Type::Double),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 | Kind::Enum(_) => {
+            (quote! { i64::from(*(#v)) }, Type::Int)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => (quote! { *(#v) }, Type::Int),
+        Kind::Uint32 | Kind::Fixed32 => (quote! { u64::from(*(#v)) }, Type::Uint),
+        Kind::Uint64 | Kind::Fixed64 => (quote! { *(#v) }, Type::Uint),
+        Kind::Bool => (quote! { *(#v) }, Type::Bool),
+        Kind::String => (quote! { (#v).as_str() }, Type::String),
+        Kind::Bytes => (quote! { &(#v)[..] }, Type::Bytes),
+        Kind::Message(m) => match message_type(m) {
+            Type::Timestamp => (
+                quote! { ::prost_validate::utils::datetime((#v).seconds, (#v).nanos) },
+                Type::Timestamp,
+            ),
+            Type::Duration => (
+                quote! { ::prost_validate::utils::duration((#v).seconds, (#v).nanos) },
+                Type::Duration,
+            ),
+            Type::Message(m) if is_google(&m) => bail!("unsupported type {}", m.full_name()),
+            ty => (quote! { Some(#v) }, ty),
+        },
+    };
+    Ok(Value::new(code, ty))
+}
+
+fn message_type(desc: &MessageDescriptor) -> Type {
+    match desc.full_name() {
+        "google.protobuf.Timestamp" => Type::Timestamp,
+        "google.protobuf.Duration" => Type::Duration,
+        _ => Type::Message(desc.clone()),
+    }
+}
+
+fn is_google(desc: &MessageDescriptor) -> bool {
+    desc.package_name() == "google.protobuf"
+}
+
+/// Returns the default value of the type, used for the unpopulated fields.
+fn default(ty: &Type) -> Result<TokenStream> {
+    Ok(match ty {
+        Type::Bool => quote! { false },
+        Type::Int => quote! { 0i64 },
+        Type::Uint => quote! { 0u64 },
+        Type::Double => quote! { 0f64 },
+        Type::String => quote! { "" },
+        Type::Bytes => quote! { &[][..] },
+        Type::Duration => quote! { ::prost_validate::utils::duration(0, 0) },
+        Type::Timestamp => quote! { ::prost_validate::utils::datetime(0, 0) },
+        ty => bail!("no default value for type {ty}"),
+    })
+}
+
+/// Returns the code checking whether `this` is the zero value of the field.
+fn is_zero(field: &FieldDescriptor) -> TokenStream {
+    if field.is_list() || field.is_map() {
+        return quote! { this.is_empty() };
+    }
+    match field.kind() {
+        Kind::String | Kind::Bytes => quote! { this.is_empty() },
+        Kind::Bool => quote! { !*this },
+        Kind::Double | Kind::Float => quote! { *this == 0.0 },
+        Kind::Message(_) => quote! { false },
+        _ => quote! { *this == 0 },
+    }
+}
+
+/// Returns the Rust module of the package of the message.
+fn package(desc: &MessageDescriptor) -> Vec<String> {
+    desc.package_name()
+        .split('.')
+        .filter(|s| !s.is_empty())
+        .map(to_snake)
+        .collect()
+}
+
+/// Returns the Rust module of the message.
+fn module(desc: &MessageDescriptor) -> Vec<String> {
+    let mut module = package(desc);
+    let mut parents = Vec::new();
+    let mut parent = desc.parent_message();
+    while let Some(p) = parent {
+        parents.push(to_snake(p.name()));
+        parent = p.parent_message();
+    }
+    module.extend(parents.into_iter().rev());
+    module
+}
+
+fn ident(s: &str) -> Ident {
+    match s.strip_prefix("r#") {
+        Some(s) => Ident::new_raw(s, Span::call_site()),
+        None => Ident::new(s, Span::call_site()),
+    }
+}
+
+fn int(v: i64) -> TokenStream {
+    let lit = Literal::i64_suffixed(v.abs());
+    if v < 0 {
+        quote! { (-#lit) }
+    } else {
+        quote! { #lit }
+    }
+}
+
+fn double(v: f64) -> Result<TokenStream> {
+    if !v.is_finite() {
+        bail!("double literal out of range");
+    }
+    let lit = Literal::f64_suffixed(v.abs());
+    Ok(if v.is_sign_negative() {
+        quote! { (-#lit) }
+    } else {
+        quote! { #lit }
+    })
+}
+
+fn size(len: TokenStream) -> TokenStream {
+    quote! { i64::try_from(#len).unwrap_or(i64::MAX) }
+}
+
+/// Returns the evaluation error `message`.
+fn error(message: &str) -> TokenStream {
+    quote! { ::std::string::String::from(#message) }
+}
+
+fn as_str(value: &impl ToTokens) -> TokenStream {
+    quote! { ::core::convert::AsRef::<str>::as_ref(&#value) }
+}
+
+fn as_bytes(value: &impl ToTokens) -> TokenStream {
+    quote! { ::core::convert::AsRef::<[u8]>::as_ref(&#value) }
+}
+
+fn expect(value: &Value, ty: &Type, what: impl fmt::Display) -> Result<()> {
+    if value.ty != *ty {
+        bail!("{what} must be of type {ty}, got {}", value.ty);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_validate_types::DESCRIPTOR_POOL;
+
+    fn rule(expression: &str) -> Rule {
+        Rule {
+            id: Some("test".to_string()),
+            message: None,
+            expression: Some(expression.to_string()),
+        }
+    }
+
+    fn compile(message: &str, expression: &str) -> Result<TokenStream> {
+        let desc = DESCRIPTOR_POOL.get_message_by_name(message).unwrap();
+        compile_message(&desc, &rule(expression), false)
+    }
+
+    #[test]
+    fn test_compile_message() {
+        for expression in [
+            "this.min_len <= this.max_len",
+            "!has(this.pattern) || this.pattern.size() > 0",
+            "this.in.all(s, s.startsWith('a')) ? '' : 'invalid'",
+            "this.not_in.exists(s, s in this.in) || size(this.in) == 0",
+            "this.in.map(s, s + '!').filter(s, s.endsWith('!')).size() == size(this.in)",
+            "has(this.well_known_regex) || has(this.email) || this.strict",
+            "this.len == 0u || int(this.len) + 1 > 0",
+            "this.in.unique() && this.prefix.matches('^[a-z]+$')",
+            "timestamp('2024-01-01T00:00:00Z') + duration('1h30m') > now",
+        ] {
+            compile("validate.StringRules", expression)
+                .unwrap_or_else(|e| panic!("{expression}: {e}"));
+        }
+        compile("validate.MessageRules", "has(this.skip) && this.skip").unwrap();
+        compile(
+            "validate.FieldRules",
+            "has(this.string) && this.string.min_len < 10u",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        for expression in [
+            "this.unknown",
+            "this.min_len < 'a'",
+            "this.min_len + 1",
+            "this.in.all(s, s)",
+            "this.prefix.matches('(')",
+            "unknown(this.prefix)",
+            "this.prefix.",
+            "duration(this.prefix) > duration('1s')",
+            "duration('1y') > duration('1s')",
+        ] {
+            assert!(
+                compile("validate.StringRules", expression).is_err(),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("60s").unwrap(), (60, 0));
+        assert_eq!(parse_duration("1h30m").unwrap(), (5400, 0));
+        assert_eq!(parse_duration("-1.5s").unwrap(), (-1, -500_000_000));
+        assert_eq!(parse_duration("1ms2ns").unwrap(), (0, 1_000_002));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("1").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("99999999999999999999999999999999999999h").is_err());
+    }
+}
@@ -0,0 +1,622 @@
+//! A recursive descent parser for the CEL expressions.
+//!
+//! It accepts the CEL syntax, except for the message construction expressions,
+//! the macros being parsed as regular calls.
+
+use anyhow::{anyhow, bail, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Int(i64),
+    Uint(u64),
+    Double(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Bool(bool),
+    Null,
+    Ident(String),
+    /// `operand.field`
+    Select(Box<Expr>, String),
+    /// `target.function(args)` or `function(args)`
+    Call(Option<Box<Expr>>, String, Vec<Expr>),
+    /// `operand[index]`
+    Index(Box<Expr>, Box<Expr>),
+    List(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `condition ? then : otherwise`
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Or => "||",
+            Self::And => "&&",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::In => "in",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(u64),
+    Uint(u64),
+    Double(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Ident(String),
+    Punct(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Uint(v) => write!(f, "{v}u"),
+            Self::Double(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v:?}"),
+            Self::Bytes(v) => write!(f, "b{:?}", String::from_utf8_lossy(v)),
+            Self::Ident(v) => write!(f, "{v}"),
+            Self::Punct(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// The punctuations, the longest first.
+const PUNCTS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "(", ")", "[", "]", "{", "}", ".", ",", ":", "?", "!", "-",
+    "+", "*", "/", "%", "<", ">",
+];
+
+/// Parses the CEL expression.
+pub(crate) fn parse(source: &str) -> Result<Expr> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => bail!("unexpected `{token}`"),
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let (token, next) = number(&chars, i)?;
+            tokens.push(token);
+            i = next;
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident = chars[start..i].iter().collect::<String>();
+            let prefix = ident.to_ascii_lowercase();
+            if matches!(chars.get(i), Some('"' | '\''))
+                && matches!(prefix.as_str(), "r" | "b" | "rb" | "br")
+            {
+                let (value, next) = string(&chars, i, prefix.contains('r'))?;
+                tokens.push(if prefix.contains('b') {
+                    Token::Bytes(value)
+                } else {
+                    Token::String(
+                        String::from_utf8(value).map_err(|_| anyhow!("invalid UTF-8 string"))?,
+                    )
+                });
+                i = next;
+                continue;
+            }
+            tokens.push(Token::Ident(ident));
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            let (value, next) = string(&chars, i, false)?;
+            tokens.push(Token::String(
+                String::from_utf8(value).map_err(|_| anyhow!("invalid UTF-8 string"))?,
+            ));
+            i = next;
+            continue;
+        }
+        let punct = PUNCTS
+            .iter()
+            .find(|p| {
+                p.chars()
+                    .enumerate()
+                    .all(|(j, p)| chars.get(i + j) == Some(&p))
+            })
+            .ok_or_else(|| anyhow!("unexpected character `{c}`"))?;
+        tokens.push(Token::Punct(punct));
+        i += punct.len();
+    }
+    Ok(tokens)
+}
+
+fn number(chars: &[char], start: usize) -> Result<(Token, usize)> {
+    let mut i = start;
+    let hex = chars[i] == '0' && matches!(chars.get(i + 1), Some('x' | 'X'));
+    let mut double = false;
+    if hex {
+        i += 2;
+        while i < chars.len() && chars[i].is_ascii_hexdigit() {
+            i += 1;
+        }
+    } else {
+        while i < chars.len() {
+            match chars[i] {
+                '0'..='9' => {}
+                '.' if !double && chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                    double = true
+                }
+                'e' | 'E' => {
+                    double = true;
+                    if matches!(chars.get(i + 1), Some('+' | '-')) {
+                        i += 1;
+                    }
+                }
+                _ => break,
+            }
+            i += 1;
+        }
+    }
+    let text = chars[start..i].iter().collect::<String>();
+    if double {
+        let value = text
+            .parse::<f64>()
+            .map_err(|_| anyhow!("invalid double literal `{text}`"))?;
+        return Ok((Token::Double(value), i));
+    }
+    let value = if hex {
+        u64::from_str_radix(&text[2..], 16)
+    } else {
+        text.parse::<u64>()
+    }
+    .map_err(|_| anyhow!("invalid integer literal `{text}`"))?;
+    if matches!(chars.get(i), Some('u' | 'U')) {
+        return Ok((Token::Uint(value), i + 1));
+    }
+    Ok((Token::Int(value), i))
+}
+
+fn string(chars: &[char], start: usize, raw: bool) -> Result<(Vec<u8>, usize)> {
+    let quote = chars[start];
+    let triple = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let delimiter = if triple { 3 } else { 1 };
+    let mut i = start + delimiter;
+    let mut out = Vec::new();
+    loop {
+        let c = *chars
+            .get(i)
+            .ok_or_else(|| anyhow!("unterminated string literal"))?;
+        if c == quote
+            && (!triple || (chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote)))
+        {
+            return Ok((out, i + delimiter));
+        }
+        if c == '\n' && !triple {
+            bail!("unterminated string literal");
+        }
+        if c != '\\' || raw {
+            let mut buf = [0; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            i += 1;
+            continue;
+        }
+        let escape = *chars
+            .get(i + 1)
+            .ok_or_else(|| anyhow!("unterminated string literal"))?;
+        i += 2;
+        let simple = match escape {
+            'a' => Some(0x07),
+            'b' => Some(0x08),
+            'f' => Some(0x0c),
+            'n' => Some(b'\n'),
+            'r' => Some(b'\r'),
+            't' => Some(b'\t'),
+            'v' => Some(0x0b),
+            '\\' | '\'' | '"' | '`' | '?' => Some(escape as u8),
+            _ => None,
+        };
+        if let Some(byte) = simple {
+            out.push(byte);
+            continue;
+        }
+        let (len, radix) = match escape {
+            'x' | 'X' => (2, 16),
+            'u' => (4, 16),
+            'U' => (8, 16),
+            '0'..='3' => {
+                i -= 1;
+                (3, 8)
+            }
+            _ => bail!("invalid escape sequence `\\{escape}`"),
+        };
+        let digits = chars
+            .get(i..i + len)
+            .ok_or_else(|| anyhow!("invalid escape sequence"))?
+            .iter()
+            .collect::<String>();
+        let value = u32::from_str_radix(&digits, radix)
+            .map_err(|_| anyhow!("invalid escape sequence `{digits}`"))?;
+        i += len;
+        match escape {
+            'u' | 'U' => {
+                let c = char::from_u32(value)
+                    .ok_or_else(|| anyhow!("invalid unicode escape sequence `{digits}`"))?;
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => out.push(u8::try_from(value).map_err(|_| anyhow!("invalid escape sequence"))?),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end of expression"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(token) => bail!("expected `{punct}`, found `{token}`"),
+            None => bail!("expected `{punct}`, found the end of expression"),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let condition = self.or()?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.or()?;
+        self.expect(":")?;
+        let otherwise = self.expr()?;
+        Ok(Expr::Ternary(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut lhs = self.and()?;
+        while self.eat("||") {
+            let rhs = self.and()?;
+            lhs = Expr::Binary(BinaryOp::Or, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut lhs = self.relation()?;
+        while self.eat("&&") {
+            let rhs = self.relation()?;
+            lhs = Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn relation(&mut self) -> Result<Expr> {
+        let mut lhs = self.addition()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("==")) => BinaryOp::Eq,
+                Some(Token::Punct("!=")) => BinaryOp::Ne,
+                Some(Token::Punct("<")) => BinaryOp::Lt,
+                Some(Token::Punct("<=")) => BinaryOp::Le,
+                Some(Token::Punct(">")) => BinaryOp::Gt,
+                Some(Token::Punct(">=")) => BinaryOp::Ge,
+                Some(Token::Ident(ident)) if ident == "in" => BinaryOp::In,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.addition()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn addition(&mut self) -> Result<Expr> {
+        let mut lhs = self.multiplication()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("+")) => BinaryOp::Add,
+                Some(Token::Punct("-")) => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.multiplication()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn multiplication(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("*")) => BinaryOp::Mul,
+                Some(Token::Punct("/")) => BinaryOp::Div,
+                Some(Token::Punct("%")) => BinaryOp::Rem,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("!") {
+            let operand = self.unary()?;
+            return Ok(Expr::Unary(UnaryOp::Not, Box::new(operand)));
+        }
+        if self.eat("-") {
+            // the negative integer literals are folded so that the minimum value can be written
+            return match self.peek() {
+                Some(Token::Int(v)) if *v == i64::MIN.unsigned_abs() => {
+                    self.pos += 1;
+                    self.member(Expr::Int(i64::MIN))
+                }
+                _ => {
+                    let operand = self.unary()?;
+                    Ok(Expr::Unary(UnaryOp::Neg, Box::new(operand)))
+                }
+            };
+        }
+        let primary = self.primary()?;
+        self.member(primary)
+    }
+
+    fn member(&mut self, mut operand: Expr) -> Result<Expr> {
+        loop {
+            if self.eat(".") {
+                let name = match self.next()? {
+                    Token::Ident(name) => name,
+                    token => bail!("expected a field or function name, found `{token}`"),
+                };
+                if self.eat("(") {
+                    let args = self.list(")")?;
+                    operand = Expr::Call(Some(Box::new(operand)), name, args);
+                } else {
+                    operand = Expr::Select(Box::new(operand), name);
+                }
+            } else if self.eat("[") {
+                let index = self.expr()?;
+                self.expect("]")?;
+                operand = Expr::Index(Box::new(operand), Box::new(index));
+            } else if self.peek() == Some(&Token::Punct("{")) {
+                bail!("message construction expressions are not supported");
+            } else {
+                return Ok(operand);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        Ok(match self.next()? {
+            Token::Int(v) => Expr::Int(
+                i64::try_from(v).map_err(|_| anyhow!("integer literal `{v}` out of range"))?,
+            ),
+            Token::Uint(v) => Expr::Uint(v),
+            Token::Double(v) => Expr::Double(v),
+            Token::String(v) => Expr::String(v),
+            Token::Bytes(v) => Expr::Bytes(v),
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                "null" => Expr::Null,
+                _ if self.eat("(") => Expr::Call(None, ident, self.list(")")?),
+                _ => Expr::Ident(ident),
+            },
+            Token::Punct(".") => match self.next()? {
+                Token::Ident(ident) => Expr::Ident(ident),
+                token => bail!("expected an identifier, found `{token}`"),
+            },
+            Token::Punct("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                expr
+            }
+            Token::Punct("[") => Expr::List(self.list("]")?),
+            Token::Punct("{") => {
+                let mut entries = Vec::new();
+                while !self.eat("}") {
+                    let key = self.expr()?;
+                    self.expect(":")?;
+                    let value = self.expr()?;
+                    entries.push((key, value));
+                    if !self.eat(",") {
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                Expr::Map(entries)
+            }
+            token => bail!("unexpected `{token}`"),
+        })
+    }
+
+    /// Parses a comma separated list of expressions up to the `end` punctuation.
+    fn list(&mut self, end: &str) -> Result<Vec<Expr>> {
+        let mut items = Vec::new();
+        while !self.eat(end) {
+            items.push(self.expr()?);
+            if !self.eat(",") {
+                self.expect(end)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Ident(name.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("this.a < this.b && !has(this.c)").unwrap(),
+            Expr::Binary(
+                BinaryOp::And,
+                Box::new(Expr::Binary(
+                    BinaryOp::Lt,
+                    Box::new(Expr::Select(ident("this"), "a".into())),
+                    Box::new(Expr::Select(ident("this"), "b".into())),
+                )),
+                Box::new(Expr::Unary(
+                    UnaryOp::Not,
+                    Box::new(Expr::Call(
+                        None,
+                        "has".into(),
+                        vec![Expr::Select(ident("this"), "c".into())]
+                    ))
+                )),
+            )
+        );
+        assert_eq!(
+            parse("1 + 2 * 3 == 7 ? 'a' : \"b\"").unwrap(),
+            Expr::Ternary(
+                Box::new(Expr::Binary(
+                    BinaryOp::Eq,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Add,
+                        Box::new(Expr::Int(1)),
+                        Box::new(Expr::Binary(
+                            BinaryOp::Mul,
+                            Box::new(Expr::Int(2)),
+                            Box::new(Expr::Int(3)),
+                        )),
+                    )),
+                    Box::new(Expr::Int(7)),
+                )),
+                Box::new(Expr::String("a".into())),
+                Box::new(Expr::String("b".into())),
+            )
+        );
+        assert_eq!(
+            parse("this.all(x, x in [1u, 0x2u])").unwrap(),
+            Expr::Call(
+                Some(ident("this")),
+                "all".into(),
+                vec![
+                    Expr::Ident("x".into()),
+                    Expr::Binary(
+                        BinaryOp::In,
+                        ident("x"),
+                        Box::new(Expr::List(vec![Expr::Uint(1), Expr::Uint(2)])),
+                    ),
+                ],
+            )
+        );
+        assert_eq!(
+            parse(r#"b'\x00a' == this[-9223372036854775808] "#).unwrap(),
+            Expr::Binary(
+                BinaryOp::Eq,
+                Box::new(Expr::Bytes(vec![0, b'a'])),
+                Box::new(Expr::Index(ident("this"), Box::new(Expr::Int(i64::MIN)))),
+            )
+        );
+        assert_eq!(
+            parse(r"'é\n' + r'\n'").unwrap(),
+            Expr::Binary(
+                BinaryOp::Add,
+                Box::new(Expr::String("é\n".into())),
+                Box::new(Expr::String("\\n".into())),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("this.").is_err());
+        assert!(parse("(1 + 2").is_err());
+        assert!(parse("1 2").is_err());
+        assert!(parse("'abc").is_err());
+        assert!(parse("a.B{c: 1}").is_err());
+        assert!(parse("9223372036854775808").is_err());
+        assert!(parse("#").is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::cel::CelRule;
 use crate::field::Field;
+use crate::to_snake;
 use darling::ast::Data;
 use darling::FromDeriveInput;
 use proc_macro2::TokenStream;
@@ -9,6 +11,13 @@ use syn::DeriveInput;
 #[darling(attributes(validate, prost), supports(struct_named, enum_any))]
 struct Opts {
     data: Data<Field, Field>,
+    #[darling(default)]
+    name: String,
+    /// Whether the type is not validated, e.g. excluded by the build configuration.
+    #[darling(default)]
+    skip: bool,
+    #[darling(multiple)]
+    cel: Vec<CelRule>,
 }
 
 pub fn derive(input: TokenStream) -> proc_macro2::TokenStream {
@@ -22,46 +31,126 @@ pub fn derive_with_module(
     let input = syn::parse2(input).unwrap();
     let opts = Opts::from_derive_input(&input).expect("Wrong validate options");
     let DeriveInput { ident, .. } = input;
+    let path = if let Some(module) = &module {
+        quote! { #module::#ident }
+    } else {
+        quote! { #ident }
+    };
+    if opts.skip {
+        return quote! {
+            impl ::prost_validate::Validator for #path {}
+        };
+    }
 
-    let implementation = match opts.data {
-        Data::Enum(e) => e
-            .iter()
-            .map(|v| Field {
-                module: module.clone().map(|v| v.to_string()),
-                ..v.clone()
-            })
-            .map(|v| v.to_token_stream())
-            .collect::<proc_macro2::TokenStream>(),
-        Data::Struct(s) => s
-            .fields
-            .iter()
-            .map(|v| Field {
+    let fields = match opts.data {
+        Data::Enum(e) => e,
+        Data::Struct(s) => s.fields,
+    };
+    let implementation = |all: bool| {
+        // the CEL rules are evaluated after the standard ones
+        let fields = [false, true]
+            .into_iter()
+            .flat_map(|cel| fields.iter().map(move |v| (cel, v)))
+            .map(|(cel, v)| Field {
                 module: module.clone().map(|v| v.to_string()),
+                all,
+                cel,
                 ..v.clone()
             })
             .map(|field| field.into_token_stream())
-            .collect::<proc_macro2::TokenStream>(),
+            .collect::<proc_macro2::TokenStream>();
+        let fail = if all {
+            quote! { errs.push }
+        } else {
+            quote! { return Err }
+        };
+        let rules = opts
+            .cel
+            .iter()
+            .map(|rule| rule.to_validation_tokens(&opts.name, &fail, true))
+            .collect::<proc_macro2::TokenStream>();
+        if rules.is_empty() {
+            return fields;
+        }
+        quote! {
+            #fields
+            let this = self;
+            #rules
+        }
     };
+    let validate_all = implementation(true);
+    let implementation = implementation(false);
+
+    // the rules of a single field, addressed by its name or, in a oneof, by the name of the variant
+    let field_arms = fields
+        .iter()
+        .filter_map(|v| {
+            let ident = v.ident.as_ref()?;
+            let key = if v.oneof {
+                to_snake(ident.to_string())
+            } else {
+                ident.to_string()
+            };
+            let key = key.strip_prefix("r#").unwrap_or(&key).to_string();
+            let body = [false, true]
+                .into_iter()
+                .map(|cel| Field {
+                    module: module.clone().map(|v| v.to_string()),
+                    all: false,
+                    cel,
+                    ..v.clone()
+                })
+                .map(|field| field.into_token_stream())
+                .collect::<proc_macro2::TokenStream>();
+            Some(quote! { #key => { #body } })
+        })
+        .collect::<proc_macro2::TokenStream>();
 
     let allow = quote! {
         #[allow(clippy::regex_creation_in_loops)]
         #[allow(irrefutable_let_patterns)]
         #[allow(unused_variables)]
     };
-
-    let path = if let Some(module) = module {
-        quote! { #module::#ident }
-    } else {
-        quote! { #ident }
+    let allow_all = quote! {
+        #allow
+        #[allow(unused_mut)]
     };
+
     if !implementation.is_empty() {
         quote! {
             impl ::prost_validate::Validator for #path {
-                #allow
                 fn validate(&self) -> ::prost_validate::Result<()> {
+                    ::prost_validate::Validator::validate_with(self, &::prost_validate::Context::default())
+                }
+                #allow
+                fn validate_with(&self, ctx: &::prost_validate::Context) -> ::prost_validate::Result<()> {
                     #implementation
                     Ok(())
                 }
+                fn validate_all(&self) -> ::prost_validate::Result<(), ::prost_validate::Violations> {
+                    ::prost_validate::Validator::validate_all_with(self, &::prost_validate::Context::default())
+                }
+                #allow_all
+                fn validate_all_with(&self, ctx: &::prost_validate::Context) -> ::prost_validate::Result<(), ::prost_validate::Violations> {
+                    let mut errs: Vec<::prost_validate::Error> = Vec::new();
+                    #validate_all
+                    if errs.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(errs.into())
+                    }
+                }
+                fn validate_field(&self, field: &str) -> ::prost_validate::Result<()> {
+                    ::prost_validate::Validator::validate_field_with(self, field, &::prost_validate::Context::default())
+                }
+                #allow
+                fn validate_field_with(&self, field: &str, ctx: &::prost_validate::Context) -> ::prost_validate::Result<()> {
+                    match field {
+                        #field_arms
+                        _ => return Err(::prost_validate::format_err!(field, "unknown field")),
+                    }
+                    Ok(())
+                }
             }
         }
     } else {
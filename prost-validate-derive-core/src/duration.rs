@@ -33,12 +33,13 @@ pub fn duration_to_tokens(name: &Ident, want: &Duration) -> (TokenStream, TokenS
 impl ToValidationTokens for DurationRules {
     fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
         let field = &ctx.name;
+        let fail = ctx.fail();
         let rules = prost_validate_types::DurationRules::from(self.clone());
         let r#const = rules.r#const.map(|v| v.as_duration()).map(|v| {
             let (got, want) = duration_to_tokens(name, &v);
             quote! {
                 if #got != #want {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Const(#want)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Const(#want)));
                 }
             }
         });
@@ -50,7 +51,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gt) = duration_to_tokens(name, &gt);
                     quote! {
                         if #val <= #gt || #val >= #lt {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(false, #gt, #lt, false)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(false, #gt, #lt, false)));
                         }
                     }
                 } else {
@@ -58,7 +59,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gt) = duration_to_tokens(name, &gt);
                     quote! {
                         if #val >= #lt && #val <= #gt {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(true, #lt, #gt, true)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(true, #lt, #gt, true)));
                         }
                     }
                 }
@@ -68,7 +69,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gte) = duration_to_tokens(name, &gte);
                     quote! {
                         if #val < #gte || #val >= #lt {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(true, #gte, #lt, false)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(true, #gte, #lt, false)));
                         }
                     }
                 } else {
@@ -76,7 +77,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gte) = duration_to_tokens(name, &gte);
                     quote! {
                         if #val >= #lt && #val < #gte {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(true, #lt, #gte, false)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(true, #lt, #gte, false)));
                         }
                     }
                 }
@@ -84,7 +85,7 @@ impl ToValidationTokens for DurationRules {
                 let (val, lt) = duration_to_tokens(name, &lt);
                 quote! {
                     if #val >= #lt {
-                        return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Lt(#lt)));
+                        #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Lt(#lt)));
                     }
                 }
             }
@@ -95,7 +96,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gt) = duration_to_tokens(name, &gt);
                     quote! {
                         if #val <= #gt || #val > #lte {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(false, #gt, #lte, true)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(false, #gt, #lte, true)));
                         }
                     }
                 } else {
@@ -103,7 +104,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gt) = duration_to_tokens(name, &gt);
                     quote! {
                         if #val >= #lte && #val < #gt {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(false, #lte, #gt, true)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(false, #lte, #gt, true)));
                         }
                     }
                 }
@@ -113,7 +114,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gte) = duration_to_tokens(name, &gte);
                     quote! {
                         if #val < #gte || #val > #lte {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(true, #gte, #lte, true)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::in_range(true, #gte, #lte, true)));
                         }
                     }
                 } else {
@@ -121,7 +122,7 @@ impl ToValidationTokens for DurationRules {
                     let (_, gte) = duration_to_tokens(name, &gte);
                     quote! {
                         if #val > #lte && #val < #gte {
-                            return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(false, #lte, #gte, false)));
+                            #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::not_in_range(false, #lte, #gte, false)));
                         }
                     }
                 }
@@ -129,7 +130,7 @@ impl ToValidationTokens for DurationRules {
                 let (val, lte) = duration_to_tokens(name, &lte);
                 quote! {
                     if #val > #lte {
-                        return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Lte(#lte)));
+                        #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Lte(#lte)));
                     }
                 }
             }
@@ -137,14 +138,14 @@ impl ToValidationTokens for DurationRules {
             let (val, gt) = duration_to_tokens(name, &gt);
             quote! {
                 if #val <= #gt {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Gt(#gt)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Gt(#gt)));
                 }
             }
         } else if let Some(gte) = rules.gte.map(|v| v.as_duration()) {
             let (val, gte) = duration_to_tokens(name, &gte);
             quote! {
                 if #val < #gte {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Gte(#gte)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::Gte(#gte)));
                 }
             }
         } else {
@@ -165,7 +166,7 @@ impl ToValidationTokens for DurationRules {
             quote! {
                 let values = [#(#vals),*];
                 if !values.contains(&#val) {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::In(values.to_vec())));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::In(values.to_vec())));
                 }
             }
         });
@@ -184,7 +185,7 @@ impl ToValidationTokens for DurationRules {
             quote! {
                 let values = [#(#vals),*];
                 if values.contains(&#val) {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::NotIn(values.to_vec())));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::duration::Error::NotIn(values.to_vec())));
                 }
             }
         });
@@ -18,11 +18,12 @@ pub struct EnumRules {
 impl ToValidationTokens for EnumRules {
     fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
         let field = &ctx.name;
+        let fail = ctx.fail();
         let rules = prost_validate_types::EnumRules::from(self.to_owned());
         let r#const = rules.r#const.map(|v| {
             quote! {
                 if (*#name as i32) != #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::Const(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::Const(#v)));
                 }
             }
         });
@@ -47,7 +48,7 @@ impl ToValidationTokens for EnumRules {
                 .expect("Invalid enum path");
             quote! {
                 if !#enum_type::is_valid(*#name) {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::DefinedOnly));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::DefinedOnly));
                 }
             }
         });
@@ -56,7 +57,7 @@ impl ToValidationTokens for EnumRules {
             quote! {
                 let values = [#(#v),*];
                 if !values.contains(&#name) {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::In(values.to_vec())));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::In(values.to_vec())));
                 }
             }
         });
@@ -65,7 +66,7 @@ impl ToValidationTokens for EnumRules {
             quote! {
                 let values = [#(#v),*];
                 if values.contains(#name) {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::NotIn(values.to_vec())));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::r#enum::Error::NotIn(values.to_vec())));
                 }
             }
         });
@@ -1,3 +1,4 @@
+use crate::cel::CelRule;
 use crate::map::MapRules;
 use crate::message::MessageRules;
 use crate::oneof::OneOfRules;
@@ -29,6 +30,64 @@ pub struct Context<'a> {
     pub prost_types: bool,
     pub wrapper: bool,
     pub module: Option<String>,
+    pub all: bool,
+}
+
+impl Context<'_> {
+    /// Returns the tokens reporting a validation error: the error is returned
+    /// or, when collecting all the violations, pushed to the `errs` vector.
+    pub fn fail(&self) -> TokenStream {
+        if self.all {
+            quote! { errs.push }
+        } else {
+            quote! { return Err }
+        }
+    }
+
+    /// Returns the tokens running the `validation` of a nested value (list item, map key or value),
+    /// its errors being converted using `map`.
+    pub fn nested(&self, validation: &TokenStream, map: &TokenStream) -> TokenStream {
+        if self.all {
+            quote! {
+                let nested = {
+                    let mut errs: Vec<::prost_validate::Error> = Vec::new();
+                    #validation
+                    errs
+                };
+                errs.extend(nested.into_iter().map(#map));
+            }
+        } else {
+            quote! {
+                || -> ::prost_validate::Result<_> {
+                    #validation
+                    Ok(())
+                }().map_err(#map)?;
+            }
+        }
+    }
+
+    /// Returns the tokens validating the embedded message `name`,
+    /// its errors being converted using `map` if any.
+    pub fn validate_message(&self, name: &Ident, map: Option<&TokenStream>) -> TokenStream {
+        match (self.all, map) {
+            (true, Some(map)) => quote! {
+                if let Err(nested) = ::prost_validate::validate_all!(#name, ctx) {
+                    errs.extend(nested.into_iter().map(#map));
+                }
+            },
+            (true, None) => quote! {
+                if let Err(nested) = ::prost_validate::validate_all!(#name, ctx) {
+                    errs.extend(nested);
+                }
+            },
+            (false, Some(map)) => quote! {
+                ::prost_validate::validate!(#name, ctx).map_err(#map)?;
+            },
+            (false, None) => quote! {
+                ::prost_validate::validate!(#name, ctx)?;
+            },
+        }
+    }
 }
 
 pub trait ToValidationTokens {
@@ -44,6 +103,9 @@ pub struct Field {
     pub oneof: bool,
     pub map: bool,
     pub module: Option<String>,
+    pub all: bool,
+    /// Whether only the CEL rules of the field are generated.
+    pub cel: bool,
 }
 
 impl Field {
@@ -76,6 +138,8 @@ impl Field {
             oneof,
             map: map.is_some(),
             module: None,
+            all: false,
+            cel: false,
         }
     }
     pub fn is_wkt(&self) -> bool {
@@ -123,8 +187,8 @@ impl Field {
                 name
             )));
         }
-        if self.prost.repeated && self.validation.r#type.is_some() {
-            if let FieldRules::Repeated(rules) = self.validation.r#type.as_ref().unwrap() {
+        if self.prost.repeated {
+            if let Some(FieldRules::Repeated(rules)) = self.validation.r#type.as_ref() {
                 if let Some(ref rules) = rules.items {
                     return Field {
                         prost: ProstField {
@@ -155,6 +219,7 @@ impl Field {
         }
         if self.prost.message.is_some() {
             let typ = self.ty.to_token_stream().to_string().replace(' ', "");
+            #[allow(clippy::collapsible_match)]
             match typ.as_str() {
                 "::core::option::Option<::prost_types::Timestamp>" => {
                     if self.validation.r#type.is_some()
@@ -546,6 +611,9 @@ impl ToTokens for Field {
         if let Err(err) = self.validate() {
             panic!("{}", err);
         }
+        if self.cel && self.validation.cel.is_empty() {
+            return;
+        }
         if let Some(ident) = &self.ident {
             let ctx = Context {
                 name: &self.validation.name,
@@ -563,6 +631,7 @@ impl ToTokens for Field {
                 oneof: self.oneof,
                 prost_types: self.is_prost_types(),
                 module: self.module.clone(),
+                all: self.all,
             };
             let name = if self.oneof {
                 let name = to_snake(ident.to_string());
@@ -575,12 +644,32 @@ impl ToTokens for Field {
                 ident.to_owned()
             };
             let name = &name;
-            let body = self.validation.to_validation_tokens(&ctx, name);
-            let required = (ctx.required && !ctx.optional_keyword).then(|| {
+            let body = if self.cel {
+                let fail = ctx.fail();
+                let rules = self
+                    .validation
+                    .cel
+                    .iter()
+                    .map(|rule| rule.to_validation_tokens(ctx.name, &fail, false));
+                quote! {
+                    let this = #name;
+                    #(#rules)*
+                }
+            } else {
+                self.validation.to_validation_tokens(&ctx, name)
+            };
+            let required = (ctx.required && !ctx.optional_keyword && !self.cel).then(|| {
                 let field = &ctx.name;
+                let fail = ctx.fail();
+                let err = match self.validation.r#type {
+                    Some(FieldRules::Any(_)) => {
+                        quote! { ::prost_validate::errors::any::Error::Required }
+                    }
+                    _ => quote! { ::prost_validate::errors::message::Error::Required },
+                };
                 quote! {
                     if self.#name.is_none() {
-                        return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::message::Error::Required));
+                        #fail(::prost_validate::Error::new(#field, #err));
                     }
                 }
             });
@@ -642,6 +731,8 @@ pub struct FieldValidation {
     pub repeated: Option<bool>,
     pub message: Option<MessageRules>,
     pub r#type: Option<FieldRules>,
+    #[darling(multiple)]
+    pub cel: Vec<CelRule>,
 }
 
 impl FieldValidation {
@@ -781,10 +872,9 @@ pub fn with_ignore_empty(name: &syn::Ident, ignore_empty: bool, body: TokenStrea
     }
     if ignore_empty {
         quote! {
-            if #name.is_empty() {
-                return Ok(());
+            if !#name.is_empty() {
+                #body
             }
-            #body
         }
     } else {
         quote! {
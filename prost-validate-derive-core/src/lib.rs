@@ -1,6 +1,7 @@
 mod any;
 mod bool;
 mod bytes;
+pub mod cel;
 pub mod derive;
 mod duration;
 mod r#enum;
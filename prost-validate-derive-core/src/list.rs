@@ -17,11 +17,12 @@ pub struct RepeatedRules {
 impl ToValidationTokens for RepeatedRules {
     fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
         let field = &ctx.name;
+        let fail = ctx.fail();
         let min_items = self.min_items.map(|v| {
             let v = v as usize;
             quote! {
                 if #name.len() < #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::MinItems(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::MinItems(#v)));
                 }
             }
         });
@@ -29,26 +30,24 @@ impl ToValidationTokens for RepeatedRules {
             let v = v as usize;
             quote! {
                 if #name.len() > #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::MaxItems(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::MaxItems(#v)));
                 }
             }
         });
         let unique = self.unique.is_true_and(|| {
             quote! {
                 if ::prost_validate::VecExt::unique(#name).len() != #name.len() {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::Unique));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::list::Error::Unique));
                 }
             }
         });
-        let map = quote! { |e| ::prost_validate::Error::new(format!("{}[{i}]", #field), ::prost_validate::errors::list::Error::Item(Box::new(e))) };
+        let map = quote! { |e| ::prost_validate::Error::list_item(#field, i, e) };
         let items = self.items.as_ref().map(|v| {
             let validation = v.to_validation_tokens(ctx, &format_ident!("item"));
+            let validation = ctx.nested(&validation, &map);
             quote! {
                 for (i, item) in #name.iter().enumerate() {
-                    || -> ::prost_validate::Result<_> {
-                        #validation
-                        Ok(())
-                    }().map_err(#map)?;
+                    #validation
                 }
             }
         });
@@ -65,10 +64,12 @@ impl ToValidationTokens for RepeatedRules {
             } else {
                 (quote! { #name.iter() }, quote! {})
             };
+            let map = quote! { |e| ::prost_validate::Error::list_item(#field, i, ::prost_validate::Error::new(#field, ::prost_validate::errors::message::Error::Message(Box::new(e)))) };
+            let validation = ctx.validate_message(&format_ident!("item"), Some(&map));
             quote! {
                 for (i, item) in #name_iter.enumerate() {
                     #item_ref
-                    ::prost_validate::validate!(item).map_err(#map)?;
+                    #validation
                 }
             }
         });
@@ -19,12 +19,13 @@ pub struct MapRules {
 impl ToValidationTokens for MapRules {
     fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
         let field = &ctx.name;
+        let fail = ctx.fail();
         let rules = prost_validate_types::MapRules::from(self.to_owned());
         let min_pairs = rules.min_pairs.map(|v| {
             let v = v as usize;
             quote! {
                 if #name.len() < #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::map::Error::MinPairs(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::map::Error::MinPairs(#v)));
                 }
             }
         });
@@ -32,7 +33,7 @@ impl ToValidationTokens for MapRules {
             let v = v as usize;
             quote! {
                 if #name.len() > #v {
-                    return Err(::prost_validate::Error::new(#field, ::prost_validate::errors::map::Error::MaxPairs(#v)));
+                    #fail(::prost_validate::Error::new(#field, ::prost_validate::errors::map::Error::MaxPairs(#v)));
                 }
             }
         });
@@ -40,25 +41,22 @@ impl ToValidationTokens for MapRules {
         let keys = self.keys.as_ref().map(|rules| {
             let validate = rules.to_validation_tokens(ctx, &key);
             validate.is_empty().not().then(|| {
+                let map = quote! { |e| ::prost_validate::Error::map_key(#field, #key.clone(), e) };
+                let validate = ctx.nested(&validate, &map);
                 quote! {
                     for #key in #name.keys() {
-                        || -> ::prost_validate::Result<_> {
-                            #validate
-                            Ok(())
-                        }().map_err(|e| ::prost_validate::Error::new(format!("{}[{}]", #field, #key), ::prost_validate::errors::map::Error::Keys(Box::new(e))))?;
+                        #validate
                     }
                 }
             })
         });
         let value = format_ident!("value");
-        let map = quote! { |e| ::prost_validate::Error::new(format!("{}[{k}]", #field), ::prost_validate::errors::map::Error::Values(Box::new(e))) };
+        let map = quote! { |e| ::prost_validate::Error::map_value(#field, k.clone(), e) };
         let quote_values = |validation: TokenStream| {
+            let validation = ctx.nested(&validation, &map);
             quote! {
                 for (k, #value) in #name.iter() {
-                    || -> ::prost_validate::Result<_> {
-                        #validation
-                        Ok(())
-                    }().map_err(#map)?;
+                    #validation
                 }
             }
         };
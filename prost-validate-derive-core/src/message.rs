@@ -21,9 +21,10 @@ impl ToValidationTokens for MessageRules {
         let validate = self.skip.not().then(|| {
             let map = quote! { |e| ::prost_validate::Error::new(#field, ::prost_validate::errors::message::Error::Message(Box::new(e))) };
             let name_ref = ctx.boxed.then(|| quote! { let #name = #name.as_ref(); });
+            let validate = ctx.validate_message(name, Some(&map));
             quote! {
                 #name_ref
-                ::prost_validate::validate!(#name).map_err(#map)?;
+                #validate
             }
         });
         validate.unwrap_or_default()
@@ -1,7 +1,6 @@
 use crate::field::{Context, ToValidationTokens};
 use darling::FromMeta;
 use proc_macro2::{Ident, TokenStream};
-use quote::quote;
 
 #[derive(Debug, FromMeta, Clone, Default)]
 pub struct OneOfRules {
@@ -10,9 +9,7 @@ pub struct OneOfRules {
 }
 
 impl ToValidationTokens for OneOfRules {
-    fn to_validation_tokens(&self, _: &Context, name: &Ident) -> TokenStream {
-        quote! {
-            ::prost_validate::validate!(#name)?;
-        }
+    fn to_validation_tokens(&self, ctx: &Context, name: &Ident) -> TokenStream {
+        ctx.validate_message(name, None)
     }
 }
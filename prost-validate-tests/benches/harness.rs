@@ -12,6 +12,24 @@ fn reflect_validate() {
     }
 }
 
+/// Validates the messages of the test cases, already converted to dynamic messages,
+/// so that only the validation through the registry is measured.
+#[cfg(feature = "reflect")]
+fn reflect_validate_dynamic(
+    registry: &prost_reflect_validate::Registry,
+    messages: &[(&str, prost_reflect::DynamicMessage, i32)],
+) {
+    for (name, message, failures) in messages {
+        match registry.validate_dynamic(message) {
+            Ok(_) => assert_eq!(*failures, 0, "{name}: unexpected validation success"),
+            Err(err) => assert!(
+                *failures > 0,
+                "{name}: unexpected validation failure: {err}"
+            ),
+        }
+    }
+}
+
 #[cfg(feature = "derive")]
 fn derive_validate() {
     for (name, f) in CASES.iter() {
@@ -26,6 +44,29 @@ fn derive_validate() {
 fn criterion_benchmark(c: &mut Criterion) {
     #[cfg(feature = "reflect")]
     c.bench_function("harness reflect", |b| b.iter(reflect_validate));
+    #[cfg(feature = "reflect")]
+    {
+        let registry = prost_reflect_validate::Registry::default();
+        let messages = CASES
+            .iter()
+            .map(|(name, f)| {
+                let (message, failures) = f();
+                (*name, message.to_dynamic(), failures)
+            })
+            .collect::<Vec<_>>();
+        c.bench_function("harness reflect dynamic", |b| {
+            b.iter(|| reflect_validate_dynamic(&registry, &messages))
+        });
+        c.bench_function("harness reflect dynamic 8 threads", |b| {
+            b.iter(|| {
+                std::thread::scope(|s| {
+                    for _ in 0..8 {
+                        s.spawn(|| reflect_validate_dynamic(&registry, &messages));
+                    }
+                })
+            })
+        });
+    }
     #[cfg(feature = "derive")]
     c.bench_function("harness derive", |b| b.iter(derive_validate));
 }
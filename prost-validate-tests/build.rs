@@ -69,7 +69,12 @@ fn gen(name: &str, files: &[String], includes: &[&str]) -> Result<()> {
             .out_dir(&pbjson_path);
         c
     };
-    prost_validate_build::Builder::new().configure(&mut pbjson_config, files, includes)?;
+    // the pbjson harness runs the implementations generated as plain Rust
+    let mut pbjson_builder = prost_validate_build::Builder::new();
+    pbjson_builder
+        .impl_files(true)
+        .out_dir(&pbjson_path)
+        .configure(&mut pbjson_config, files, includes)?;
     prost_reflect_build::Builder::new()
         .file_descriptor_set_bytes(format!(
             "crate::_{}_FILE_DESCRIPTOR_SET_BYTES",
@@ -82,6 +87,7 @@ fn gen(name: &str, files: &[String], includes: &[&str]) -> Result<()> {
                 .join(format!("{}_file_descriptor_set.bin", name)),
         )
         .compile_protos_with_config(pbjson_config, files, includes)?;
+    pbjson_builder.write_impl_files()?;
 
     // prost_types build
     let mut config = prost_build::Config::new();
@@ -21,6 +21,733 @@ fn regress_detail_contains_array_index() {
     );
 }
 
+#[test]
+fn regress_error_path() {
+    fn paths(
+        msg: &(impl ::prost_validate::Validator + ::prost_reflect::ReflectMessage),
+    ) -> Vec<String> {
+        #[allow(clippy::unwrap_used)]
+        let derive = ::prost_validate::Validator::validate_all(msg).unwrap_err();
+        #[allow(clippy::unwrap_used)]
+        let reflect = ::prost_reflect_validate::ValidatorExt::validate_all(msg).unwrap_err();
+        let derive = derive
+            .iter()
+            .map(|e| e.path.to_string())
+            .collect::<Vec<_>>();
+        let reflect = reflect
+            .iter()
+            .map(|e| e.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(derive, reflect);
+        derive
+    }
+    assert_eq!(
+        paths(&RepeatedItemMaxLen {
+            val: vec!["one".to_owned(), "Two".to_owned(), "THREE".to_owned()],
+        }),
+        vec!["val[2]"]
+    );
+    assert_eq!(
+        paths(&RepeatedEmbedNone {
+            val: vec![Embed { val: 1 }, Embed { val: 0 }],
+        }),
+        vec!["val[1].val"]
+    );
+    assert_eq!(
+        paths(&MapKeys {
+            val: HashMap::from([(1, "a".to_owned())]),
+        }),
+        vec!["val[1]"]
+    );
+    assert_eq!(
+        paths(&MapValues {
+            val: HashMap::from([("a".to_owned(), "b".to_owned())]),
+        }),
+        vec![r#"val["a"]"#]
+    );
+    assert_eq!(
+        paths(&MapRecursive {
+            val: HashMap::from([(1, map_recursive::Msg::default())]),
+        }),
+        vec!["val[1].val"]
+    );
+    assert_eq!(
+        paths(&Message {
+            val: Some(TestMsg {
+                r#const: "bar".to_owned(),
+                nested: None,
+            }),
+        }),
+        vec!["val.const"]
+    );
+}
+
+#[test]
+fn regress_context_clock() {
+    use ::prost_reflect_validate::ValidatorExt;
+    use ::prost_validate::Context;
+
+    fn check(
+        msg: &(impl ::prost_validate::Validator + ::prost_reflect::ReflectMessage),
+        now: i64,
+    ) -> bool {
+        let ctx = Context::new().with_now(::prost_validate::utils::datetime(now, 0));
+        let derive = ::prost_validate::Validator::validate_with(msg, &ctx).is_ok();
+        let reflect = ValidatorExt::validate_with(msg, &ctx).is_ok();
+        assert_eq!(derive, reflect);
+        assert_eq!(
+            ::prost_validate::Validator::validate_all_with(msg, &ctx).is_ok(),
+            derive
+        );
+        assert_eq!(ValidatorExt::validate_all_with(msg, &ctx).is_ok(), derive);
+        derive
+    }
+    let val = Some(Timestamp {
+        seconds: 10_000,
+        nanos: 0,
+    });
+    assert!(check(&TimestampLtNow { val }, 20_000));
+    assert!(!check(&TimestampLtNow { val }, 5_000));
+    assert!(check(&TimestampGtNow { val }, 5_000));
+    assert!(!check(&TimestampGtNow { val }, 20_000));
+    assert!(check(&TimestampWithin { val }, 12_000));
+    assert!(!check(&TimestampWithin { val }, 20_000));
+    assert!(check(&TimestampLtNowWithin { val }, 12_000));
+    assert!(!check(&TimestampLtNowWithin { val }, 20_000));
+    assert!(check(&TimestampGtNowWithin { val }, 8_000));
+    assert!(!check(&TimestampGtNowWithin { val }, 5_000));
+}
+
+#[test]
+fn regress_cel() {
+    use ::prost_reflect_validate::ValidatorExt;
+    use ::prost_validate::{Context, Violations};
+
+    fn violations(
+        msg: &(impl ::prost_validate::Validator + ::prost_reflect::ReflectMessage),
+    ) -> Vec<(String, String, String)> {
+        fn collect(errs: &Violations) -> Vec<(String, String, String)> {
+            errs.iter()
+                .map(|e| {
+                    (
+                        e.path.to_string(),
+                        e.rule_id().to_string(),
+                        e.details.to_string(),
+                    )
+                })
+                .collect()
+        }
+        let ctx = Context::new().with_now(::prost_validate::utils::datetime(10_000, 0));
+        let derive = match ::prost_validate::Validator::validate_all_with(msg, &ctx) {
+            Ok(()) => vec![],
+            Err(errs) => collect(&errs),
+        };
+        let reflect = match ValidatorExt::validate_all_with(msg, &ctx) {
+            Ok(()) => vec![],
+            Err(errs) => collect(&errs),
+        };
+        assert_eq!(derive, reflect);
+        assert_eq!(
+            ::prost_validate::Validator::validate_with(msg, &ctx)
+                .err()
+                .map(|e| e.to_string()),
+            ValidatorExt::validate_with(msg, &ctx)
+                .err()
+                .map(|e| e.to_string())
+        );
+        derive
+    }
+    fn violation(path: &str, id: &str, message: &str) -> Vec<(String, String, String)> {
+        vec![(path.to_owned(), id.to_owned(), message.to_owned())]
+    }
+
+    let val = "foo".to_owned();
+    assert!(violations(&CelFieldString { val }).is_empty());
+    assert_eq!(
+        violations(&CelFieldString { val: "fo".into() }),
+        violation(
+            "val",
+            "string.three_chars",
+            "value must be at least 3 characters long"
+        )
+    );
+    assert!(violations(&CelFieldResult { val: 2 }).is_empty());
+    assert_eq!(
+        violations(&CelFieldResult { val: 3 }),
+        violation("val", "int32.even", "value must be even")
+    );
+    assert_eq!(
+        violations(&CelFieldDefaultMessage { val: 10 }),
+        violation("val", "uint64.small", "`this < 10u` returned false")
+    );
+    assert!(violations(&CelFieldMultiple {
+        val: "foobar".into()
+    })
+    .is_empty());
+    assert_eq!(
+        violations(&CelFieldMultiple { val: "baz".into() })
+            .into_iter()
+            .map(|(_, id, _)| id)
+            .collect::<Vec<_>>(),
+        vec!["string.prefix", "string.suffix"]
+    );
+    assert!(violations(&CelFieldIgnoreEmpty { val: "".into() }).is_empty());
+    assert!(violations(&CelFieldIgnoreEmpty {
+        val: "foo@example.com".into()
+    })
+    .is_empty());
+    assert_eq!(
+        violations(&CelFieldIgnoreEmpty { val: "foo".into() }),
+        violation("val", "string.email", "value must be an email address")
+    );
+    assert_eq!(
+        violations(&CelFieldMixed {
+            val: "FooBar".into()
+        })
+        .into_iter()
+        .map(|(_, id, _)| id)
+        .collect::<Vec<_>>(),
+        vec!["string.max_len", "string.lowercase"]
+    );
+    assert!(violations(&CelFieldRepeated {
+        val: vec!["a".into(), "b".into()]
+    })
+    .is_empty());
+    assert_eq!(
+        violations(&CelFieldRepeated {
+            val: vec!["a".into(), "a".into()]
+        }),
+        violation("val", "repeated.unique", "items must be unique")
+    );
+    assert!(violations(&CelFieldMap {
+        val: HashMap::from([("a".into(), 1)])
+    })
+    .is_empty());
+    assert_eq!(
+        violations(&CelFieldMap {
+            val: HashMap::from([("a".into(), 0)])
+        }),
+        violation("val", "map.positive", "values must be positive")
+    );
+    let ts = |seconds| Some(Timestamp { seconds, nanos: 0 });
+    assert!(violations(&CelFieldTimestamp { val: None }).is_empty());
+    assert!(violations(&CelFieldTimestamp { val: ts(5_000) }).is_empty());
+    assert_eq!(
+        violations(&CelFieldTimestamp { val: ts(20_000) }),
+        violation("val", "timestamp.past", "value must be in the past")
+    );
+    let val = Some(Duration {
+        seconds: 30,
+        nanos: 0,
+    });
+    assert!(violations(&CelFieldDuration { val }).is_empty());
+    let val = Some(Duration {
+        seconds: 60,
+        nanos: 1,
+    });
+    assert_eq!(
+        violations(&CelFieldDuration { val }),
+        violation(
+            "val",
+            "duration.short",
+            "value must be shorter than a minute"
+        )
+    );
+    assert!(violations(&CelMessage { min: 1, max: 2 }).is_empty());
+    assert_eq!(
+        violations(&CelMessage { min: 2, max: 1 }),
+        violation("", "message.range", "min must be less than max")
+    );
+    assert!(violations(&CelMessageHas {
+        a: Some("".into()),
+        b: None
+    })
+    .is_empty());
+    assert_eq!(
+        violations(&CelMessageHas { a: None, b: None }),
+        violation("", "message.either", "a or b must be set")
+    );
+    assert_eq!(
+        violations(&CelMessageNested {
+            val: Some(CelMessage { min: 0, max: 0 })
+        }),
+        violation(
+            "val",
+            "message.range",
+            r#""tests.harness.cases.CelMessage": min must be less than max"#
+        )
+    );
+    use cel_message_oneof::O;
+    assert!(violations(&CelMessageOneof { o: None }).is_empty());
+    assert!(violations(&CelMessageOneof { o: Some(O::I(1)) }).is_empty());
+    assert_eq!(
+        violations(&CelMessageOneof { o: Some(O::I(0)) }),
+        violation("", "oneof.positive", "i must be positive")
+    );
+    assert_eq!(
+        violations(&CelMessageOneof {
+            o: Some(O::S("".into()))
+        }),
+        violation("s", "oneof.s", "s must not be empty")
+    );
+    assert_eq!(
+        violations(&CelMessageOneof {
+            o: Some(O::M(CelMessage { min: 1, max: 1 }))
+        })
+        .into_iter()
+        .map(|(_, id, _)| id)
+        .collect::<Vec<_>>(),
+        vec!["message.range", "oneof.range"]
+    );
+    let collections = || CelMessageCollections {
+        tags: vec!["a".into(), "b".into()],
+        limits: HashMap::from([("a".into(), 1), ("b".into(), 10)]),
+        ranges: vec![CelMessage { min: 0, max: 10 }],
+        deadline: Some(Timestamp {
+            seconds: 12_000,
+            nanos: 0,
+        }),
+    };
+    assert!(violations(&collections()).is_empty());
+    assert_eq!(
+        violations(&CelMessageCollections {
+            tags: vec!["c".into()],
+            ..collections()
+        }),
+        violation(
+            "",
+            "collections.tags",
+            "every tag must have a limit of at most 10"
+        )
+    );
+    assert_eq!(
+        violations(&CelMessageCollections {
+            limits: HashMap::from([("a".into(), 1), ("b".into(), 11)]),
+            ..collections()
+        }),
+        violation(
+            "",
+            "collections.tags",
+            "every tag must have a limit of at most 10"
+        )
+    );
+    assert_eq!(
+        violations(&CelMessageCollections {
+            ranges: vec![CelMessage { min: 0, max: 11 }],
+            ..collections()
+        }),
+        violation("", "collections.ranges", "ranges must be at most 10 wide")
+    );
+    assert_eq!(
+        violations(&CelMessageCollections {
+            deadline: Some(Timestamp {
+                seconds: 14_000,
+                nanos: 0,
+            }),
+            ..collections()
+        }),
+        violation(
+            "",
+            "collections.deadline",
+            "deadline must be within an hour"
+        )
+    );
+}
+
+/// Returns the pool of the test cases without any validation rule.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+fn pool_without_rules() -> ::prost_reflect::DescriptorPool {
+    use ::prost::Message;
+
+    let mut set =
+        ::prost_types::FileDescriptorSet::decode(crate::_CASES_FILE_DESCRIPTOR_SET_BYTES).unwrap();
+    for file in set.file.iter_mut() {
+        for message in file.message_type.iter_mut() {
+            for field in message.field.iter_mut() {
+                field.options = None;
+            }
+        }
+    }
+    ::prost_reflect::DescriptorPool::from_file_descriptor_set(set).unwrap()
+}
+
+/// Returns the options of the first field of the message in the file descriptor set.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+fn field_options<'a>(
+    set: &'a mut ::prost_reflect::DynamicMessage,
+    message: &str,
+) -> &'a mut ::prost_reflect::DynamicMessage {
+    use ::prost_reflect::Value;
+
+    set.get_field_by_name_mut("file")
+        .and_then(Value::as_list_mut)
+        .unwrap()
+        .iter_mut()
+        .filter_map(Value::as_message_mut)
+        .flat_map(|file| {
+            file.get_field_by_name_mut("message_type")
+                .and_then(Value::as_list_mut)
+                .unwrap()
+                .iter_mut()
+        })
+        .filter_map(Value::as_message_mut)
+        .find(|m| m.get_field_by_name("name").unwrap().as_str() == Some(message))
+        .and_then(|m| m.get_field_by_name_mut("field"))
+        .and_then(Value::as_list_mut)
+        .and_then(|fields| fields.first_mut())
+        .and_then(Value::as_message_mut)
+        .and_then(|field| field.get_field_by_name_mut("options"))
+        .and_then(Value::as_message_mut)
+        .unwrap()
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_registry_pool() {
+    use ::prost_reflect::DescriptorPool;
+    use ::prost_reflect_validate::Registry;
+
+    let pool = pool_without_rules();
+
+    let msg = MapMin {
+        val: HashMap::from([(1, 1.0)]),
+    };
+    let bare = Registry::new(pool.clone());
+    let cases = Registry::new(
+        ::prost_reflect::ReflectMessage::descriptor(&msg)
+            .parent_pool()
+            .clone(),
+    );
+    assert!(bare.validate(&msg).is_ok());
+    assert!(bare.validate_all(&msg).is_ok());
+    assert!(cases.validate(&msg).is_err());
+    assert!(::prost_reflect_validate::validate(&msg).is_err());
+    assert_eq!(cases.validate_all(&msg).unwrap_err().len(), 1);
+    assert_eq!(bare.pool(), Some(pool));
+
+    let err = Registry::new(DescriptorPool::new())
+        .validate(&msg)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("not found"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_registry_reload() {
+    use ::prost::Message;
+    use ::prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage};
+    use ::prost_reflect_validate::Registry;
+
+    let msg = MapMin {
+        val: HashMap::from([(1, 1.0)]),
+    };
+    let bare = pool_without_rules();
+    let cases = msg.descriptor().parent_pool().clone();
+
+    let registry = Registry::new(bare.clone());
+    let shared = registry.clone();
+    assert!(registry.validate(&msg).is_ok());
+    registry.reload(cases.clone()).unwrap();
+    assert!(shared.validate(&msg).is_err());
+    assert_eq!(shared.pool(), Some(cases.clone()));
+    registry.reload(bare.clone()).unwrap();
+    assert!(shared.validate(&msg).is_ok());
+
+    // an invalid pool is rejected and the registry keeps the current one
+    let mut set = DynamicMessage::decode(
+        cases
+            .get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap(),
+        crate::_CASES_FILE_DESCRIPTOR_SET_BYTES,
+    )
+    .unwrap();
+    // string rules on a map field
+    let string = field_options(&mut set, "StringPattern").clone();
+    *field_options(&mut set, "MapMin") = string;
+    let invalid = DescriptorPool::decode(set.encode_to_vec().as_slice()).unwrap();
+    assert!(registry.reload(invalid).is_err());
+    assert_eq!(registry.pool(), Some(bare.clone()));
+
+    // the default registry compiles a message again when its definition changes
+    let registry = Registry::default();
+    let encoded = msg.encode_to_vec();
+    let decode = |pool: &DescriptorPool| {
+        let desc = pool
+            .get_message_by_name(msg.descriptor().full_name())
+            .unwrap();
+        DynamicMessage::decode(desc, encoded.as_slice()).unwrap()
+    };
+    assert!(registry.validate_dynamic(&decode(&cases)).is_err());
+    assert!(registry.validate_dynamic(&decode(&bare)).is_ok());
+    assert!(registry.validate_dynamic(&decode(&cases)).is_err());
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_registry_overrides() {
+    use ::prost_reflect::ReflectMessage;
+    use ::prost_reflect_validate::{Overrides, Registry};
+    use ::prost_validate::RuleOrigin;
+
+    let msg = |val: &str| StringMinMaxLen {
+        val: val.to_owned(),
+    };
+    let registry = Registry::new(msg("").descriptor().parent_pool().clone());
+    assert!(registry.validate(&msg("abcde")).is_ok());
+
+    let overrides: Overrides = serde_json::from_str(
+        r#"{
+            "merge": {
+                "tests.harness.cases.StringMinMaxLen.val": {"string": {"maxLen": 4}},
+                "tests.harness.cases.RepeatedMax.val": {"repeated": {"items": {"double": {"gt": 0}}}},
+                "tests.harness.cases.Int32In.val": {"int32": {"in": [2]}}
+            },
+            "replace": {"tests.harness.cases.StringIn.val": {}}
+        }"#,
+    )
+    .unwrap();
+    registry.set_overrides(overrides).unwrap();
+    let err = registry.validate(&msg("abcde")).unwrap_err();
+    assert_eq!(err.rule_id(), "string.max_len");
+    assert_eq!(err.origin, RuleOrigin::Override);
+    assert!(err.to_string().ends_with("(override)"), "{err}");
+    let err = registry.validate(&msg("ab")).unwrap_err();
+    assert_eq!(err.rule_id(), "string.min_len");
+    assert_eq!(err.origin, RuleOrigin::Proto);
+    let err = registry
+        .validate(&RepeatedMax { val: vec![-1.0] })
+        .unwrap_err();
+    assert_eq!(err.rule_id(), "double.gt");
+    assert_eq!(err.origin, RuleOrigin::Override);
+    let err = registry
+        .validate(&RepeatedMax {
+            val: vec![1.0, 2.0, 3.0, 4.0],
+        })
+        .unwrap_err();
+    assert_eq!(err.rule_id(), "repeated.max_items");
+    assert_eq!(err.origin, RuleOrigin::Proto);
+    // the list of the override replaces the annotated one
+    assert!(registry.validate(&Int32In { val: 2 }).is_ok());
+    let err = registry.validate(&Int32In { val: 3 }).unwrap_err();
+    assert_eq!(err.rule_id(), "int32.in");
+    assert_eq!(err.origin, RuleOrigin::Override);
+    // replaced by empty rules
+    assert!(registry
+        .validate(&StringIn {
+            val: "foo".to_owned(),
+        })
+        .is_ok());
+
+    // unknown fields and invalid rules are rejected, the current overrides are kept
+    let unknown = Overrides::new().with_merged(
+        "tests.harness.cases.StringMinMaxLen.missing",
+        Default::default(),
+    );
+    assert!(registry.set_overrides(unknown).is_err());
+    let invalid: Overrides = serde_json::from_str(
+        r#"{"merge": {"tests.harness.cases.StringMinMaxLen.val": {"int32": {"gt": 1}}}}"#,
+    )
+    .unwrap();
+    assert!(registry.set_overrides(invalid).is_err());
+    assert!(registry.validate(&msg("abcde")).is_err());
+
+    registry.set_overrides(Overrides::new()).unwrap();
+    assert!(registry.validate(&msg("abcde")).is_ok());
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_validate_json() {
+    use ::prost_reflect::ReflectMessage;
+    use ::prost_validate::errors;
+
+    let desc = KitchenSinkMessage::default().descriptor();
+    let valid = r#"{"val": {"const": "abcd", "intConst": 5, "floatVal": 1, "durVal": "1s", "tsVal": "1970-01-01T00:00:08Z", "doubleIn": 123, "enumConst": "ComplexTWO", "bytesVal": "AJk=", "x": "x"}}"#;
+    ::prost_reflect_validate::validate_json(&desc, valid).unwrap();
+
+    let invalid = r#"{
+        "val": {
+            "const": "abcd", "intConst": 4, "floatVal": 1, "durVal": "1s", "tsVal": "1970-01-01T00:00:08Z",
+            "doubleIn": 123, "enumConst": "ComplexTWO", "bytesVal": "AJk=", "x": "x",
+            "repTsVal": ["1970-01-01T00:00:00Z"], "mapVal": {"1": "a"}
+        }
+    }"#;
+    let errs = ::prost_reflect_validate::validate_all_json(&desc, invalid).unwrap_err();
+    let pointers = errs
+        .iter()
+        .map(|err| (err.path.to_json_pointer(), err.rule_id().to_owned()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pointers,
+        [
+            ("/val/intConst".to_owned(), "int32.const".to_owned()),
+            ("/val/repTsVal/0".to_owned(), "timestamp.gte".to_owned()),
+            ("/val/mapVal/1".to_owned(), "sint32.lt".to_owned()),
+        ]
+    );
+    assert_eq!(errs[0].path.to_string(), "val.intConst");
+
+    let err = ::prost_reflect_validate::validate_json(&desc, r#"{"val": {"intConst": "a"}}"#)
+        .unwrap_err();
+    assert!(matches!(err.details, errors::Error::Decode(_)), "{err}");
+    let err = ::prost_reflect_validate::validate_json(&desc, "{} {}").unwrap_err();
+    assert!(matches!(err.details, errors::Error::Decode(_)), "{err}");
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_validate_field() {
+    use ::prost_reflect::{ReflectMessage, Value};
+    use ::prost_validate::errors;
+
+    // only the rules of the field are run, the message being invalid as a whole
+    let msg = ComplexTestMsg {
+        r#const: "abcd".to_string(),
+        int_const: 4,
+        ..Default::default()
+    };
+    assert!(::prost_validate::Validator::validate(&msg).is_err());
+    ::prost_validate::Validator::validate_field(&msg, "const").unwrap();
+    let err = ::prost_validate::Validator::validate_field(&msg, "int_const").unwrap_err();
+    assert_eq!(err.rule_id(), "int32.const");
+    assert!(::prost_validate::Validator::validate_field(&msg, "dur_val").is_err());
+    let err = ::prost_validate::Validator::validate_field(&msg, "missing").unwrap_err();
+    assert!(
+        matches!(err.details, errors::Error::InvalidRules(_)),
+        "{err}"
+    );
+
+    let desc = msg.descriptor();
+    // the other fields of the reflected message are left unset and invalid
+    let field = desc.get_field_by_name("const").unwrap();
+    ::prost_reflect_validate::validate_field(&field, &Value::String("abcd".to_string())).unwrap();
+    let err = ::prost_reflect_validate::validate_field(&field, &Value::String("a".to_string()))
+        .unwrap_err();
+    assert_eq!(err.rule_id(), "string.const");
+    let field = desc.get_field_by_name("int_const").unwrap();
+    ::prost_reflect_validate::validate_field(&field, &Value::I32(5)).unwrap();
+    let err = ::prost_reflect_validate::validate_field(&field, &Value::I32(4)).unwrap_err();
+    assert_eq!(err.rule_id(), "int32.const");
+    assert_eq!(err.path.to_string(), "int_const");
+    let err = ::prost_reflect_validate::validate_field(&field, &Value::String("a".to_string()))
+        .unwrap_err();
+    assert!(matches!(err.details, errors::Error::Decode(_)), "{err}");
+
+    // a field of a required oneof satisfies it
+    let x = desc.get_field_by_name("x").unwrap();
+    ::prost_reflect_validate::validate_field(&x, &Value::String("x".to_string())).unwrap();
+    let field = desc.get_field_by_name("rep_ts_val").unwrap();
+    let ts = Timestamp::default().transcode_to_dynamic();
+    let err =
+        ::prost_reflect_validate::validate_field(&field, &Value::List(vec![Value::Message(ts)]))
+            .unwrap_err();
+    assert_eq!(err.rule_id(), "timestamp.gte");
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_registry_compile_pool() {
+    use ::prost::Message;
+    use ::prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
+    use ::prost_reflect_validate::Registry;
+
+    let pool = StringPattern::default().descriptor().parent_pool().clone();
+    let registry = Registry::compile_pool(&pool).unwrap();
+    assert!(registry
+        .validate(&StringPattern {
+            val: "a!".to_owned(),
+        })
+        .is_err());
+
+    let mut set = DynamicMessage::decode(
+        pool.get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap(),
+        crate::_CASES_FILE_DESCRIPTOR_SET_BYTES,
+    )
+    .unwrap();
+    // string rules on an int32 field
+    let string = field_options(&mut set, "StringPattern").clone();
+    *field_options(&mut set, "Int32Const") = string;
+    // an invalid pattern
+    let ext = pool.get_extension_by_name("validate.rules").unwrap();
+    field_options(&mut set, "StringPattern")
+        .get_extension_mut(&ext)
+        .as_message_mut()
+        .and_then(|rules| rules.get_field_by_name_mut("string"))
+        .and_then(Value::as_message_mut)
+        .unwrap()
+        .set_field_by_name("pattern", Value::String("(".to_owned()));
+    let pool = DescriptorPool::decode(set.encode_to_vec().as_slice()).unwrap();
+
+    let errs = Registry::compile_pool(&pool).unwrap_err();
+    let mut fields = errs.iter().map(|e| e.field.as_str()).collect::<Vec<_>>();
+    fields.sort();
+    assert_eq!(
+        fields,
+        vec![
+            "tests.harness.cases.Int32Const.val",
+            "tests.harness.cases.StringPattern.val"
+        ],
+        "unexpected errors: {errs}"
+    );
+    // the invalid rules are reported when validating too
+    let err = Registry::new(pool)
+        .validate(&Int32Const { val: 1 })
+        .unwrap_err();
+    assert_eq!(err.field, "tests.harness.cases.Int32Const.val");
+}
+
+#[test]
+fn regress_validate_dynamic_and_bytes() {
+    use ::prost::Message;
+    use ::prost_reflect::ReflectMessage;
+
+    for (name, f) in CASES.iter() {
+        let (message, failures) = f();
+        let dynamic = message.to_dynamic();
+        let buf = dynamic.encode_to_vec();
+        assert_eq!(
+            ::prost_reflect_validate::validate_dynamic(&dynamic).is_ok(),
+            failures == 0,
+            "{name}: unexpected dynamic validation result"
+        );
+        assert_eq!(
+            ::prost_reflect_validate::validate_bytes(&dynamic.descriptor(), &buf).is_ok(),
+            failures == 0,
+            "{name}: unexpected bytes validation result"
+        );
+    }
+    #[allow(clippy::unwrap_used)]
+    let err =
+        ::prost_reflect_validate::validate_bytes(&StringConst::default().descriptor(), &[0xff])
+            .unwrap_err();
+    assert_eq!(err.rule_id(), "decode");
+}
+
+#[test]
+#[allow(clippy::unwrap_used)]
+fn regress_map_no_sparse_bytes() {
+    use ::prost_reflect::ReflectMessage;
+
+    let desc = MapNoSparse::default().descriptor();
+    // {1: {}}, the value is set to the default message
+    let valid = [0x0a, 0x04, 0x08, 0x01, 0x12, 0x00];
+    assert!(::prost_reflect_validate::validate_bytes(&desc, &valid).is_ok());
+    // {1: {}, 2: <missing>}
+    let sparse = [0x0a, 0x04, 0x08, 0x01, 0x12, 0x00, 0x0a, 0x02, 0x08, 0x02];
+    let err = ::prost_reflect_validate::validate_bytes(&desc, &sparse).unwrap_err();
+    assert_eq!(err.rule_id(), "map.no_sparse");
+    assert_eq!(err.path.to_string(), "val[2]");
+    // the decoded message cannot tell the missing value from the default one
+    let msg = ::prost_reflect::DynamicMessage::decode(desc, &sparse[..]).unwrap();
+    assert!(::prost_reflect_validate::validate_dynamic(&msg).is_ok());
+}
+
 pub static CASES: Lazy<HashMap<&'static str, Factory>> = Lazy::new(|| {
     HashMap::from([
         (
@@ -8606,5 +9333,423 @@ pub static CASES: Lazy<HashMap<&'static str, Factory>> = Lazy::new(|| {
                 )
             }) as Factory,
         ),
+        (
+            "protovalidate_string_min_len_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringMinLen {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_min_len_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringMinLen {
+                        val: "fo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_well_known_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringWellKnown {
+                        val: "foo@example.com".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_well_known_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringWellKnown {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringRequired {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateStringRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_ignore_empty_valid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateStringIgnoreEmpty>::default() as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_ignore_empty_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringIgnoreEmpty {
+                        val: "fo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bytes_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateBytesRequired {
+                        val: b"foo".to_vec(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bytes_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateBytesRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_int64_range_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateInt64Range { val: 10 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_int64_range_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateInt64Range { val: 11 }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_uint32_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateUInt32Required { val: 1 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_uint32_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateUInt32Required>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bool_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateBoolRequired { val: true }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bool_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateBoolRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateEnumRequired {
+                        val: ProtovalidateEnum::One as i32,
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateEnumRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_invalid_undefined",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateEnumRequired { val: 2 }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageRequired {
+                        val: Some(ProtovalidateEmbed { val: 1 }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateMessageRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_invalid_embedded",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageRequired {
+                        val: Some(ProtovalidateEmbed { val: 0 }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_ignored_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageIgnored {
+                        val: Some(ProtovalidateEmbed { val: 0 }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedItems {
+                        val: vec!["foo".to_string()],
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_invalid_min_items",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateRepeatedItems>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_invalid_item",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedItems {
+                        val: vec!["foo".to_string(), "f".to_string()],
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedRequired { val: vec![0] }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateRepeatedRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_map_keys_values_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMapKeysValues {
+                        val: HashMap::from([("a".to_string(), 1)]),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_map_keys_values_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMapKeysValues {
+                        val: HashMap::from([("".to_string(), 0)]),
+                    }) as Box<dyn Validator>,
+                    2,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateDurationRequired {
+                        val: Some(Duration {
+                            seconds: 1,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateDurationRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_invalid_lt",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateDurationRequired {
+                        val: Some(Duration {
+                            seconds: 10,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_timestamp_lt_now_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateTimestampLtNow {
+                        val: Some(Timestamp {
+                            seconds: 0,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_timestamp_lt_now_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateTimestampLtNow {
+                        val: Some(Timestamp {
+                            seconds: now() + 7200,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_oneof_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateOneofRequired {
+                        o: Some(protovalidate_oneof_required::O::X("".to_string())),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_oneof_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateOneofRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_disabled_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageDisabled { val: 0 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_mixed_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMixed {
+                        pgv: "a".to_string(),
+                        protovalidate: "b".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_mixed_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMixed {
+                        pgv: "".to_string(),
+                        protovalidate: "abcd".to_string(),
+                    }) as Box<dyn Validator>,
+                    2,
+                )
+            }) as Factory,
+        ),
     ])
 });
@@ -6,6 +6,39 @@ use once_cell::sync::Lazy;
 use pbjson_types::*;
 use std::collections::HashMap;
 
+#[test]
+fn regress_cel_nested_oneof() {
+    use ::prost_reflect_validate::ValidatorExt;
+    use cel_nested_oneof::{inner, leaf, Inner, Leaf};
+
+    // the implementations of the pbjson harness are generated in the `.validate.rs` files
+    fn rule_ids(msg: CelNestedOneof) -> Vec<String> {
+        let derive = match ::prost_validate::Validator::validate_all(&msg) {
+            Ok(()) => vec![],
+            Err(errs) => errs.iter().map(|e| e.rule_id().to_string()).collect(),
+        };
+        let reflect = match ValidatorExt::validate_all(&msg) {
+            Ok(()) => vec![],
+            Err(errs) => errs.iter().map(|e| e.rule_id().to_string()).collect(),
+        };
+        assert_eq!(derive, reflect);
+        derive
+    }
+    let msg = |o| CelNestedOneof {
+        val: Some(Inner { o: Some(o) }),
+    };
+    assert!(rule_ids(msg(inner::O::I(1))).is_empty());
+    assert_eq!(rule_ids(msg(inner::O::I(0))), ["nested_oneof.positive"]);
+    let valid = Leaf {
+        p: Some(leaf::P::J(0)),
+    };
+    assert!(rule_ids(msg(inner::O::Leaf(valid))).is_empty());
+    assert_eq!(
+        rule_ids(msg(inner::O::Leaf(Leaf { p: None }))),
+        ["nested_oneof.leaf"]
+    );
+}
+
 pub static CASES: Lazy<HashMap<&'static str, Factory>> = Lazy::new(|| {
     HashMap::from([
         (
@@ -8623,5 +8656,423 @@ pub static CASES: Lazy<HashMap<&'static str, Factory>> = Lazy::new(|| {
                 )
             }) as Factory,
         ),
+        (
+            "protovalidate_string_min_len_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringMinLen {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_min_len_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringMinLen {
+                        val: "fo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_well_known_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringWellKnown {
+                        val: "foo@example.com".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_well_known_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringWellKnown {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringRequired {
+                        val: "foo".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateStringRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_ignore_empty_valid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateStringIgnoreEmpty>::default() as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_string_ignore_empty_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateStringIgnoreEmpty {
+                        val: "fo".to_string(),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bytes_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateBytesRequired {
+                        val: b"foo".to_vec(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bytes_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateBytesRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_int64_range_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateInt64Range { val: 10 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_int64_range_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateInt64Range { val: 11 }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_uint32_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateUInt32Required { val: 1 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_uint32_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateUInt32Required>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bool_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateBoolRequired { val: true }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_bool_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateBoolRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateEnumRequired {
+                        val: ProtovalidateEnum::One as i32,
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateEnumRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_enum_required_invalid_undefined",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateEnumRequired { val: 2 }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageRequired {
+                        val: Some(ProtovalidateEmbed { val: 1 }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateMessageRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_required_invalid_embedded",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageRequired {
+                        val: Some(ProtovalidateEmbed { val: 0 }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_ignored_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageIgnored {
+                        val: Some(ProtovalidateEmbed { val: 0 }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedItems {
+                        val: vec!["foo".to_string()],
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_invalid_min_items",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateRepeatedItems>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_items_invalid_item",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedItems {
+                        val: vec!["foo".to_string(), "f".to_string()],
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateRepeatedRequired { val: vec![0] }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_repeated_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateRepeatedRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_map_keys_values_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMapKeysValues {
+                        val: HashMap::from([("a".to_string(), 1)]),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_map_keys_values_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMapKeysValues {
+                        val: HashMap::from([("".to_string(), 0)]),
+                    }) as Box<dyn Validator>,
+                    2,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateDurationRequired {
+                        val: Some(Duration {
+                            seconds: 1,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateDurationRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_duration_required_invalid_lt",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateDurationRequired {
+                        val: Some(Duration {
+                            seconds: 10,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_timestamp_lt_now_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateTimestampLtNow {
+                        val: Some(Timestamp {
+                            seconds: 0,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_timestamp_lt_now_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateTimestampLtNow {
+                        val: Some(Timestamp {
+                            seconds: now() + 7200,
+                            nanos: 0,
+                        }),
+                    }) as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_oneof_required_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateOneofRequired {
+                        o: Some(protovalidate_oneof_required::O::X("".to_string())),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_oneof_required_invalid",
+            Box::new(|| {
+                (
+                    Box::<ProtovalidateOneofRequired>::default() as Box<dyn Validator>,
+                    1,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_message_disabled_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMessageDisabled { val: 0 }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_mixed_valid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMixed {
+                        pgv: "a".to_string(),
+                        protovalidate: "b".to_string(),
+                    }) as Box<dyn Validator>,
+                    0,
+                )
+            }) as Factory,
+        ),
+        (
+            "protovalidate_mixed_invalid",
+            Box::new(|| {
+                (
+                    Box::new(ProtovalidateMixed {
+                        pgv: "".to_string(),
+                        protovalidate: "abcd".to_string(),
+                    }) as Box<dyn Validator>,
+                    2,
+                )
+            }) as Factory,
+        ),
     ])
 });
@@ -1,4 +1,4 @@
-use prost_reflect::ReflectMessage;
+use prost_reflect::{DynamicMessage, ReflectMessage};
 use prost_reflect_validate::ValidatorExt;
 use prost_validate::Validator as ValidatorDerive;
 
@@ -31,9 +31,15 @@ static _CASES_YET_ANOTHER_PACKAGE_FILE_DESCRIPTOR_SET_BYTES: &[u8] = include_byt
     "/cases_yet_another_package_file_descriptor_set.bin"
 ));
 
-pub trait Validator: ReflectMessage + ValidatorExt + ValidatorDerive {}
+pub trait Validator: ReflectMessage + ValidatorExt + ValidatorDerive {
+    fn to_dynamic(&self) -> DynamicMessage;
+}
 
-impl<T: ReflectMessage + ValidatorExt + ValidatorDerive> Validator for T {}
+impl<T: ReflectMessage + ValidatorExt + ValidatorDerive> Validator for T {
+    fn to_dynamic(&self) -> DynamicMessage {
+        self.transcode_to_dynamic()
+    }
+}
 
 pub type Factory = Box<dyn Fn() -> (Box<dyn Validator>, i32) + Send + Sync>;
 
@@ -6,30 +6,50 @@
 #[allow(clippy::regex_creation_in_loops)]
 pub(crate) mod cases {
     include!(concat!(env!("OUT_DIR"), "/pbjson/tests.harness.cases.rs"));
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/pbjson/tests.harness.cases.validate.rs"
+    ));
 
     pub(crate) mod sort {
         include!(concat!(
             env!("OUT_DIR"),
             "/pbjson/tests.harness.cases.sort.rs"
         ));
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/pbjson/tests.harness.cases.sort.validate.rs"
+        ));
     }
     pub(crate) mod yet_another_package {
         include!(concat!(
             env!("OUT_DIR"),
             "/pbjson/tests.harness.cases.yet_another_package.rs"
         ));
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/pbjson/tests.harness.cases.yet_another_package.validate.rs"
+        ));
     }
     pub(crate) mod other_package {
         include!(concat!(
             env!("OUT_DIR"),
             "/pbjson/tests.harness.cases.other_package.rs"
         ));
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/pbjson/tests.harness.cases.other_package.validate.rs"
+        ));
     }
     pub(crate) mod proto2 {
         include!(concat!(
             env!("OUT_DIR"),
             "/pbjson/tests.harness.cases.proto2.rs"
         ));
+        include!(concat!(
+            env!("OUT_DIR"),
+            "/pbjson/tests.harness.cases.proto2.validate.rs"
+        ));
     }
 }
 
@@ -38,4 +58,8 @@ pub(crate) mod cases {
 #[allow(dead_code)]
 pub(crate) mod harness {
     include!(concat!(env!("OUT_DIR"), "/pbjson/tests.harness.rs"));
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/pbjson/tests.harness.validate.rs"
+    ));
 }
@@ -34,6 +34,44 @@ macro_rules! test_cases {
                         Ok(_) => assert_eq!(failures, 0, "unexpected validation success"),
                     }
                 }
+
+                #[cfg(feature = "reflect")]
+                #[test]
+                fn reflect_all() {
+                    let (message, failures) = crate::cases_pbjson::CASES.get(stringify!($name)).unwrap()();
+                    match ValidatorExt::validate_all(&*message) {
+                        Err(errs) => {
+                            println!("{}", errs);
+                            assert_eq!(errs.len() as i32, failures, "unexpected validation failures: {errs}");
+                        },
+                        Ok(_) => assert_eq!(failures, 0, "unexpected validation success"),
+                    }
+                }
+
+                #[cfg(feature = "derive")]
+                #[test]
+                fn derive_all() {
+                    let (message, failures) = crate::cases_pbjson::CASES.get(stringify!($name)).unwrap()();
+                    match ValidatorDerive::validate_all(&*message) {
+                        Err(errs) => {
+                            println!("{}", errs);
+                            assert_eq!(errs.len() as i32, failures, "unexpected validation failures: {errs}");
+                        },
+                        Ok(_) => assert_eq!(failures, 0, "unexpected validation success"),
+                    }
+                }
+
+                #[cfg(all(feature = "reflect", feature = "derive"))]
+                #[test]
+                fn rule_ids() {
+                    let (message, _) = crate::cases_pbjson::CASES.get(stringify!($name)).unwrap()();
+                    let rule_ids = |errs: prost_validate::Violations| {
+                        errs.iter().map(|e| e.rule_id().to_string()).collect::<std::collections::BTreeSet<_>>()
+                    };
+                    let reflect = ValidatorExt::validate_all(&*message).err().map(rule_ids);
+                    let derive = ValidatorDerive::validate_all(&*message).err().map(rule_ids);
+                    assert_eq!(reflect, derive, "rule ids mismatch between reflect and derive");
+                }
             }
         )*
     }
@@ -1241,3 +1279,49 @@ mod kitchensink {
 mod nested {
     test_cases![nested_wkt_uuid_field_valid, nested_wkt_uuid_field_invalid,];
 }
+#[cfg(test)]
+mod protovalidate {
+
+    test_cases![
+        protovalidate_string_min_len_valid,
+        protovalidate_string_min_len_invalid,
+        protovalidate_string_well_known_valid,
+        protovalidate_string_well_known_invalid,
+        protovalidate_string_required_valid,
+        protovalidate_string_required_invalid,
+        protovalidate_string_ignore_empty_valid,
+        protovalidate_string_ignore_empty_invalid,
+        protovalidate_bytes_required_valid,
+        protovalidate_bytes_required_invalid,
+        protovalidate_int64_range_valid,
+        protovalidate_int64_range_invalid,
+        protovalidate_uint32_required_valid,
+        protovalidate_uint32_required_invalid,
+        protovalidate_bool_required_valid,
+        protovalidate_bool_required_invalid,
+        protovalidate_enum_required_valid,
+        protovalidate_enum_required_invalid,
+        protovalidate_enum_required_invalid_undefined,
+        protovalidate_message_required_valid,
+        protovalidate_message_required_invalid,
+        protovalidate_message_required_invalid_embedded,
+        protovalidate_message_ignored_valid,
+        protovalidate_repeated_items_valid,
+        protovalidate_repeated_items_invalid_min_items,
+        protovalidate_repeated_items_invalid_item,
+        protovalidate_repeated_required_valid,
+        protovalidate_repeated_required_invalid,
+        protovalidate_map_keys_values_valid,
+        protovalidate_map_keys_values_invalid,
+        protovalidate_duration_required_valid,
+        protovalidate_duration_required_invalid,
+        protovalidate_duration_required_invalid_lt,
+        protovalidate_timestamp_lt_now_valid,
+        protovalidate_timestamp_lt_now_invalid,
+        protovalidate_oneof_required_valid,
+        protovalidate_oneof_required_invalid,
+        protovalidate_message_disabled_valid,
+        protovalidate_mixed_valid,
+        protovalidate_mixed_invalid,
+    ];
+}
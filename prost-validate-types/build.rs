@@ -1,10 +1,10 @@
 use std::env;
 use std::path::PathBuf;
 
-static DIR: &str = "proto/validate";
+static DIR: &str = "proto";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let files = &["validate.proto"];
+    let files = &["validate/validate.proto", "buf/validate/validate.proto"];
     files.iter().for_each(|f| {
         println!("cargo:rerun-if-changed={}/{}", DIR, f);
     });
@@ -12,9 +12,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[allow(clippy::unwrap_used)]
     let base_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     let descriptor_path = base_path.join("file_descriptor_set.bin");
-    prost_reflect_build::Builder::new()
-        .file_descriptor_set_path(&descriptor_path)
-        .descriptor_pool("DESCRIPTOR_POOL")
-        .compile_protos(files, &[DIR])?;
+    let mut config = prost_build::Config::new();
+    config.file_descriptor_set_path(&descriptor_path);
+    let fds = config.load_fds(files, &[DIR])?;
+    // prost_reflect_build registers the messages without the leading dot, which prost_build
+    // matches as a suffix: the `validate` attributes would also apply to the `buf.validate`
+    // messages, so we register them with their fully qualified paths instead.
+    for file in fds
+        .file
+        .iter()
+        .filter(|f| !f.package().starts_with("google."))
+    {
+        for message in &file.message_type {
+            let name = format!("{}.{}", file.package(), message.name());
+            config
+                .type_attribute(
+                    format!(".{name}"),
+                    "#[derive(::prost_reflect::ReflectMessage)]",
+                )
+                .type_attribute(
+                    format!(".{name}"),
+                    format!(r#"#[prost_reflect(message_name = "{name}")]"#),
+                )
+                .type_attribute(
+                    format!(".{name}"),
+                    r#"#[prost_reflect(descriptor_pool = "crate::DESCRIPTOR_POOL")]"#,
+                );
+        }
+    }
+    config.compile_fds(fds)?;
     Ok(())
 }
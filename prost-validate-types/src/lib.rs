@@ -1,5 +1,6 @@
 #[allow(clippy::len_without_is_empty)]
 mod proto;
+mod protovalidate;
 
 use anyhow::anyhow;
 use once_cell::sync::Lazy;
@@ -9,6 +10,9 @@ use prost_reflect::{
 pub use proto::*;
 use std::borrow::Cow;
 
+#[cfg(feature = "serde")]
+mod serialize;
+
 #[allow(clippy::unwrap_used)]
 static VALIDATION_DISABLED: Lazy<ExtensionDescriptor> = Lazy::new(|| {
     DESCRIPTOR_POOL
@@ -37,24 +41,83 @@ static VALIDATION_ONE_OF_RULES: Lazy<ExtensionDescriptor> = Lazy::new(|| {
         .ok_or(anyhow!("validate.required extension not found"))
         .unwrap()
 });
+#[allow(clippy::unwrap_used)]
+static BUF_VALIDATE_MESSAGE: Lazy<ExtensionDescriptor> = Lazy::new(|| {
+    DESCRIPTOR_POOL
+        .get_extension_by_name("buf.validate.message")
+        .ok_or(anyhow!("buf.validate.message extension not found"))
+        .unwrap()
+});
+#[allow(clippy::unwrap_used)]
+static BUF_VALIDATE_ONEOF: Lazy<ExtensionDescriptor> = Lazy::new(|| {
+    DESCRIPTOR_POOL
+        .get_extension_by_name("buf.validate.oneof")
+        .ok_or(anyhow!("buf.validate.oneof extension not found"))
+        .unwrap()
+});
+#[allow(clippy::unwrap_used)]
+static BUF_VALIDATE_FIELD: Lazy<ExtensionDescriptor> = Lazy::new(|| {
+    DESCRIPTOR_POOL
+        .get_extension_by_name("buf.validate.field")
+        .ok_or(anyhow!("buf.validate.field extension not found"))
+        .unwrap()
+});
 
+/// Gives access to the validation rules of a field.
+///
+/// The rules are read from the PGV `(validate.rules)` option or, if the field does not have it,
+/// from the protovalidate `(buf.validate.field)` one, which are mapped onto the equivalent PGV rules.
+/// A field cannot have both, except for the `cel` expressions of the protovalidate rules,
+/// which are read with [`protovalidate_rules`](FieldRulesExt::protovalidate_rules).
 pub trait FieldRulesExt {
     fn validation_rules(&self) -> anyhow::Result<Option<FieldRules>>;
+    /// Returns the protovalidate `(buf.validate.field)` rules of the field as is,
+    /// e.g. to evaluate their CEL expressions.
+    fn protovalidate_rules(&self) -> anyhow::Result<Option<buf::validate::FieldRules>>;
     fn real_oneof(&self) -> Option<OneofDescriptor>;
     fn optional(&self) -> bool;
 }
 
 impl FieldRulesExt for FieldDescriptor {
     fn validation_rules(&self) -> anyhow::Result<Option<FieldRules>> {
-        match self
-            .options()
-            .get_extension(&VALIDATION_FIELD_RULES)
-            .as_message()
+        let options = self.options();
+        if !options.has_extension(&VALIDATION_FIELD_RULES)
+            && options.has_extension(&BUF_VALIDATE_FIELD)
         {
+            return match options.get_extension(&BUF_VALIDATE_FIELD).as_message() {
+                Some(r) => protovalidate::field_rules(self, &r.transcode_to()?),
+                None => Ok(None),
+            };
+        }
+        if options.has_extension(&BUF_VALIDATE_FIELD) {
+            if let Some(r) = options.get_extension(&BUF_VALIDATE_FIELD).as_message() {
+                let mut r = r.clone();
+                r.clear_field_by_name("cel");
+                if r.fields().next().is_some() {
+                    return Err(anyhow!(
+                        "both (validate.rules) and (buf.validate.field) rules are set, \
+                        only the cel expressions of the latter can be combined with the former"
+                    ));
+                }
+            }
+        }
+        match options.get_extension(&VALIDATION_FIELD_RULES).as_message() {
             Some(r) => Ok(Some(r.transcode_to::<FieldRules>()?)),
             None => Ok(None),
         }
     }
+
+    fn protovalidate_rules(&self) -> anyhow::Result<Option<buf::validate::FieldRules>> {
+        let options = self.options();
+        if !options.has_extension(&BUF_VALIDATE_FIELD) {
+            return Ok(None);
+        }
+        match options.get_extension(&BUF_VALIDATE_FIELD).as_message() {
+            Some(r) => Ok(Some(r.transcode_to()?)),
+            None => Ok(None),
+        }
+    }
+
     fn real_oneof(&self) -> Option<OneofDescriptor> {
         if let Some(oneof) = self.containing_oneof() {
             if oneof.is_synthetic() {
@@ -76,25 +139,47 @@ pub trait OneofRulesExt {
 
 impl OneofRulesExt for OneofDescriptor {
     fn required(&self) -> bool {
-        self.options()
-            .get_extension(&VALIDATION_ONE_OF_RULES)
-            .is_true()
+        let options = self.options();
+        options.get_extension(&VALIDATION_ONE_OF_RULES).is_true()
+            || options
+                .get_extension(&BUF_VALIDATE_ONEOF)
+                .as_message()
+                .is_some_and(|r| r.get_field_by_name("required").is_true())
     }
 }
 
 pub trait MessageRulesExt {
     fn validation_disabled(&self) -> bool;
     fn validation_ignored(&self) -> bool;
+    /// Returns the protovalidate `(buf.validate.message)` rules of the message as is,
+    /// e.g. to evaluate their CEL expressions.
+    fn protovalidate_rules(&self) -> anyhow::Result<Option<buf::validate::MessageRules>>;
 }
 
 impl MessageRulesExt for MessageDescriptor {
     fn validation_disabled(&self) -> bool {
-        self.options().get_extension(&VALIDATION_DISABLED).is_true()
+        let options = self.options();
+        options.get_extension(&VALIDATION_DISABLED).is_true()
+            || options
+                .get_extension(&BUF_VALIDATE_MESSAGE)
+                .as_message()
+                .is_some_and(|r| r.get_field_by_name("disabled").is_true())
     }
 
     fn validation_ignored(&self) -> bool {
         self.options().get_extension(&VALIDATION_IGNORED).is_true()
     }
+
+    fn protovalidate_rules(&self) -> anyhow::Result<Option<buf::validate::MessageRules>> {
+        let options = self.options();
+        if !options.has_extension(&BUF_VALIDATE_MESSAGE) {
+            return Ok(None);
+        }
+        match options.get_extension(&BUF_VALIDATE_MESSAGE).as_message() {
+            Some(r) => Ok(Some(r.transcode_to()?)),
+            None => Ok(None),
+        }
+    }
 }
 
 trait IsTrueExt {
@@ -106,3 +191,9 @@ impl IsTrueExt for Cow<'_, Value> {
         self.as_bool().unwrap_or(false)
     }
 }
+
+impl IsTrueExt for Option<Cow<'_, Value>> {
+    fn is_true(&self) -> bool {
+        self.as_ref().is_some_and(IsTrueExt::is_true)
+    }
+}
@@ -10,3 +10,10 @@ pub static DESCRIPTOR_POOL: Lazy<DescriptorPool> = Lazy::new(|| {
 });
 
 include!(concat!(env!("OUT_DIR"), "/validate.rs"));
+
+/// The [protovalidate](https://github.com/bufbuild/protovalidate) `buf.validate` rules.
+pub mod buf {
+    pub mod validate {
+        include!(concat!(env!("OUT_DIR"), "/buf.validate.rs"));
+    }
+}
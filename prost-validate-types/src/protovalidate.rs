@@ -0,0 +1,286 @@
+//! Mapping of the protovalidate `buf.validate` field rules onto the PGV `validate` ones.
+//!
+//! Most protovalidate type rules share their names and semantics with the PGV ones
+//! and are copied as is. The `required` and `ignore` rules, which apply to the whole
+//! field in protovalidate, are translated into the equivalent type rules.
+//! Rules without a PGV equivalent are reported as unsupported, except for the CEL
+//! expressions of the field which are exposed separately.
+
+use crate::{buf, FieldRules, MessageRules};
+use anyhow::{anyhow, bail};
+use prost_reflect::{DynamicMessage, FieldDescriptor, Kind, ReflectMessage, Value};
+
+/// `buf.validate.Ignore.IGNORE_UNSPECIFIED`
+const IGNORE_UNSPECIFIED: i32 = 0;
+/// `buf.validate.Ignore.IGNORE_IF_ZERO_VALUE`
+const IGNORE_IF_ZERO_VALUE: i32 = 1;
+/// `buf.validate.Ignore.IGNORE_ALWAYS`
+const IGNORE_ALWAYS: i32 = 3;
+
+pub(crate) fn field_rules(
+    field: &FieldDescriptor,
+    rules: &buf::validate::FieldRules,
+) -> anyhow::Result<Option<FieldRules>> {
+    match convert(field, &rules.transcode_to_dynamic(), false)? {
+        Some(rules) => Ok(Some(rules.transcode_to::<FieldRules>()?)),
+        None => Ok(None),
+    }
+}
+
+/// Converts the `buf.validate.FieldRules` of the field, or of its items if `item` is true.
+fn convert(
+    field: &FieldDescriptor,
+    src: &DynamicMessage,
+    item: bool,
+) -> anyhow::Result<Option<DynamicMessage>> {
+    let mut dst = FieldRules::default().transcode_to_dynamic();
+    let mut required = false;
+    let mut ignore = IGNORE_UNSPECIFIED;
+    for (desc, value) in src.fields() {
+        match desc.name() {
+            "required" => required = value.as_bool().unwrap_or_default(),
+            "ignore" => ignore = value.as_enum_number().unwrap_or_default(),
+            // the expressions of the field are evaluated on their own, see `FieldRulesExt::protovalidate_rules`
+            "cel" if !item => {}
+            name if desc.containing_oneof().is_some() => {
+                let rules = value
+                    .as_message()
+                    .ok_or_else(|| anyhow!("{}: expected a message", desc.full_name()))?;
+                let target = dst
+                    .descriptor()
+                    .get_field_by_name(name)
+                    .ok_or_else(|| unsupported(desc.full_name()))?;
+                let rules = convert_type_rules(field, rules, &target)?;
+                dst.try_set_field(&target, Value::Message(rules))?;
+            }
+            _ => return Err(unsupported(desc.full_name())),
+        }
+    }
+    if required {
+        require(field, &mut dst, item)?;
+    }
+    match ignore {
+        IGNORE_UNSPECIFIED => {}
+        IGNORE_IF_ZERO_VALUE => ignore_empty(field, &mut dst, item)?,
+        IGNORE_ALWAYS => return Ok(skip(field, item).map(|r| r.transcode_to_dynamic())),
+        v => bail!("unsupported protovalidate ignore value: {v}"),
+    }
+    Ok(Some(dst))
+}
+
+/// Copies the protovalidate type rules into the PGV ones described by `target`.
+fn convert_type_rules(
+    field: &FieldDescriptor,
+    src: &DynamicMessage,
+    target: &FieldDescriptor,
+) -> anyhow::Result<DynamicMessage> {
+    let desc = target
+        .kind()
+        .as_message()
+        .cloned()
+        .ok_or_else(|| anyhow!("{}: expected a message", target.full_name()))?;
+    let mut dst = DynamicMessage::new(desc);
+    for (desc, value) in src.fields() {
+        // the examples are only informative
+        if desc.name() == "example" {
+            continue;
+        }
+        let target = dst
+            .descriptor()
+            .get_field_by_name(desc.name())
+            .ok_or_else(|| unsupported(desc.full_name()))?;
+        let value = match desc.name() {
+            "items" | "keys" | "values" => {
+                let field = match (desc.name(), field.kind().as_message()) {
+                    ("keys", Some(entry)) => entry.map_entry_key_field(),
+                    ("values", Some(entry)) => entry.map_entry_value_field(),
+                    _ => field.clone(),
+                };
+                let rules = value
+                    .as_message()
+                    .ok_or_else(|| anyhow!("{}: expected a message", desc.full_name()))?;
+                match convert(&field, rules, true)? {
+                    Some(rules) => Value::Message(rules),
+                    None => continue,
+                }
+            }
+            _ => value.clone(),
+        };
+        dst.try_set_field(&target, value)?;
+    }
+    Ok(dst)
+}
+
+/// Translates the `required` rule: a field without presence must not have its zero value.
+fn require(field: &FieldDescriptor, dst: &mut DynamicMessage, item: bool) -> anyhow::Result<()> {
+    if !item && field.is_map() {
+        return set_min(type_rules(dst, "map")?, "min_pairs");
+    }
+    if !item && field.is_list() {
+        return set_min(type_rules(dst, "repeated")?, "min_items");
+    }
+    let kind = field.kind();
+    let name = match &kind {
+        Kind::Message(m) => {
+            let rules = match m.full_name() {
+                "google.protobuf.Any" => type_rules(dst, "any")?,
+                "google.protobuf.Duration" => type_rules(dst, "duration")?,
+                "google.protobuf.Timestamp" => type_rules(dst, "timestamp")?,
+                _ => dst
+                    .get_field_by_name_mut("message")
+                    .and_then(Value::as_message_mut)
+                    .ok_or_else(|| anyhow!("validate.FieldRules.message not found"))?,
+            };
+            rules.try_set_field_by_name("required", Value::Bool(true))?;
+            return Ok(());
+        }
+        _ if !item && field.supports_presence() => bail!(
+            "unsupported protovalidate rule: buf.validate.FieldRules.required on {}",
+            field.full_name()
+        ),
+        kind => type_name(kind)?,
+    };
+    let rules = type_rules(dst, name)?;
+    match kind {
+        Kind::String | Kind::Bytes => {
+            if !rules.has_field_by_name("len") {
+                set_min(rules, "min_len")?;
+            }
+        }
+        Kind::Bool => {
+            if !rules.has_field_by_name("const") {
+                rules.try_set_field_by_name("const", Value::Bool(true))?;
+            }
+        }
+        kind => {
+            let zero = match kind {
+                Kind::Enum(_) => Value::I32(0),
+                kind => Value::default_value(&kind),
+            };
+            if let Some(Value::List(not_in)) = rules.get_field_by_name_mut("not_in") {
+                not_in.push(zero);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Translates the `IGNORE_IF_ZERO_VALUE` rule.
+fn ignore_empty(
+    field: &FieldDescriptor,
+    dst: &mut DynamicMessage,
+    item: bool,
+) -> anyhow::Result<()> {
+    // unpopulated fields with presence are never validated
+    if !item && field.supports_presence() {
+        return Ok(());
+    }
+    let Some((desc, rules)) = dst
+        .fields_mut()
+        .find(|(desc, _)| desc.containing_oneof().is_some())
+    else {
+        return Ok(());
+    };
+    let rules = rules
+        .as_message_mut()
+        .ok_or_else(|| anyhow!("{}: expected a message", desc.full_name()))?;
+    if rules
+        .descriptor()
+        .get_field_by_name("ignore_empty")
+        .is_some()
+    {
+        rules.try_set_field_by_name("ignore_empty", Value::Bool(true))?;
+        return Ok(());
+    }
+    match field.kind() {
+        Kind::Message(_) => Ok(()),
+        _ => bail!(
+            "unsupported protovalidate rule: buf.validate.FieldRules.ignore on {}",
+            field.full_name()
+        ),
+    }
+}
+
+/// Translates the `IGNORE_ALWAYS` rule: only the embedded messages need to be skipped.
+fn skip(field: &FieldDescriptor, item: bool) -> Option<FieldRules> {
+    use crate::field_rules::Type;
+
+    let is_message = |field: &FieldDescriptor| {
+        field
+            .kind()
+            .as_message()
+            .is_some_and(|m| !m.full_name().starts_with("google.protobuf."))
+    };
+    let skip = || FieldRules {
+        message: Some(MessageRules {
+            skip: Some(true),
+            required: None,
+        }),
+        r#type: None,
+    };
+    if !item && field.is_map() {
+        let entry = field.kind().as_message()?.clone();
+        return is_message(&entry.map_entry_value_field()).then(|| FieldRules {
+            message: None,
+            r#type: Some(Type::Map(Box::new(crate::MapRules {
+                values: Some(Box::new(skip())),
+                ..Default::default()
+            }))),
+        });
+    }
+    if !item && field.is_list() {
+        return is_message(field).then(|| FieldRules {
+            message: None,
+            r#type: Some(Type::Repeated(Box::new(crate::RepeatedRules {
+                items: Some(Box::new(skip())),
+                ..Default::default()
+            }))),
+        });
+    }
+    is_message(field).then(skip)
+}
+
+/// Returns the type rules `name` of the PGV rules, creating them if needed.
+fn type_rules<'a>(
+    dst: &'a mut DynamicMessage,
+    name: &str,
+) -> anyhow::Result<&'a mut DynamicMessage> {
+    dst.get_field_by_name_mut(name)
+        .and_then(Value::as_message_mut)
+        .ok_or_else(|| anyhow!("validate.FieldRules.{name} not found"))
+}
+
+/// Sets the `name` lower bound of the rules to at least one.
+fn set_min(rules: &mut DynamicMessage, name: &str) -> anyhow::Result<()> {
+    if rules.get_field_by_name(name).and_then(|v| v.as_u64()) < Some(1) {
+        rules.try_set_field_by_name(name, Value::U64(1))?;
+    }
+    Ok(())
+}
+
+/// Returns the name of the PGV type rules of the scalar kind.
+fn type_name(kind: &Kind) -> anyhow::Result<&'static str> {
+    Ok(match kind {
+        Kind::Double => "double",
+        Kind::Float => "float",
+        Kind::Int32 => "int32",
+        Kind::Int64 => "int64",
+        Kind::Uint32 => "uint32",
+        Kind::Uint64 => "uint64",
+        Kind::Sint32 => "sint32",
+        Kind::Sint64 => "sint64",
+        Kind::Fixed32 => "fixed32",
+        Kind::Fixed64 => "fixed64",
+        Kind::Sfixed32 => "sfixed32",
+        Kind::Sfixed64 => "sfixed64",
+        Kind::Bool => "bool",
+        Kind::String => "string",
+        Kind::Bytes => "bytes",
+        Kind::Enum(_) => "enum",
+        Kind::Message(m) => bail!("{}: not a scalar type", m.full_name()),
+    })
+}
+
+fn unsupported(name: &str) -> anyhow::Error {
+    anyhow!("unsupported protovalidate rule: {name}")
+}
@@ -0,0 +1,27 @@
+//! Serialization of the rules in the protobuf JSON mapping,
+//! e.g. to load rules from a configuration file.
+//!
+//! The rules are read and written with the [`serde`](https://docs.rs/serde) data model,
+//! so any format implementing it can be used, such as JSON or YAML:
+//! `{"string": {"maxLen": 3}}`. The original field names, e.g. `max_len`, are accepted too.
+
+use crate::FieldRules;
+use prost_reflect::ReflectMessage;
+use serde::de::{DeserializeSeed, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl Serialize for FieldRules {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.transcode_to_dynamic().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldRules {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        FieldRules::default()
+            .descriptor()
+            .deserialize(deserializer)?
+            .transcode_to()
+            .map_err(Error::custom)
+    }
+}
@@ -0,0 +1,373 @@
+//! An [`axum`](https://docs.rs/axum) extractor decoding and validating protobuf messages.
+//!
+//! The [`Validated`] extractor decodes the request body, either from JSON (`application/json`)
+//! using the message's `serde` implementation, e.g. generated by `pbjson`,
+//! or from protobuf (`application/protobuf`, `application/x-protobuf`), then validates it.
+//! The [`ValidatedProto`] extractor only accepts protobuf bodies, for the messages without `serde` implementation.
+//!
+//! Requests that cannot be decoded or that are invalid are rejected with an
+//! `application/problem+json` response listing the invalid fields and the rules that failed.
+//! The fields of the JSON bodies are named with their JSON names, in lowerCamelCase: see
+//! [`FieldPath::to_json_names`](crate::FieldPath::to_json_names) for the fields with a custom `json_name`.
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Bad Request",
+//!   "status": 400,
+//!   "detail": "\"pkg.Msg.name\": characters length must be greater than or equal to 3",
+//!   "violations": [
+//!     {
+//!       "field": "name",
+//!       "rule": "string.min_len",
+//!       "message": "characters length must be greater than or equal to 3"
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::{Validator, Violations};
+use ::axum_core::body::Body;
+use ::axum_core::extract::{FromRequest, Request};
+use ::axum_core::response::{IntoResponse, Response};
+use http::header::CONTENT_TYPE;
+use http::{HeaderValue, StatusCode};
+use prost::bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+/// The content type of the rejection responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An extractor decoding the request body as a `T` message and validating it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    /// Returns the validated message.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, S> FromRequest<S> for Validated<T>
+where
+    T: prost::Message + Default + DeserializeOwned + Validator,
+    S: Send + Sync,
+{
+    type Rejection = ValidationRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let json = |body: &[u8]| serde_json::from_slice::<T>(body).map_err(|err| err.to_string());
+        extract(req, state, Some(json)).await.map(Validated)
+    }
+}
+
+/// An extractor decoding the protobuf request body as a `T` message and validating it.
+///
+/// Unlike [`Validated`], it does not require the message to implement `serde::Deserialize`,
+/// and rejects the JSON bodies as unsupported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedProto<T>(pub T);
+
+impl<T> ValidatedProto<T> {
+    /// Returns the validated message.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for ValidatedProto<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, S> FromRequest<S> for ValidatedProto<T>
+where
+    T: prost::Message + Default + Validator,
+    S: Send + Sync,
+{
+    type Rejection = ValidationRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        extract::<T, S, fn(&[u8]) -> Result<T, String>>(req, state, None)
+            .await
+            .map(ValidatedProto)
+    }
+}
+
+/// Decodes the request body as a `T` message, from JSON with `json` if given, and validates it.
+async fn extract<T, S, F>(
+    req: Request,
+    state: &S,
+    json: Option<F>,
+) -> Result<T, ValidationRejection>
+where
+    T: prost::Message + Default + Validator,
+    S: Send + Sync,
+    F: FnOnce(&[u8]) -> Result<T, String>,
+{
+    let format = match req.headers().get(CONTENT_TYPE).map(Format::from_header) {
+        Some(Some(format)) => format,
+        _ => return Err(ValidationRejection::UnsupportedMediaType),
+    };
+    let body = Bytes::from_request(req, state)
+        .await
+        .map_err(|err| ValidationRejection::InvalidBody(err.body_text()))?;
+    let msg = match (format, json) {
+        (Format::Json, Some(json)) => json(&body).map_err(ValidationRejection::InvalidBody)?,
+        (Format::Json, None) => return Err(ValidationRejection::UnsupportedMediaType),
+        (Format::Protobuf, _) => {
+            T::decode(body).map_err(|err| ValidationRejection::InvalidBody(err.to_string()))?
+        }
+    };
+    msg.validate_all().map_err(|errs| {
+        let errs = match format {
+            // the JSON bodies name their fields with their JSON names
+            Format::Json => errs
+                .into_iter()
+                .map(|err| {
+                    let path = err.path.to_json_names();
+                    err.with_path(path)
+                })
+                .collect(),
+            Format::Protobuf => errs,
+        };
+        ValidationRejection::Invalid(errs)
+    })?;
+    Ok(msg)
+}
+
+/// The body formats supported by the [`Validated`] extractor.
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Protobuf,
+}
+
+impl Format {
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let mime = value.split(';').next()?.trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "application/json" => Some(Self::Json),
+            "application/protobuf" | "application/x-protobuf" => Some(Self::Protobuf),
+            _ if mime.starts_with("application/") && mime.ends_with("+json") => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The rejection of the [`Validated`] extractor.
+#[derive(Debug)]
+pub enum ValidationRejection {
+    /// The request content type is not supported by the extractor.
+    UnsupportedMediaType,
+    /// The request body could not be read or decoded.
+    InvalidBody(String),
+    /// The decoded message is invalid.
+    Invalid(Violations),
+}
+
+impl ValidationRejection {
+    /// Returns the status code of the rejection response.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::InvalidBody(_) | Self::Invalid(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Returns the `application/problem+json` body of the rejection response.
+    pub fn problem(&self) -> serde_json::Value {
+        let status = self.status();
+        let mut problem = serde_json::json!({
+            "type": "about:blank",
+            "title": status.canonical_reason().unwrap_or_default(),
+            "status": status.as_u16(),
+        });
+        let detail = match self {
+            Self::UnsupportedMediaType => "unsupported request body content type".to_string(),
+            Self::InvalidBody(err) => err.clone(),
+            Self::Invalid(errs) => {
+                problem["violations"] = errs
+                    .iter()
+                    .map(|err| {
+                        serde_json::json!({
+                            "field": err.path.to_string(),
+                            "rule": err.rule_id(),
+                            "message": err.details.to_string(),
+                        })
+                    })
+                    .collect();
+                errs.to_string()
+            }
+        };
+        problem["detail"] = detail.into();
+        problem
+    }
+}
+
+impl IntoResponse for ValidationRejection {
+    fn into_response(self) -> Response {
+        let mut res = Response::new(Body::from(self.problem().to_string()));
+        *res.status_mut() = self.status();
+        let _ = res
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::string;
+    use crate::Error;
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, prost::Message, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Msg {
+        #[prost(string, tag = "1")]
+        user_name: String,
+    }
+
+    impl Validator for Msg {
+        fn validate(&self) -> crate::Result {
+            if self.user_name.chars().count() < 3 {
+                return Err(Error::new("pkg.Msg.user_name", string::Error::MinLen(3)));
+            }
+            Ok(())
+        }
+    }
+
+    /// A message without `serde` implementation.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct ProtoMsg {
+        #[prost(string, tag = "1")]
+        user_name: String,
+    }
+
+    impl Validator for ProtoMsg {
+        fn validate(&self) -> crate::Result {
+            if self.user_name.chars().count() < 3 {
+                return Err(Error::new("pkg.Msg.user_name", string::Error::MinLen(3)));
+            }
+            Ok(())
+        }
+    }
+
+    fn request(content_type: &str, body: impl Into<Body>) -> Request {
+        let mut req = Request::new(body.into());
+        let _ = req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_str(content_type).unwrap_or(HeaderValue::from_static("")),
+        );
+        req
+    }
+
+    async fn extract(req: Request) -> Result<Msg, ValidationRejection> {
+        Validated::<Msg>::from_request(req, &())
+            .await
+            .map(Validated::into_inner)
+    }
+
+    async fn extract_proto(req: Request) -> Result<ProtoMsg, ValidationRejection> {
+        ValidatedProto::<ProtoMsg>::from_request(req, &())
+            .await
+            .map(ValidatedProto::into_inner)
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_extract() {
+        let msg = extract(request("application/json", r#"{"userName":"foo"}"#))
+            .await
+            .unwrap();
+        assert_eq!(msg.user_name, "foo");
+
+        let body = Msg {
+            user_name: "bar".to_string(),
+        }
+        .encode_to_vec();
+        let msg = extract(request("application/x-protobuf", body))
+            .await
+            .unwrap();
+        assert_eq!(msg.user_name, "bar");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_reject() {
+        let err = extract(request("text/plain", "foo")).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let err = extract(request("application/json", "{")).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        assert!(matches!(err, ValidationRejection::InvalidBody(_)));
+
+        let err = extract(request(
+            "application/json; charset=utf-8",
+            r#"{"userName":"f"}"#,
+        ))
+        .await
+        .unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+        let problem = err.problem();
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["violations"][0]["field"], "userName");
+        assert_eq!(problem["violations"][0]["rule"], "string.min_len");
+
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), PROBLEM_JSON);
+
+        // the protobuf bodies keep the proto names of the fields
+        let body = Msg {
+            user_name: "f".to_string(),
+        }
+        .encode_to_vec();
+        let err = extract(request("application/protobuf", body))
+            .await
+            .unwrap_err();
+        assert_eq!(err.problem()["violations"][0]["field"], "user_name");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_extract_proto() {
+        let body = ProtoMsg {
+            user_name: "foo".to_string(),
+        }
+        .encode_to_vec();
+        let msg = extract_proto(request("application/protobuf", body))
+            .await
+            .unwrap();
+        assert_eq!(msg.user_name, "foo");
+
+        let err = extract_proto(request("application/json", r#"{"userName":"foo"}"#))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let body = ProtoMsg {
+            user_name: "f".to_string(),
+        }
+        .encode_to_vec();
+        let err = extract_proto(request("application/x-protobuf", body))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ValidationRejection::Invalid(_)));
+    }
+}
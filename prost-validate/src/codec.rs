@@ -0,0 +1,152 @@
+//! `tonic` codecs validating the messages.
+//!
+//! The [`ValidatingCodec`] decodes the messages as the `tonic_prost::ProstCodec` does,
+//! and rejects the invalid ones with an `InvalidArgument` status carrying the `BadRequest` details.
+//! Unlike the `tower` layer, it validates each message of the streaming requests too.
+//!
+//! The [`ResponseValidatingCodec`] also validates the encoded messages, i.e. the responses of a server,
+//! which helps catching server bugs in staging: an invalid response fails the call with an `Internal` status.
+//! `tonic` reports the errors of the encoders with their message only, without the `BadRequest` details.
+//!
+//! They are used by the validated servers generated by `prost-validate-build` with its `tonic` feature,
+//! and can be used with other `tonic` generators through their `codec_path` option:
+//!
+//! ```ignore
+//! tonic_prost_build::configure().codec_path("::prost_validate::codec::ValidatingCodec");
+//! ```
+
+use crate::{Validator, Violations};
+use prost::Message;
+use tonic::codec::{BufferSettings, Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::Status;
+use tonic_prost::ProstCodec;
+
+/// A [`Codec`] encoding and decoding the messages with prost, and validating the decoded messages.
+#[derive(Debug, Clone)]
+pub struct ValidatingCodec<T, U> {
+    inner: ProstCodec<T, U>,
+}
+
+impl<T, U> Default for ValidatingCodec<T, U> {
+    fn default() -> Self {
+        Self {
+            inner: ProstCodec::default(),
+        }
+    }
+}
+
+impl<T, U> Codec for ValidatingCodec<T, U>
+where
+    T: Message + Send + 'static,
+    U: Message + Default + Validator + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+    type Encoder = <ProstCodec<T, U> as Codec>::Encoder;
+    type Decoder = ValidatingDecoder<<ProstCodec<T, U> as Codec>::Decoder>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.inner.encoder()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ValidatingDecoder {
+            inner: self.inner.decoder(),
+        }
+    }
+}
+
+/// A [`Codec`] validating the encoded messages too, e.g. the responses of a server.
+#[derive(Debug, Clone)]
+pub struct ResponseValidatingCodec<T, U> {
+    inner: ValidatingCodec<T, U>,
+}
+
+impl<T, U> Default for ResponseValidatingCodec<T, U> {
+    fn default() -> Self {
+        Self {
+            inner: ValidatingCodec::default(),
+        }
+    }
+}
+
+impl<T, U> Codec for ResponseValidatingCodec<T, U>
+where
+    T: Message + Validator + Send + 'static,
+    U: Message + Default + Validator + 'static,
+{
+    type Encode = T;
+    type Decode = U;
+    type Encoder = ValidatingEncoder<<ValidatingCodec<T, U> as Codec>::Encoder>;
+    type Decoder = <ValidatingCodec<T, U> as Codec>::Decoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ValidatingEncoder {
+            inner: self.inner.encoder(),
+        }
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.inner.decoder()
+    }
+}
+
+/// The [`Decoder`] of the [`ValidatingCodec`].
+#[derive(Debug, Clone)]
+pub struct ValidatingDecoder<D> {
+    inner: D,
+}
+
+impl<D> Decoder for ValidatingDecoder<D>
+where
+    D: Decoder<Error = Status>,
+    D::Item: Validator,
+{
+    type Item = D::Item;
+    type Error = Status;
+
+    fn decode(&mut self, buf: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        let item = self.inner.decode(buf)?;
+        if let Some(item) = &item {
+            item.validate_all()?;
+        }
+        Ok(item)
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        self.inner.buffer_settings()
+    }
+}
+
+/// The [`Encoder`] of the [`ResponseValidatingCodec`].
+#[derive(Debug, Clone)]
+pub struct ValidatingEncoder<E> {
+    inner: E,
+}
+
+impl<E> Encoder for ValidatingEncoder<E>
+where
+    E: Encoder<Error = Status>,
+    E::Item: Validator,
+{
+    type Item = E::Item;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.validate_all().map_err(invalid_response)?;
+        self.inner.encode(item, dst)
+    }
+
+    fn buffer_settings(&self) -> BufferSettings {
+        self.inner.buffer_settings()
+    }
+}
+
+/// Returns the `Internal` status reported for an invalid response.
+pub(crate) fn invalid_response(errs: Violations) -> Status {
+    <Status as tonic_types::StatusExt>::with_error_details(
+        tonic::Code::Internal,
+        format!("invalid response: {errs}"),
+        errs.into(),
+    )
+}
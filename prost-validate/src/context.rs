@@ -0,0 +1,81 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+/// A source of the current time, used by the time-relative timestamp rules
+/// (`lt_now`, `gt_now` and `within`).
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// A fixed point in time is a clock that never moves.
+impl Clock for OffsetDateTime {
+    fn now(&self) -> OffsetDateTime {
+        *self
+    }
+}
+
+/// The context in which a value is validated.
+///
+/// The default context uses the [`SystemClock`].
+#[derive(Clone, Default)]
+pub struct Context {
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl Context {
+    /// Creates a new context using the system clock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the context using the given clock.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Some(Arc::new(clock)),
+        }
+    }
+
+    /// Returns the context evaluating the time-relative rules against `now`,
+    /// e.g. the logical time of a request.
+    pub fn with_now(self, now: OffsetDateTime) -> Self {
+        self.with_clock(now)
+    }
+
+    /// Returns the current time according to the context's clock.
+    pub fn now(&self) -> OffsetDateTime {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+}
+
+impl Debug for Context {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(Context::new().with_now(now).now(), now);
+        assert!(Context::new().now() > now);
+    }
+}
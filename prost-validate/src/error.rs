@@ -1,29 +1,219 @@
-use crate::errors;
+use crate::errors::{list, map, message};
+use crate::{errors, FieldPath, PathKey, PathSegment};
 
 /// Represents a validation error for a field.
 #[derive(Debug, Clone)]
 pub struct Error {
     /// The field associated with the error.
     pub field: String,
+    /// The path to the field from the root of the validated message.
+    pub path: FieldPath,
     /// The error message.
     pub details: errors::Error,
+    /// Where the rule that failed was defined.
+    pub origin: RuleOrigin,
+}
+
+/// Where a validation rule was defined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RuleOrigin {
+    /// The rules annotated on the field in the proto files.
+    #[default]
+    Proto,
+    /// An override of the annotated rules, loaded at runtime by the reflection backend.
+    Override,
 }
 
 impl Error {
     /// Creates a new `Error` instance.
+    ///
+    /// The path of the error is the name of the field, followed by the path
+    /// of the nested error for embedded messages.
     #[allow(clippy::needless_pass_by_value)]
     pub fn new<T: ToString>(field: T, details: impl Into<errors::Error>) -> Self {
+        let field = field.to_string();
+        let details = details.into();
+        let name = PathSegment::Field(field_name(&field).to_string());
+        let (path, origin) = match &details {
+            errors::Error::Message(message::Error::Message(err)) => {
+                (err.path.clone().prefixed([name]), err.origin)
+            }
+            _ => (FieldPath::from(vec![name]), RuleOrigin::Proto),
+        };
+        Self {
+            field,
+            path,
+            details,
+            origin,
+        }
+    }
+
+    /// Creates an error for the item at `index` of the repeated field `field`
+    /// from the error reported for the item.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn list_item<T: ToString>(field: T, index: usize, err: Error) -> Self {
+        let field = field.to_string();
+        let path = err.path.clone().tail().prefixed([
+            PathSegment::Field(field_name(&field).to_string()),
+            PathSegment::Index(index),
+        ]);
         Self {
-            field: field.to_string(),
-            details: details.into(),
+            field: format!("{field}[{index}]"),
+            path,
+            origin: err.origin,
+            details: list::Error::Item(Box::new(err)).into(),
         }
     }
+
+    /// Creates an error for the `key` of the map field `field`
+    /// from the error reported for the key.
+    pub fn map_key<T: ToString>(field: T, key: impl Into<PathKey>, err: Error) -> Self {
+        let (field, path) = map_entry(field, key.into(), &err);
+        Self {
+            field,
+            path,
+            origin: err.origin,
+            details: map::Error::Keys(Box::new(err)).into(),
+        }
+    }
+
+    /// Creates an error for the value at `key` of the map field `field`
+    /// from the error reported for the value.
+    pub fn map_value<T: ToString>(field: T, key: impl Into<PathKey>, err: Error) -> Self {
+        let (field, path) = map_entry(field, key.into(), &err);
+        Self {
+            field,
+            path,
+            origin: err.origin,
+            details: map::Error::Values(Box::new(err)).into(),
+        }
+    }
+
+    /// Returns the stable identifier of the rule that failed, e.g. `string.min_len`,
+    /// `timestamp.gt_now` or `message.required`.
+    ///
+    /// For errors reported on repeated items, map entries or embedded messages,
+    /// this is the identifier of the rule that failed on the nested value.
+    /// For CEL expression rules, this is the `id` of the rule.
+    pub fn rule_id(&self) -> &str {
+        self.details.rule_id()
+    }
+
+    /// Returns the error with the given path.
+    pub fn with_path(self, path: FieldPath) -> Self {
+        Self { path, ..self }
+    }
+
+    /// Returns the error with the given rule origin.
+    pub fn with_origin(self, origin: RuleOrigin) -> Self {
+        Self { origin, ..self }
+    }
+}
+
+/// Returns the name of the field from its full name.
+fn field_name(field: &str) -> &str {
+    field.rsplit('.').next().unwrap_or(field)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn map_entry<T: ToString>(field: T, key: PathKey, err: &Error) -> (String, FieldPath) {
+    let field = field.to_string();
+    let name = format!("{field}[{key}]");
+    let path = err.path.clone().tail().prefixed([
+        PathSegment::Field(field_name(&field).to_string()),
+        PathSegment::Key(key),
+    ]);
+    (name, path)
 }
 
 impl std::fmt::Display for Error {
     /// Formats the error for display.
+    ///
+    /// The errors of the rules defined by an override are suffixed with `(override)`.
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "\"{}\": {}", self.field, self.details)
+        write!(f, "\"{}\": {}", self.field, self.details)?;
+        if self.origin == RuleOrigin::Override {
+            write!(f, " (override)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The domain used in the `ErrorInfo` details of the `tonic::Status` built from validation errors.
+#[cfg(feature = "tonic")]
+pub const ERROR_DOMAIN: &str = "prost-validate";
+
+/// Represents all the validation errors collected for a value.
+#[derive(Debug, Clone, Default)]
+pub struct Violations(Vec<Error>);
+
+impl Violations {
+    /// Returns the collected errors.
+    pub fn into_inner(self) -> Vec<Error> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Violations {
+    type Target = [Error];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Error> for Violations {
+    fn from(value: Error) -> Self {
+        Self(vec![value])
+    }
+}
+
+impl From<Vec<Error>> for Violations {
+    fn from(value: Vec<Error>) -> Self {
+        Self(value)
+    }
+}
+
+impl FromIterator<Error> for Violations {
+    fn from_iter<I: IntoIterator<Item = Error>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Error> for Violations {
+    fn extend<I: IntoIterator<Item = Error>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for Violations {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Violations {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl std::fmt::Display for Violations {
+    /// Formats the errors for display, separated by `; `.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{err}")?;
+        }
+        Ok(())
     }
 }
 
@@ -32,8 +222,9 @@ impl From<Error> for tonic_types::FieldViolation {
     /// Converts an `Error` into a `FieldViolation`.
     fn from(value: Error) -> Self {
         Self {
-            field: value.field,
+            field: value.path.to_string(),
             description: value.details.to_string(),
+            reason: value.rule_id().to_string(),
             ..tonic_types::FieldViolation::default()
         }
     }
@@ -43,7 +234,7 @@ impl From<Error> for tonic_types::FieldViolation {
 impl From<Error> for tonic_types::ErrorDetails {
     /// Converts an `Error` into `ErrorDetails`.
     fn from(value: Error) -> Self {
-        tonic_types::ErrorDetails::with_bad_request(vec![value.into()])
+        Violations::from(value).into()
     }
 }
 
@@ -63,6 +254,55 @@ impl From<Error> for tonic::Status {
     }
 }
 
+#[cfg(feature = "tonic")]
+impl From<Violations> for tonic_types::ErrorDetails {
+    /// Converts `Violations` into `ErrorDetails` with one field violation per error.
+    ///
+    /// The rule identifiers are also reported in the `ErrorInfo` details:
+    /// its reason is the rule identifier of the first error and its metadata
+    /// maps the path of each invalid field to the identifier of the rule that failed.
+    fn from(value: Violations) -> Self {
+        let mut details = tonic_types::ErrorDetails::new();
+        if let Some(err) = value.first() {
+            details.set_error_info(
+                err.rule_id(),
+                ERROR_DOMAIN,
+                value
+                    .iter()
+                    .map(|e| (e.path.to_string(), e.rule_id().to_string()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+            );
+        }
+        details.set_bad_request(
+            value
+                .into_iter()
+                .map(tonic_types::FieldViolation::from)
+                .collect::<Vec<_>>(),
+        );
+        details
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl From<Violations> for tonic::Status {
+    /// Converts `Violations` into a `tonic::Status`.
+    fn from(value: Violations) -> Self {
+        let code = if value
+            .iter()
+            .any(|v| matches!(v.details, errors::Error::InvalidRules(_)))
+        {
+            tonic::Code::Internal
+        } else {
+            tonic::Code::InvalidArgument
+        };
+        <tonic::Status as tonic_types::StatusExt>::with_error_details(
+            code,
+            value.to_string(),
+            value.into(),
+        )
+    }
+}
+
 /// Macro to format an error.
 ///
 /// # Arguments
@@ -77,22 +317,22 @@ impl From<Error> for tonic::Status {
 #[macro_export]
 macro_rules! format_err {
     ($msg:literal $(,)?) => {
-        ::prost_validate::Error {
-            field: "".to_string(),
-            details: ::prost_validate::errors::Error::InvalidRules(format!("{}", $msg)),
-        }
+        ::prost_validate::Error::new(
+            "",
+            ::prost_validate::errors::Error::InvalidRules(format!("{}", $msg)),
+        )
     };
     ($field:ident, $msg:ident) => {
-        ::prost_validate::Error {
-            field: format!("{}", $field),
-            details: ::prost_validate::errors::Error::InvalidRules(format!("{}", $msg)),
-        }
+        ::prost_validate::Error::new(
+            format!("{}", $field),
+            ::prost_validate::errors::Error::InvalidRules(format!("{}", $msg)),
+        )
     };
     ($field:expr, $($arg:tt)*) => {
-        ::prost_validate::Error {
-            field: format!("{}", $field),
-            details: ::prost_validate::errors::Error::InvalidRules(format!($($arg)*)),
-        }
+        ::prost_validate::Error::new(
+            format!("{}", $field),
+            ::prost_validate::errors::Error::InvalidRules(format!($($arg)*)),
+        )
     };
 }
 
@@ -137,5 +377,81 @@ mod tests {
         assert_eq!(f.len(), 1);
         assert_eq!(f[0].field, "field");
         assert_eq!(f[0].description, "required");
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "message.required");
+        assert_eq!(info.domain, crate::ERROR_DOMAIN);
+        assert_eq!(info.metadata.get("field").unwrap(), "message.required");
+
+        let f: tonic_types::FieldViolation = Error::new("field", message::Error::Required).into();
+        assert_eq!(f.reason, "message.required");
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_status_violations() {
+        use crate::errors::{message, string};
+        use crate::{Error, Violations};
+        use tonic_types::StatusExt;
+
+        let violations = Violations::from(vec![
+            Error::new("a", message::Error::Required),
+            Error::new("b", string::Error::MinLen(3)),
+        ]);
+        let status: tonic::Status = violations.into();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(
+            status.message(),
+            "\"a\": required; \"b\": characters length must be greater than or equal to 3"
+        );
+        let details = status.get_error_details();
+        let f = &details.bad_request().unwrap().field_violations;
+        assert_eq!(f.len(), 2);
+        assert_eq!(f[0].field, "a");
+        assert_eq!(f[1].field, "b");
+        assert_eq!(
+            f[1].description,
+            "characters length must be greater than or equal to 3"
+        );
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "message.required");
+        assert_eq!(info.metadata.get("a").unwrap(), "message.required");
+        assert_eq!(info.metadata.get("b").unwrap(), "string.min_len");
+    }
+
+    #[test]
+    fn test_rule_id() {
+        use crate::errors::{cel, int32, list, map, message, string, timestamp};
+        use crate::Error;
+
+        let err = Error::new("a", string::Error::MinLen(3));
+        assert_eq!(err.rule_id(), "string.min_len");
+        let err = Error::new("a", timestamp::Error::GtNow);
+        assert_eq!(err.rule_id(), "timestamp.gt_now");
+        let err = Error::new("a", int32::Error::in_range(false, 1, 10, true));
+        assert_eq!(err.rule_id(), "int32.gt_lte");
+        let err = Error::new("a", int32::Error::not_in_range(true, 1, 10, false));
+        assert_eq!(err.rule_id(), "int32.gte_lt_exclusive");
+        let err = Error::new("a", list::Error::MinItems(1));
+        assert_eq!(err.rule_id(), "repeated.min_items");
+        let err = Error::new("a", map::Error::NoSparse);
+        assert_eq!(err.rule_id(), "map.no_sparse");
+        let err = Error::new(
+            "a",
+            cel::Error::Expression {
+                id: "a.valid".to_string(),
+                message: "a must be valid".to_string(),
+            },
+        );
+        assert_eq!(err.rule_id(), "a.valid");
+        assert_eq!(err.to_string(), "\"a\": a must be valid");
+
+        let nested = Error::new("b", string::Error::Email);
+        let err = Error::list_item("a", 0, nested.clone());
+        assert_eq!(err.rule_id(), "string.email");
+        let err = Error::map_value("a", "k", nested.clone());
+        assert_eq!(err.rule_id(), "string.email");
+        let err = Error::new("a", message::Error::Message(Box::new(nested)));
+        assert_eq!(err.rule_id(), "string.email");
     }
 }
@@ -10,6 +10,17 @@ pub enum Error {
     NotIn(Vec<String>),
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `any.required`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Required => "any.required",
+            Self::In(_) => "any.in",
+            Self::NotIn(_) => "any.not_in",
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Any(value)
@@ -6,6 +6,15 @@ pub enum Error {
     Const(bool),
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `bool.const`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "bool.const",
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Bool(value)
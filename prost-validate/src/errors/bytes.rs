@@ -30,6 +30,27 @@ pub enum Error {
     Ipv6,
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `bytes.const`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "bytes.const",
+            Self::Len(_) => "bytes.len",
+            Self::MinLen(_) => "bytes.min_len",
+            Self::MaxLen(_) => "bytes.max_len",
+            Self::Pattern(_) => "bytes.pattern",
+            Self::Prefix(_) => "bytes.prefix",
+            Self::Suffix(_) => "bytes.suffix",
+            Self::Contains(_) => "bytes.contains",
+            Self::In(_) => "bytes.in",
+            Self::NotIn(_) => "bytes.not_in",
+            Self::Ip => "bytes.ip",
+            Self::Ipv4 => "bytes.ipv4",
+            Self::Ipv6 => "bytes.ipv6",
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Bytes(value)
@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    /// A CEL expression rule failed, e.g. a protovalidate `(buf.validate.field).cel` rule.
+    #[error("{message}")]
+    Expression { id: String, message: String },
+}
+
+impl Error {
+    /// Returns the identifier of the expression rule that failed.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Expression { id, .. } => id,
+        }
+    }
+}
+
+impl From<Error> for super::Error {
+    fn from(value: Error) -> Self {
+        Self::Cel(value)
+    }
+}
@@ -49,6 +49,23 @@ impl Error {
             if end_inclusive { "]" } else { ")" }.to_string(),
         )
     }
+
+    /// Returns the stable identifier of the rule that failed, e.g. `duration.lt`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "duration.const",
+            Self::Lt(_) => "duration.lt",
+            Self::Lte(_) => "duration.lte",
+            Self::Gt(_) => "duration.gt",
+            Self::Gte(_) => "duration.gte",
+            Self::In(_) => "duration.in",
+            Self::NotIn(_) => "duration.not_in",
+            Self::InRange(start, _, _, end) => range_rule_id!(duration, start, end),
+            Self::NotInRange(start, _, _, end) => {
+                range_rule_id!(duration, start, end, "_exclusive")
+            }
+        }
+    }
 }
 
 impl From<Error> for super::Error {
@@ -12,6 +12,18 @@ pub enum Error {
     NotIn(Vec<i32>),
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `enum.const`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "enum.const",
+            Self::DefinedOnly => "enum.defined_only",
+            Self::In(_) => "enum.in",
+            Self::NotIn(_) => "enum.not_in",
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Enum(value)
@@ -12,6 +12,18 @@ pub enum Error {
     Item(Box<crate::Error>),
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `repeated.min_items`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::MinItems(_) => "repeated.min_items",
+            Self::MaxItems(_) => "repeated.max_items",
+            Self::Unique => "repeated.unique",
+            Self::Item(err) => err.rule_id(),
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::List(value)
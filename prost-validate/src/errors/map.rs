@@ -10,10 +10,22 @@ pub enum Error {
     Keys(Box<crate::Error>),
     #[error("value: {0}")]
     Values(Box<crate::Error>),
-    #[error("must no have sparse values")]
+    #[error("must not have sparse values")]
     NoSparse,
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `map.min_pairs`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::MinPairs(_) => "map.min_pairs",
+            Self::MaxPairs(_) => "map.max_pairs",
+            Self::NoSparse => "map.no_sparse",
+            Self::Keys(err) | Self::Values(err) => err.rule_id(),
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Map(value)
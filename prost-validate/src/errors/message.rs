@@ -8,6 +8,16 @@ pub enum Error {
     Message(Box<crate::Error>),
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `message.required`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Required => "message.required",
+            Self::Message(err) => err.rule_id(),
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::Message(value)
@@ -1,11 +1,25 @@
 use crate::make_error;
 use thiserror::Error;
 
+/// Returns the rule identifier of a range error from its bounds delimiters,
+/// e.g. `int32.gt_lte` for `(start, end]`.
+macro_rules! range_rule_id {
+    ($name:ident, $start:expr, $end:expr $(, $suffix:literal)?) => {
+        match ($start.as_str(), $end.as_str()) {
+            ("[", "]") => concat!(stringify!($name), ".gte_lte" $(, $suffix)?),
+            ("[", _) => concat!(stringify!($name), ".gte_lt" $(, $suffix)?),
+            (_, "]") => concat!(stringify!($name), ".gt_lte" $(, $suffix)?),
+            _ => concat!(stringify!($name), ".gt_lt" $(, $suffix)?),
+        }
+    };
+}
+
 mod number;
 
 pub mod any;
 pub mod bool;
 pub mod bytes;
+pub mod cel;
 pub mod duration;
 pub mod r#enum;
 pub mod list;
@@ -18,6 +32,9 @@ pub mod timestamp;
 pub enum Error {
     #[error("invalid validation rules: {0}")]
     InvalidRules(String),
+    /// The message could not be decoded, e.g. when validating raw wire bytes.
+    #[error("invalid message encoding: {0}")]
+    Decode(String),
     #[error(transparent)]
     Bool(bool::Error),
     #[error(transparent)]
@@ -62,6 +79,8 @@ pub enum Error {
     Any(any::Error),
     #[error(transparent)]
     Enum(r#enum::Error),
+    #[error(transparent)]
+    Cel(cel::Error),
 }
 
 make_error!(float, f32, Float);
@@ -77,6 +96,42 @@ make_error!(fixed64, u64, Fixed64);
 make_error!(sfixed32, i32, Sfixed32);
 make_error!(sfixed64, i64, Sfixed64);
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `string.min_len`.
+    ///
+    /// Errors reported for repeated items, map keys and values or embedded messages
+    /// return the identifier of the nested error, and CEL expression rules their own `id`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::InvalidRules(_) => "invalid_rules",
+            Self::Decode(_) => "decode",
+            Self::Bool(err) => err.rule_id(),
+            Self::String(err) => err.rule_id(),
+            Self::Bytes(err) => err.rule_id(),
+            Self::Float(err) => err.rule_id(),
+            Self::Double(err) => err.rule_id(),
+            Self::Int32(err) => err.rule_id(),
+            Self::Int64(err) => err.rule_id(),
+            Self::Uint32(err) => err.rule_id(),
+            Self::Uint64(err) => err.rule_id(),
+            Self::Sint32(err) => err.rule_id(),
+            Self::Sint64(err) => err.rule_id(),
+            Self::Fixed32(err) => err.rule_id(),
+            Self::Fixed64(err) => err.rule_id(),
+            Self::Sfixed32(err) => err.rule_id(),
+            Self::Sfixed64(err) => err.rule_id(),
+            Self::List(err) => err.rule_id(),
+            Self::Map(err) => err.rule_id(),
+            Self::Duration(err) => err.rule_id(),
+            Self::Timestamp(err) => err.rule_id(),
+            Self::Message(err) => err.rule_id(),
+            Self::Any(err) => err.rule_id(),
+            Self::Enum(err) => err.rule_id(),
+            Self::Cel(err) => err.rule_id(),
+        }
+    }
+}
+
 // TODO(adphi): remove when not necessary anymore
 impl From<&str> for Error {
     /// Converts a string into an `Error`.
@@ -53,6 +53,23 @@ macro_rules! make_error {
                         if end_inclusive { "]" } else { ")" }.to_string(),
                     )
                 }
+
+                /// Returns the stable identifier of the rule that failed, e.g. `int32.lt`.
+                pub fn rule_id(&self) -> &str {
+                    match self {
+                        Self::Const(_) => concat!(stringify!($name), ".const"),
+                        Self::Lt(_) => concat!(stringify!($name), ".lt"),
+                        Self::Lte(_) => concat!(stringify!($name), ".lte"),
+                        Self::Gt(_) => concat!(stringify!($name), ".gt"),
+                        Self::Gte(_) => concat!(stringify!($name), ".gte"),
+                        Self::InRange(start, _, _, end) => range_rule_id!($name, start, end),
+                        Self::NotInRange(start, _, _, end) => {
+                            range_rule_id!($name, start, end, "_exclusive")
+                        }
+                        Self::In(_) => concat!(stringify!($name), ".in"),
+                        Self::NotIn(_) => concat!(stringify!($name), ".not_in"),
+                    }
+                }
             }
             impl From<Error> for $crate::errors::Error {
                 fn from(value: Error) -> Self {
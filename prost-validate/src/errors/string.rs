@@ -54,6 +54,39 @@ pub enum Error {
     HttpHeaderValue,
 }
 
+impl Error {
+    /// Returns the stable identifier of the rule that failed, e.g. `string.const`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "string.const",
+            Self::Len(_) => "string.len",
+            Self::MinLen(_) => "string.min_len",
+            Self::MaxLen(_) => "string.max_len",
+            Self::LenBytes(_) => "string.len_bytes",
+            Self::MinLenBytes(_) => "string.min_bytes",
+            Self::MaxLenBytes(_) => "string.max_bytes",
+            Self::Pattern(_) => "string.pattern",
+            Self::Prefix(_) => "string.prefix",
+            Self::Suffix(_) => "string.suffix",
+            Self::Contains(_) => "string.contains",
+            Self::NotContains(_) => "string.not_contains",
+            Self::In(_) => "string.in",
+            Self::NotIn(_) => "string.not_in",
+            Self::Email => "string.email",
+            Self::Hostname => "string.hostname",
+            Self::Ip => "string.ip",
+            Self::Ipv4 => "string.ipv4",
+            Self::Ipv6 => "string.ipv6",
+            Self::Uri => "string.uri",
+            Self::UriRef => "string.uri_ref",
+            Self::Address => "string.address",
+            Self::Uuid => "string.uuid",
+            Self::HttpHeaderName => "string.well_known_regex.http_header_name",
+            Self::HttpHeaderValue => "string.well_known_regex.http_header_value",
+        }
+    }
+}
+
 impl From<Error> for super::Error {
     fn from(value: Error) -> Self {
         Self::String(value)
@@ -55,6 +55,26 @@ impl Error {
             if end_inclusive { "]" } else { ")" }.to_string(),
         )
     }
+
+    /// Returns the stable identifier of the rule that failed, e.g. `timestamp.lt`.
+    pub fn rule_id(&self) -> &str {
+        match self {
+            Self::Const(_) => "timestamp.const",
+            Self::Lt(_) => "timestamp.lt",
+            Self::Lte(_) => "timestamp.lte",
+            Self::Gt(_) => "timestamp.gt",
+            Self::Gte(_) => "timestamp.gte",
+            Self::LtNow => "timestamp.lt_now",
+            Self::LtNowWithin(_) => "timestamp.lt_now_within",
+            Self::GtNow => "timestamp.gt_now",
+            Self::GtNowWithin(_) => "timestamp.gt_now_within",
+            Self::Within(_) => "timestamp.within",
+            Self::InRange(start, _, _, end) => range_rule_id!(timestamp, start, end),
+            Self::NotInRange(start, _, _, end) => {
+                range_rule_id!(timestamp, start, end, "_exclusive")
+            }
+        }
+    }
 }
 
 impl From<Error> for super::Error {
@@ -1,13 +1,23 @@
+#[cfg(feature = "axum")]
+pub mod axum;
 mod bytes;
+#[cfg(feature = "tonic")]
+pub mod codec;
+mod context;
 mod error;
 pub mod errors;
+mod path;
 mod string;
+#[cfg(feature = "tower")]
+pub mod tower;
 #[doc(hidden)]
 pub mod utils;
 
 #[doc(hidden)]
 pub use bytes::ValidateBytesExt;
+pub use context::*;
 pub use error::*;
+pub use path::*;
 #[doc(hidden)]
 pub use string::ValidateStringExt;
 #[doc(hidden)]
@@ -25,6 +35,40 @@ pub trait Validator: Send + Sync {
     fn validate(&self) -> Result {
         Ok(())
     }
+
+    /// Validates the value in the given context,
+    /// e.g. using a custom clock for the time-relative rules.
+    fn validate_with(&self, ctx: &Context) -> Result {
+        let _ = ctx;
+        self.validate()
+    }
+
+    /// Validates the value without stopping at the first error,
+    /// returning all the violations found.
+    fn validate_all(&self) -> Result<(), Violations> {
+        self.validate_all_with(&Context::default())
+    }
+
+    /// Validates the value in the given context without stopping at the first error,
+    /// returning all the violations found.
+    fn validate_all_with(&self, ctx: &Context) -> Result<(), Violations> {
+        self.validate_with(ctx).map_err(Violations::from)
+    }
+
+    /// Validates only the field named `field`, e.g. `"name"` for the `name` field of a message,
+    /// running its rules without those of the other fields or the CEL expressions of the message.
+    /// The fields of a oneof are named after their variant, e.g. `"email"` for `Contact::Email`.
+    ///
+    /// The derived implementations return an error for an unknown field.
+    fn validate_field(&self, field: &str) -> Result {
+        self.validate_field_with(field, &Context::default())
+    }
+
+    /// Validates only the field named `field` in the given context.
+    fn validate_field_with(&self, field: &str, ctx: &Context) -> Result {
+        let _ = (field, ctx);
+        Ok(())
+    }
 }
 
 // NoopValidator is the same trait as `Validator`.
@@ -34,6 +78,18 @@ pub trait NoopValidator {
     fn validate(&self) -> Result {
         Ok(())
     }
+
+    fn validate_with(&self, _: &Context) -> Result {
+        Ok(())
+    }
+
+    fn validate_all(&self) -> Result<(), Violations> {
+        Ok(())
+    }
+
+    fn validate_all_with(&self, _: &Context) -> Result<(), Violations> {
+        Ok(())
+    }
 }
 
 // Implement `NoopValidator` for any type.
@@ -49,9 +105,21 @@ impl<T: ?Sized + Validator> SafeValidator<'_, T> {
     pub fn validate(&self) -> Result {
         Validator::validate(self.0)
     }
+
+    pub fn validate_with(&self, ctx: &Context) -> Result {
+        Validator::validate_with(self.0, ctx)
+    }
+
+    pub fn validate_all(&self) -> Result<(), Violations> {
+        Validator::validate_all(self.0)
+    }
+
+    pub fn validate_all_with(&self, ctx: &Context) -> Result<(), Violations> {
+        Validator::validate_all_with(self.0, ctx)
+    }
 }
 
-/// Validate any value if it implements the Validator trait.
+/// Validate any value if it implements the Validator trait, optionally in the given context.
 /// If the value does not implement the Validator trait, it will return Ok(()).
 #[macro_export]
 macro_rules! validate {
@@ -60,6 +128,28 @@ macro_rules! validate {
         use std::ops::Deref;
         ::prost_validate::SafeValidator($value.deref()).validate()
     }};
+    ($value:tt, $ctx:expr) => {{
+        use ::prost_validate::NoopValidator;
+        use std::ops::Deref;
+        ::prost_validate::SafeValidator($value.deref()).validate_with($ctx)
+    }};
+}
+
+/// Validate any value if it implements the Validator trait, collecting all the violations,
+/// optionally in the given context.
+/// If the value does not implement the Validator trait, it will return Ok(()).
+#[macro_export]
+macro_rules! validate_all {
+    ($value:tt) => {{
+        use ::prost_validate::NoopValidator;
+        use std::ops::Deref;
+        ::prost_validate::SafeValidator($value.deref()).validate_all()
+    }};
+    ($value:tt, $ctx:expr) => {{
+        use ::prost_validate::NoopValidator;
+        use std::ops::Deref;
+        ::prost_validate::SafeValidator($value.deref()).validate_all_with($ctx)
+    }};
 }
 
 #[cfg(test)]
@@ -97,4 +187,23 @@ mod tests {
         let c = &42;
         assert!(prost_validate::validate!(c).is_ok());
     }
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_validate_all() {
+        let a = &A {};
+        assert_eq!(prost_validate::validate_all!(a).unwrap_err().len(), 1);
+        let b = &B {};
+        assert!(prost_validate::validate_all!(b).is_ok());
+    }
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_validate_with() {
+        let ctx = prost_validate::Context::default();
+        let a = &A {};
+        assert!(prost_validate::validate!(a, &ctx).is_err());
+        assert_eq!(prost_validate::validate_all!(a, &ctx).unwrap_err().len(), 1);
+        let b = &B {};
+        assert!(prost_validate::validate!(b, &ctx).is_ok());
+        assert!(prost_validate::validate_all!(b, &ctx).is_ok());
+    }
 }
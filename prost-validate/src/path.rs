@@ -0,0 +1,225 @@
+use std::fmt::{Display, Formatter};
+
+/// The path to a field from the root of the validated message,
+/// e.g. `a.b[3].c["key"]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FieldPath(Vec<PathSegment>);
+
+/// A segment of a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A field name.
+    Field(String),
+    /// An index in a repeated field.
+    Index(usize),
+    /// A key in a map field.
+    Key(PathKey),
+}
+
+/// A map key in a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathKey {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    String(String),
+}
+
+impl FieldPath {
+    /// Creates a path made of the given field.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn field<T: ToString>(name: T) -> Self {
+        Self(vec![PathSegment::Field(name.to_string())])
+    }
+
+    /// Returns the segments of the path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Returns whether the path is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the path as a [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901), e.g. `/a/b/3/c/key`.
+    ///
+    /// The map keys are written unquoted, as the keys of the JSON objects.
+    pub fn to_json_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in &self.0 {
+            let segment = match segment {
+                PathSegment::Field(name) => name.clone(),
+                PathSegment::Index(index) => index.to_string(),
+                PathSegment::Key(PathKey::String(key)) => key.clone(),
+                PathSegment::Key(key) => key.to_string(),
+            };
+            pointer.push('/');
+            pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        }
+        pointer
+    }
+
+    /// Returns the path with the names of the fields converted to their JSON names,
+    /// e.g. `user_id[3].first_name` to `userId[3].firstName`.
+    ///
+    /// The names are converted to lowerCamelCase as `protoc` does by default: the custom `json_name`
+    /// of a field is not known from its path, and should be resolved from the descriptors instead.
+    pub fn to_json_names(&self) -> Self {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Field(name) => PathSegment::Field(json_name(name)),
+                segment => segment.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the path prefixed by `prefix`.
+    pub(crate) fn prefixed(self, prefix: impl IntoIterator<Item = PathSegment>) -> Self {
+        Self(prefix.into_iter().chain(self.0).collect())
+    }
+
+    /// Returns the path without its first segment.
+    pub(crate) fn tail(self) -> Self {
+        Self(self.0.into_iter().skip(1).collect())
+    }
+}
+
+/// Returns the default JSON name of a field: the underscores are removed,
+/// and the letters following them are capitalized.
+fn json_name(name: &str) -> String {
+    let mut json = String::with_capacity(name.len());
+    let mut capitalize = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            json.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            json.push(c);
+        }
+    }
+    json
+}
+
+impl From<Vec<PathSegment>> for FieldPath {
+    fn from(value: Vec<PathSegment>) -> Self {
+        Self(value)
+    }
+}
+
+impl FromIterator<PathSegment> for FieldPath {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for FieldPath {
+    type Item = PathSegment;
+    type IntoIter = std::vec::IntoIter<PathSegment>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Display for FieldPath {
+    /// Formats the path using its canonical representation, e.g. `a.b[3].c["key"]`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if i == 0 => write!(f, "{name}")?,
+                PathSegment::Field(name) => write!(f, ".{name}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Key(key) => write!(f, "[{key}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for PathKey {
+    /// Formats the key, quoting and escaping string keys.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathKey::Bool(v) => write!(f, "{v}"),
+            PathKey::Int(v) => write!(f, "{v}"),
+            PathKey::Uint(v) => write!(f, "{v}"),
+            PathKey::String(v) => write!(f, "{v:?}"),
+        }
+    }
+}
+
+macro_rules! path_key_from {
+    ($variant:ident, $target:ty, $($typ:ty),+) => {
+        $(
+            impl From<$typ> for PathKey {
+                fn from(value: $typ) -> Self {
+                    Self::$variant(<$target>::from(value))
+                }
+            }
+        )+
+    };
+}
+
+path_key_from!(Bool, bool, bool);
+path_key_from!(Int, i64, i32, i64);
+path_key_from!(Uint, u64, u32, u64);
+path_key_from!(String, String, String, &str);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let path = FieldPath::from(vec![
+            PathSegment::Field("a".to_string()),
+            PathSegment::Field("b".to_string()),
+            PathSegment::Index(3),
+            PathSegment::Field("c".to_string()),
+            PathSegment::Key("key".into()),
+        ]);
+        assert_eq!(path.to_string(), r#"a.b[3].c["key"]"#);
+
+        let path = FieldPath::field("m").prefixed([]);
+        assert_eq!(path.to_string(), "m");
+
+        let path = FieldPath::from(vec![
+            PathSegment::Field("m".to_string()),
+            PathSegment::Key(42u32.into()),
+            PathSegment::Key(true.into()),
+            PathSegment::Key("\"quoted\"".into()),
+        ]);
+        assert_eq!(path.to_string(), r#"m[42][true]["\"quoted\""]"#);
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        let path = FieldPath::from(vec![
+            PathSegment::Field("a".to_string()),
+            PathSegment::Index(3),
+            PathSegment::Field("c".to_string()),
+            PathSegment::Key("k/e~y".into()),
+            PathSegment::Key(42u32.into()),
+        ]);
+        assert_eq!(path.to_json_pointer(), "/a/3/c/k~1e~0y/42");
+        assert_eq!(FieldPath::default().to_json_pointer(), "");
+    }
+
+    #[test]
+    fn test_json_names() {
+        let path = FieldPath::from(vec![
+            PathSegment::Field("user_id".to_string()),
+            PathSegment::Index(3),
+            PathSegment::Field("first_name_2".to_string()),
+            PathSegment::Key("snake_key".into()),
+        ]);
+        assert_eq!(
+            path.to_json_names().to_string(),
+            r#"userId[3].firstName2["snake_key"]"#
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! A [`tower`](https://docs.rs/tower) middleware validating the messages of `tonic` services.
+//!
+//! `tonic` interceptors only have access to the request metadata, not to the decoded message.
+//! The [`ValidationLayer`] wraps services handling typed `tonic::Request<T>` and validates
+//! the request message before the inner service is called, rejecting invalid requests with an
+//! `InvalidArgument` status carrying the `BadRequest` details.
+//!
+//! The servers generated by `tonic` handle the encoded `http::Request`s, so the layer cannot wrap them.
+//! To validate every request of a generated server, streaming requests included, serve the validated server
+//! generated along with it by the `tonic` builder of `prost-validate-build`, e.g. `ValidatedExampleServiceServer`,
+//! which decodes the requests with the [`ValidatingCodec`](crate::codec::ValidatingCodec).
+//! With its `validate_responses(true)` option, the validated servers validate the responses too,
+//! with the [`ResponseValidatingCodec`](crate::codec::ResponseValidatingCodec).
+//!
+//! The layer wraps the other services handling typed requests, e.g. the handlers called by an implementation
+//! of a service trait. The responses can optionally be validated too, which helps catching server bugs in staging:
+//! an invalid response is replaced by an `Internal` status.
+//!
+//! ```rust
+//! use prost_validate::tower::ValidationLayer;
+//!
+//! let layer = ValidationLayer::new().validate_responses(true);
+//! ```
+
+use crate::codec::invalid_response;
+use crate::{Context, Validator};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A [`Layer`] validating the requests, and optionally the responses,
+/// of the wrapped `tonic` services.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationLayer {
+    ctx: Context,
+    responses: bool,
+}
+
+impl ValidationLayer {
+    /// Creates a new layer validating the requests.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the layer validating the messages in the given context.
+    pub fn with_context(self, ctx: Context) -> Self {
+        Self { ctx, ..self }
+    }
+
+    /// Returns the layer validating the responses too if `enabled`.
+    pub fn validate_responses(self, enabled: bool) -> Self {
+        Self {
+            responses: enabled,
+            ..self
+        }
+    }
+}
+
+impl<S> Layer<S> for ValidationLayer {
+    type Service = ValidationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidationService {
+            inner,
+            ctx: self.ctx.clone(),
+            responses: self.responses,
+        }
+    }
+}
+
+/// The service created by the [`ValidationLayer`].
+#[derive(Debug, Clone)]
+pub struct ValidationService<S> {
+    inner: S,
+    ctx: Context,
+    responses: bool,
+}
+
+impl<S, T, R> Service<tonic::Request<T>> for ValidationService<S>
+where
+    S: Service<tonic::Request<T>, Response = tonic::Response<R>, Error = tonic::Status>,
+    S::Future: Send + 'static,
+    T: Validator,
+    R: Validator + 'static,
+{
+    type Response = tonic::Response<R>;
+    type Error = tonic::Status;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<tonic::Response<R>, tonic::Status>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: tonic::Request<T>) -> Self::Future {
+        if let Err(errs) = req.get_ref().validate_all_with(&self.ctx) {
+            return Box::pin(std::future::ready(Err(errs.into())));
+        }
+        let fut = self.inner.call(req);
+        let ctx = self.responses.then(|| self.ctx.clone());
+        Box::pin(async move {
+            let res = fut.await?;
+            if let Some(ctx) = ctx {
+                if let Err(errs) = res.get_ref().validate_all_with(&ctx) {
+                    return Err(invalid_response(errs));
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::string;
+    use crate::Error;
+    use std::future::Ready;
+    use tonic_types::StatusExt;
+
+    struct Msg(&'static str);
+
+    impl Validator for Msg {
+        fn validate(&self) -> crate::Result {
+            if self.0.is_empty() {
+                return Err(Error::new("val", string::Error::MinLen(1)));
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Echo(&'static str);
+
+    impl Service<tonic::Request<Msg>> for Echo {
+        type Response = tonic::Response<Msg>;
+        type Error = tonic::Status;
+        type Future = Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: tonic::Request<Msg>) -> Self::Future {
+            std::future::ready(Ok(tonic::Response::new(Msg(self.0))))
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validate_requests() {
+        let mut svc = ValidationLayer::new().layer(Echo(""));
+        assert!(svc.call(tonic::Request::new(Msg("ok"))).await.is_ok());
+
+        let status = svc.call(tonic::Request::new(Msg(""))).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let details = status.get_error_details();
+        let f = &details.bad_request().unwrap().field_violations;
+        assert_eq!(f.len(), 1);
+        assert_eq!(f[0].field, "val");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::unwrap_used)]
+    async fn test_validate_responses() {
+        let mut svc = ValidationLayer::new()
+            .validate_responses(true)
+            .layer(Echo(""));
+        let status = svc
+            .call(tonic::Request::new(Msg("ok")))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::Internal);
+        assert!(status.get_error_details().bad_request().is_some());
+
+        let mut svc = ValidationLayer::new()
+            .validate_responses(true)
+            .layer(Echo("ok"));
+        assert!(svc.call(tonic::Request::new(Msg("ok"))).await.is_ok());
+    }
+}
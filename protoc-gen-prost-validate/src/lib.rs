@@ -0,0 +1,339 @@
+//! A `protoc` plugin generating the `prost_validate::Validator` implementations of the messages,
+//! e.g. with `buf generate`, along with the code generated by
+//! [`protoc-gen-prost`](https://crates.io/crates/protoc-gen-prost).
+//!
+//! The implementations of each package are written to a `<package>.validate.rs` file,
+//! next to the `<package>.rs` file of `protoc-gen-prost`, as with
+//! [`Builder::impl_files`](prost_validate_build::Builder::impl_files).
+//!
+//! The options changing the Rust types of the fields must be the same as those of `protoc-gen-prost`:
+//! `btree_map`, `bytes`, `boxed`, `extern_path`, `compile_well_known_types` and `default_package_filename`.
+//! Its other options are accepted and ignored, so that both plugins can share their options.
+//!
+//! The plugin runs [`run`] on the request read from stdin, and [`generate`] runs the same generation
+//! on the files of a [`DescriptorPool`].
+
+use bytes::Bytes;
+use prost::Message;
+use prost_reflect::DescriptorPool;
+use prost_types::compiler::code_generator_response::{Feature, File};
+use prost_types::compiler::CodeGeneratorResponse;
+use prost_validate_build::{Builder, Severity};
+use std::io::{self, Write};
+
+/// The options of `protoc-gen-prost` not changing the types of the fields.
+const IGNORED: &[&str] = &[
+    "disable_comments",
+    "enable_type_names",
+    "enum_attribute",
+    "field_attribute",
+    "file_descriptor_set",
+    "message_attribute",
+    "no_include",
+    "retain_enum_prefix",
+    "skip_debug",
+    "type_attribute",
+    "type_name_domain",
+];
+
+/// The fields of the `CodeGeneratorRequest` used by the plugin.
+///
+/// The files are kept encoded, so that their options are decoded with the validation rules extensions.
+#[derive(Clone, PartialEq, Message)]
+struct Request {
+    #[prost(string, repeated, tag = "1")]
+    file_to_generate: Vec<String>,
+    #[prost(string, optional, tag = "2")]
+    parameter: Option<String>,
+    #[prost(bytes = "bytes", repeated, tag = "15")]
+    proto_file: Vec<Bytes>,
+}
+
+/// Runs the plugin on the encoded `CodeGeneratorRequest` sent by `protoc`,
+/// writing the warnings of the rules to `warnings`.
+///
+/// The generation errors are reported in the [`CodeGeneratorResponse::error`].
+pub fn run(request: &[u8], warnings: &mut impl Write) -> io::Result<CodeGeneratorResponse> {
+    let request =
+        Request::decode(request).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let parameter = request.parameter.unwrap_or_default();
+    let mut pool = DescriptorPool::new();
+    let files = request
+        .proto_file
+        .into_iter()
+        .try_for_each(|file| pool.decode_file_descriptor_proto(file))
+        .map_err(|e| e.to_string())
+        .and_then(|()| generate(&pool, &request.file_to_generate, &parameter, warnings));
+    Ok(match files {
+        Ok(file) => CodeGeneratorResponse {
+            supported_features: Some(Feature::Proto3Optional as u64),
+            file,
+            ..CodeGeneratorResponse::default()
+        },
+        Err(error) => CodeGeneratorResponse {
+            error: Some(error),
+            ..CodeGeneratorResponse::default()
+        },
+    })
+}
+
+/// Generates the implementations of the `files` of the pool with the plugin options of `parameter`,
+/// e.g. `bytes=.`, writing the warnings of the rules to `warnings`.
+///
+/// Returns the error reported to `protoc`.
+pub fn generate(
+    pool: &DescriptorPool,
+    files: &[String],
+    parameter: &str,
+    warnings: &mut impl Write,
+) -> Result<Vec<File>, String> {
+    let options = Options::parse(parameter)?;
+    lint(pool, files, warnings)?;
+
+    let mut config = prost_build::Config::new();
+    options.configure(&mut config);
+    let modules = Builder::new()
+        .generate_impls(config, pool, files)
+        .map_err(|e| e.to_string())?;
+    let mut files = modules
+        .into_iter()
+        .map(|(module, content)| {
+            let name = module.to_file_name_or(&options.default_package_filename);
+            File {
+                name: Some(format!(
+                    "{}.validate.rs",
+                    name.strip_suffix(".rs").unwrap_or(&name)
+                )),
+                content: Some(content),
+                ..File::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Lints the rules of the files to generate, writing the warnings to `warnings`
+/// and returning the errors.
+fn lint(pool: &DescriptorPool, files: &[String], warnings: &mut impl Write) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for diagnostic in prost_validate_build::lint(pool) {
+        // the rules of a message are reported with the name of the message
+        let generated = pool
+            .get_message_by_name(&diagnostic.field)
+            .or_else(|| {
+                let (message, _) = diagnostic.field.rsplit_once('.')?;
+                pool.get_message_by_name(message)
+            })
+            .is_some_and(|message| files.iter().any(|f| f == message.parent_file().name()));
+        if !generated {
+            continue;
+        }
+        match diagnostic.severity {
+            Severity::Error => errors.push(diagnostic.to_string()),
+            Severity::Warning => {
+                writeln!(warnings, "{}", diagnostic).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// The options of the plugin, as `protoc-gen-prost` ones.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    btree_map: Vec<String>,
+    bytes: Vec<String>,
+    boxed: Vec<String>,
+    extern_path: Vec<(String, String)>,
+    compile_well_known_types: bool,
+    default_package_filename: String,
+}
+
+impl Options {
+    /// Parses the comma separated options, e.g. `bytes=.,extern_path=.google.protobuf=::pbjson_types`.
+    fn parse(parameter: &str) -> Result<Self, String> {
+        let mut options = Self {
+            default_package_filename: "_".to_string(),
+            ..Self::default()
+        };
+        for option in parameter.split(',').filter(|o| !o.is_empty()) {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            match name {
+                "btree_map" => options.btree_map.push(value.to_string()),
+                "bytes" => options.bytes.push(value.to_string()),
+                "boxed" => options.boxed.push(value.to_string()),
+                "extern_path" => {
+                    let (proto, rust) = value
+                        .split_once('=')
+                        .ok_or_else(|| format!("invalid extern_path option: {}", value))?;
+                    options
+                        .extern_path
+                        .push((proto.to_string(), rust.to_string()));
+                }
+                "compile_well_known_types" => options.compile_well_known_types = true,
+                "default_package_filename" => options.default_package_filename = value.to_string(),
+                name if IGNORED.contains(&name) => {}
+                name => return Err(format!("unknown option: {}", name)),
+            }
+        }
+        Ok(options)
+    }
+
+    fn configure(&self, config: &mut prost_build::Config) {
+        config
+            .btree_map(&self.btree_map)
+            .bytes(&self.bytes)
+            .default_package_filename(&self.default_package_filename);
+        for path in &self.boxed {
+            config.boxed(path);
+        }
+        for (proto, rust) in &self.extern_path {
+            config.extern_path(proto, rust);
+        }
+        if self.compile_well_known_types {
+            config.compile_well_known_types();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use prost_reflect::DynamicMessage;
+    use prost_validate_types::DESCRIPTOR_POOL;
+
+    /// Returns the request generating the `test.proto` file of the `test` package with the `Message` message
+    /// and the `root.proto` file without package with the `Root` message, whose `val` field has the given rules.
+    fn request(parameter: &str, rules: &serde_json::Value) -> Vec<u8> {
+        let desc = DESCRIPTOR_POOL
+            .get_message_by_name("google.protobuf.FileDescriptorProto")
+            .unwrap();
+        let file = |name: &str, package: Option<&str>, message: &str| {
+            let file = serde_json::json!({
+                "name": name,
+                "package": package,
+                "dependency": ["validate/validate.proto"],
+                "syntax": "proto3",
+                "messageType": [{
+                    "name": message,
+                    "field": [{
+                        "name": "val",
+                        "number": 1,
+                        "label": "LABEL_OPTIONAL",
+                        "type": "TYPE_INT32",
+                        "options": {"[validate.rules]": rules},
+                    }],
+                }],
+            });
+            let file = DynamicMessage::deserialize(desc.clone(), file).unwrap();
+            Bytes::from(file.encode_to_vec())
+        };
+        let mut proto_file = DESCRIPTOR_POOL
+            .files()
+            .map(|file| Bytes::from(file.encode_to_vec()))
+            .collect::<Vec<_>>();
+        proto_file.push(file("test.proto", Some("test"), "Message"));
+        proto_file.push(file("root.proto", None, "Root"));
+        Request {
+            file_to_generate: vec!["test.proto".to_string(), "root.proto".to_string()],
+            parameter: Some(parameter.to_string()),
+            proto_file,
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_run() {
+        let rules = serde_json::json!({"int32": {"gt": 0}});
+        let mut warnings = Vec::new();
+        let response = run(&request("", &rules), &mut warnings).unwrap();
+        assert_eq!(response.error, None);
+        assert!(warnings.is_empty());
+        let names = response
+            .file
+            .iter()
+            .map(|file| file.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["_.validate.rs", "test.validate.rs"]);
+        for (file, message) in response.file.iter().zip(["Root", "Message"]) {
+            assert!(
+                file.content().contains(&format!(
+                    "impl ::prost_validate::Validator for {} {{",
+                    message
+                )),
+                "{}",
+                file.content()
+            );
+        }
+
+        let response = run(
+            &request("default_package_filename=root", &rules),
+            &mut warnings,
+        )
+        .unwrap();
+        let names = response
+            .file
+            .iter()
+            .map(|file| file.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["root.validate.rs", "test.validate.rs"]);
+    }
+
+    #[test]
+    fn test_run_errors() {
+        let mut warnings = Vec::new();
+        let rules = serde_json::json!({"int32": {"gte": 1, "lte": 1}});
+        let response = run(&request("", &rules), &mut warnings).unwrap();
+        assert!(response.file.is_empty());
+        let error = response.error.unwrap();
+        assert!(error.contains("test.Message.val"), "{}", error);
+        assert!(error.contains("use int32.const instead"), "{}", error);
+
+        let rules = serde_json::json!({"int32": {"gt": 0}});
+        let response = run(&request("unknown", &rules), &mut warnings).unwrap();
+        assert_eq!(response.error.as_deref(), Some("unknown option: unknown"));
+        assert!(warnings.is_empty());
+
+        assert!(run(b"invalid", &mut warnings).is_err());
+    }
+
+    #[test]
+    fn test_run_warnings() {
+        let mut warnings = Vec::new();
+        let rules = serde_json::json!({"int32": {"gt": 1, "lt": 1}});
+        let response = run(&request("", &rules), &mut warnings).unwrap();
+        assert_eq!(response.error, None);
+        assert_eq!(response.file.len(), 2);
+        let warnings = String::from_utf8(warnings).unwrap();
+        assert_eq!(warnings.lines().count(), 2, "{}", warnings);
+        assert!(
+            warnings.contains("test.Message.val: int32.gt equals int32.lt (1)"),
+            "{}",
+            warnings
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options =
+            Options::parse("bytes=.,extern_path=.google.protobuf=::pbjson_types,skip_debug=.a")
+                .unwrap();
+        assert_eq!(
+            options,
+            Options {
+                bytes: vec![".".to_string()],
+                extern_path: vec![(".google.protobuf".to_string(), "::pbjson_types".to_string())],
+                default_package_filename: "_".to_string(),
+                ..Options::default()
+            }
+        );
+        assert!(Options::parse("unknown").is_err());
+        assert!(Options::parse("extern_path=.a").is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! The `protoc-gen-prost-validate` plugin, see [`protoc_gen_prost_validate`].
+
+use prost::Message;
+use std::io::{self, Read, Write};
+
+fn main() -> io::Result<()> {
+    let mut buf = Vec::new();
+    io::stdin().read_to_end(&mut buf)?;
+    let response = protoc_gen_prost_validate::run(&buf, &mut io::stderr())?;
+    io::stdout().write_all(&response.encode_to_vec())
+}